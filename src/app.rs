@@ -1,6 +1,9 @@
 use std::collections::VecDeque;
 use serde_derive::{Serialize, Deserialize};
-use crate::{snippet::CodeSnippet, StatefulList};
+use crate::{snippet::CodeSnippet, duplicates::group_duplicates, runner::RunOutput, search::SavedSearch, similarity::SimilarityIndex, textarea::TextArea, undo::UndoHistory, vim::Vim, StatefulList};
+
+/// How many submitted searches are remembered
+pub const SEARCH_HISTORY_LEN: usize = 100;
 
 
 #[derive(Clone, Copy, PartialEq)]
@@ -11,18 +14,57 @@ pub enum NewSnippetMode {
 }
 
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum SavedSearchMode {
+    /// Moving through the saved searches in the sidebar
+    Browse,
+    /// Typing the name of a new saved search (None) or renaming an existing one
+    Name(Option<usize>),
+    /// Editing the query of an existing saved search
+    Query(usize),
+}
+
+
+#[derive(Clone, PartialEq, Default)]
 pub enum InputMode {
     Normal,
+    #[default]
     Search,
     NewSnippet(NewSnippetMode),
     ConfirmDelete(usize),
+    SavedSearches(SavedSearchMode),
+    /// Reverse search through the search history, holds which of the matches is shown
+    HistorySearch(usize),
+    TagManager(TagManagerMode),
+    /// Moving through the snippets related to the selected one, holds the selected related snippet
+    Related(usize),
+    /// The snippet that is about to be saved has the same code as the snippet with this idx
+    ConfirmDuplicate(usize),
+    Duplicates(DuplicatesMode),
+    /// Asking whether the snippet with this idx should really be run
+    ConfirmRun(usize),
+    /// The output pane of the last run snippet
+    RunOutput,
 }
 
-impl Default for InputMode {
-    fn default() -> Self {
-        InputMode::Search
-    }
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TagManagerMode {
+    /// Moving through the list of tags
+    Browse,
+    /// Typing a new name for the tag at this list idx
+    Rename(usize),
+    /// Picking the tag that the tag at this list idx gets merged into
+    Merge(usize),
+}
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DuplicatesMode {
+    /// Moving through the groups of suspected duplicates
+    Browse,
+    /// Typing the name the merged snippet of the group at this list idx gets
+    MergeName(usize),
 }
 
 
@@ -47,6 +89,59 @@ pub struct App {
     // Currently edited snippet
     #[serde(skip_serializing, skip_deserializing)]
     pub current_snippet: Option<CodeSnippet>,
+
+    /// Editor of the code of `current_snippet`, the code is copied into the snippet after every change
+    #[serde(skip_serializing, skip_deserializing)]
+    pub code_editor: TextArea,
+
+    /// Undo history of the name field of `current_snippet`, the code editor has its own
+    #[serde(skip_serializing, skip_deserializing)]
+    pub name_history: UndoHistory<String>,
+
+    /// Undo history of the tags field (`input` while a snippet is edited)
+    #[serde(skip_serializing, skip_deserializing)]
+    pub tags_history: UndoHistory<String>,
+
+    /// State of the vim keymap, None if the default keymap is used
+    #[serde(skip_serializing, skip_deserializing)]
+    pub vim: Option<Vim>,
+
+    /// Searches the user saved under a name, shown as collections in the sidebar
+    #[serde(default)]
+    pub saved_searches: StatefulList<SavedSearch>,
+
+    /// Submitted searches, most recent first
+    #[serde(default)]
+    pub search_history: VecDeque<String>,
+
+    /// Every tag that was ever saved, so tags stay known (and can be cleaned up)
+    /// after the last snippet using them is gone
+    #[serde(default)]
+    pub known_tags: Vec<String>,
+
+    /// Tags listed in the tag manager
+    #[serde(skip_serializing, skip_deserializing)]
+    pub tag_list: StatefulList<String>,
+
+    /// Groups of snippet idxs with the same code, listed in the duplicates view
+    #[serde(skip_serializing, skip_deserializing)]
+    pub duplicate_groups: StatefulList<Vec<usize>>,
+
+    /// Text typed into small popups (e.g. the name of a saved search)
+    #[serde(skip_serializing, skip_deserializing)]
+    pub prompt: String,
+
+    /// Shown below the shortcuts until the next key press, e.g. errors of the external editor
+    #[serde(skip_serializing, skip_deserializing)]
+    pub status_message: String,
+
+    /// Output of the snippet that was run last
+    #[serde(skip_serializing, skip_deserializing)]
+    pub run_output: Option<RunOutput>,
+
+    /// Used to find related snippets, has to be built with `rebuild_similarity_index` after loading
+    #[serde(skip_serializing, skip_deserializing)]
+    pub similarity: SimilarityIndex,
 }
 
 impl Default for App {
@@ -58,6 +153,19 @@ impl Default for App {
             open_idxs: VecDeque::new(),
             found_snippets: StatefulList::with_items(vec![]),
             current_snippet: None,
+            code_editor: TextArea::default(),
+            name_history: UndoHistory::default(),
+            tags_history: UndoHistory::default(),
+            vim: None,
+            saved_searches: StatefulList::with_items(vec![]),
+            search_history: VecDeque::new(),
+            known_tags: vec![],
+            tag_list: StatefulList::with_items(vec![]),
+            duplicate_groups: StatefulList::with_items(vec![]),
+            prompt: String::new(),
+            status_message: String::new(),
+            run_output: None,
+            similarity: SimilarityIndex::default(),
         };
 
         let mut example_snippet = CodeSnippet::new(app.return_next_idx());
@@ -81,6 +189,7 @@ impl Default for App {
             print(hey bro)".to_string();
             example_snippet3.tags = vec!["example".to_string(), "bro".to_string()];
         app.snippets.push(example_snippet3);
+        app.rebuild_similarity_index();
         
         app
     }
@@ -104,12 +213,218 @@ impl App {
         }
     }
 
+    /// Opens the snippet in the editor popup, its tags go into `input`. Every field starts with an empty undo history
+    pub fn start_editing(&mut self, snip: CodeSnippet) {
+        self.input = snip.tags.join(" ");
+        self.code_editor = TextArea::new(&snip.code);
+        self.name_history = UndoHistory::default();
+        self.tags_history = UndoHistory::default();
+        if let Some(vim) = self.vim.as_mut() {
+            vim.start_editing(&mut self.code_editor);
+        };
+        self.current_snippet = Some(snip);
+    }
+
+    /// Adds a new snippet or replaces the snippet with the same idx
+    pub fn add_snippet(&mut self, snippet: CodeSnippet) {
+        self.register_tags(&snippet.tags);
+        self.similarity.update(&snippet);
+        match self.snippets.iter().position(|r| r.idx == snippet.idx) {
+            Some(index) => self.snippets[index] = snippet,
+            None => self.snippets.push(snippet),
+        };
+    }
+
     pub fn remove_snippet(&mut self, snippet_idx: usize) {
         let index = self.snippets.iter().position(|r| r.idx == snippet_idx).unwrap();
-        self.snippets.remove(index);
+        let removed = self.snippets.remove(index);
+        self.register_tags(&removed.tags);
+        self.similarity.remove(snippet_idx);
         self.open_idxs.push_back(snippet_idx);
     }
 
+    /// Merges the snippets with the given idxs into the first of them.
+    /// The merged snippet gets `name` and the tags of all of them, the others are removed
+    pub fn merge_snippets(&mut self, snippet_idxs: &[usize], name: &str) {
+        let Some((keep_idx, others)) = snippet_idxs.split_first() else {
+            return;
+        };
+        let Some(mut merged) = self.get_snippet(*keep_idx).cloned() else {
+            return;
+        };
+        for other_idx in others {
+            if let Some(other) = self.get_snippet(*other_idx).cloned() {
+                for tag in other.tags {
+                    if !merged.tags.contains(&tag) {
+                        merged.tags.push(tag);
+                    };
+                };
+                self.remove_snippet(*other_idx);
+            };
+        };
+        merged.name = name.to_string();
+        self.add_snippet(merged);
+    }
+
+    pub fn get_snippet(&self, snippet_idx: usize) -> Option<&CodeSnippet> {
+        self.snippets.iter().find(|s| s.idx == snippet_idx)
+    }
+
+    /// Re-computes the groups shown in the duplicates view, keeping the selection in bounds
+    pub fn refresh_duplicate_groups(&mut self) {
+        self.duplicate_groups.items = group_duplicates(&self.snippets);
+        match self.duplicate_groups.state.selected() {
+            _ if self.duplicate_groups.items.is_empty() => self.duplicate_groups.unselect(),
+            Some(selected) => self.duplicate_groups.state.select(Some(selected.min(self.duplicate_groups.items.len() - 1))),
+            None => self.duplicate_groups.state.select(Some(0)),
+        };
+    }
+
+    pub fn rebuild_similarity_index(&mut self) {
+        self.similarity = SimilarityIndex::from_snippets(&self.snippets);
+    }
+
+    /// Up to `count` snippets that are similar to the snippet with the given idx, most similar first
+    pub fn related_snippets(&self, snippet_idx: usize, count: usize) -> Vec<&CodeSnippet> {
+        self.similarity
+            .related(snippet_idx, count)
+            .into_iter()
+            .filter_map(|(idx, _)| self.snippets.iter().find(|s| s.idx == idx))
+            .collect()
+    }
+
+    /// Remembers the given tags in `known_tags`
+    pub fn register_tags(&mut self, tags: &[String]) {
+        for tag in tags {
+            if !tag.is_empty() && !self.known_tags.contains(tag) {
+                self.known_tags.push(tag.clone());
+            };
+        };
+    }
+
+    /// Every known tag together with the number of snippets using it, most used first
+    pub fn tag_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: Vec<(String, usize)> = self.known_tags.iter().map(|t| (t.clone(), 0)).collect();
+        for snip in self.snippets.iter() {
+            for tag in snip.tags.iter().filter(|t| !t.is_empty()) {
+                match counts.iter_mut().find(|(t, _)| t == tag) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((tag.clone(), 1)),
+                };
+            };
+        };
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.to_lowercase().cmp(&b.0.to_lowercase())));
+        counts
+    }
+
+    /// Suggests an existing tag starting with `prefix` (non-case-sensitive), preferring the most used one
+    pub fn complete_tag(&self, prefix: &str) -> Option<String> {
+        if prefix.is_empty() {
+            return None;
+        };
+        let prefix_lower = prefix.to_lowercase();
+        self.tag_counts()
+            .into_iter()
+            .map(|(tag, _)| tag)
+            .find(|tag| tag.to_lowercase().starts_with(&prefix_lower) && tag != prefix)
+    }
+
+    /// Renames `old` to `new` on every snippet. If a snippet already has `new`
+    /// the two tags get merged. Returns how many snippets were changed
+    pub fn rename_tag(&mut self, old: &str, new: &str) -> usize {
+        let mut changed = 0;
+        for snip in self.snippets.iter_mut() {
+            if !snip.tags.iter().any(|t| t == old) {
+                continue;
+            };
+            let mut new_tags: Vec<String> = vec![];
+            for tag in snip.tags.iter() {
+                let tag = if tag == old {new} else {tag.as_str()};
+                if !new_tags.iter().any(|t| t == tag) {
+                    new_tags.push(tag.to_string());
+                };
+            };
+            snip.tags = new_tags;
+            self.similarity.update(snip);
+            changed += 1;
+        };
+        self.known_tags.retain(|t| t != old);
+        self.register_tags(&[new.to_string()]);
+        changed
+    }
+
+    /// Forgets every known tag that no snippet uses anymore, returns how many were removed
+    pub fn remove_unused_tags(&mut self) -> usize {
+        let before = self.known_tags.len();
+        let snippets = &self.snippets;
+        self.known_tags.retain(|tag| snippets.iter().any(|s| s.tags.contains(tag)));
+        before - self.known_tags.len()
+    }
+
+    /// Forgets `tag` if no snippet uses it, returns whether it was removed
+    pub fn remove_tag_if_unused(&mut self, tag: &str) -> bool {
+        if self.snippets.iter().any(|s| s.tags.iter().any(|t| t == tag)) {
+            return false;
+        };
+        self.known_tags.retain(|t| t != tag);
+        true
+    }
+
+    /// Re-reads the tags shown in the tag manager, keeping the selection in bounds
+    pub fn refresh_tag_list(&mut self) {
+        self.tag_list.items = self.tag_counts().into_iter().map(|(tag, _)| tag).collect();
+        match self.tag_list.state.selected() {
+            _ if self.tag_list.items.is_empty() => self.tag_list.unselect(),
+            Some(selected) => self.tag_list.state.select(Some(selected.min(self.tag_list.items.len() - 1))),
+            None => self.tag_list.state.select(Some(0)),
+        };
+    }
+
+    /// Saves `query` under `name`, unless a saved search has the name already. Returns whether it was saved
+    pub fn save_search(&mut self, name: &str, query: &str) -> bool {
+        let taken = self.saved_searches.items.iter().any(|s| s.name == name);
+        if !taken {
+            self.saved_searches.items.push(SavedSearch::new(name, query));
+        };
+        !taken
+    }
+
+    /// Renames the saved search, unless another saved search has the name already. Returns whether it was renamed
+    pub fn rename_saved_search(&mut self, list_idx: usize, name: &str) -> bool {
+        let taken = self.saved_searches.items.iter().enumerate().any(|(i, s)| i != list_idx && s.name == name);
+        if !taken {
+            self.saved_searches.items[list_idx].name = name.to_string();
+        };
+        !taken
+    }
+
+    pub fn remove_saved_search(&mut self, list_idx: usize) {
+        if list_idx < self.saved_searches.items.len() {
+            self.saved_searches.items.remove(list_idx);
+        };
+        if self.saved_searches.items.is_empty() {
+            self.saved_searches.unselect();
+        } else if let Some(selected) = self.saved_searches.state.selected() {
+            self.saved_searches.state.select(Some(selected.min(self.saved_searches.items.len() - 1)));
+        };
+    }
+
+    /// Puts `query` at the front of the search history, removing older copies of it
+    pub fn add_to_search_history(&mut self, query: &str) {
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        };
+        self.search_history.retain(|q| q != query);
+        self.search_history.push_front(query.to_string());
+        self.search_history.truncate(SEARCH_HISTORY_LEN);
+    }
+
+    /// All entries of the search history containing `query`, most recent first
+    pub fn search_history_matches(&self, query: &str) -> Vec<&String> {
+        self.search_history.iter().filter(|q| q.contains(query)).collect()
+    }
+
     pub fn has_snippet_with_idx(&self, snippet_idx: usize) -> bool {
         for snip in self.snippets.iter() {
             if snip.idx == snippet_idx {
@@ -0,0 +1,260 @@
+use std::io::{self, Read, Seek, Write};
+
+use clap::ValueEnum;
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::{app::App, snippet::CodeSnippet};
+
+/// Format name in the manifest, so other zip files aren't taken for bundles
+const FORMAT_NAME: &str = "snippy-bundle";
+/// Version of the bundle format. Has to be increased whenever the manifest or the snippet files change
+/// in a way older versions of snippy can't read
+pub const BUNDLE_VERSION: u32 = 1;
+
+const MANIFEST_PATH: &str = "manifest.json";
+const SNIPPETS_DIR: &str = "snippets";
+
+
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    /// Version of snippy that created the bundle
+    pub snippy_version: String,
+    pub snippets: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Path of the snippet file in the archive
+    pub path: String,
+    pub name: String,
+    /// SHA-256 of the snippet file, hex encoded
+    pub sha256: String,
+}
+
+/// What to do with a bundled snippet named like an existing snippet of the same language
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Resolution {
+    /// Leave the existing snippet alone
+    Skip,
+    /// Add the bundled snippet under another name
+    Rename,
+    /// Replace the existing snippet with the bundled one
+    Overwrite,
+    /// Add the bundled snippet with the same name
+    KeepBoth,
+}
+
+/// A snippet of a bundle and how it relates to the saved snippets
+pub enum Incoming {
+    New(CodeSnippet),
+    /// Same name, language and code as the saved snippet with this idx
+    Identical(CodeSnippet, usize),
+    /// Same name and language as the saved snippet with this idx, but other code
+    Conflict(CodeSnippet, usize),
+    /// Same name and language as an earlier snippet of the bundle, it isn't imported
+    Duplicate(CodeSnippet),
+}
+
+
+/// Writes the snippets as a zip archive holding a manifest and one JSON file per snippet
+pub fn create(snippets: &[&CodeSnippet], writer: impl Write + Seek) -> io::Result<()> {
+    let mut zip = ZipWriter::new(writer);
+    let options = SimpleFileOptions::default();
+    let mut entries = vec![];
+    for (i, snip) in snippets.iter().enumerate() {
+        let path = format!("{}/{:04}.json", SNIPPETS_DIR, i + 1);
+        let contents = serde_json::to_vec_pretty(snip)?;
+        zip.start_file(path.as_str(), options)?;
+        zip.write_all(&contents)?;
+        entries.push(ManifestEntry { path, name: snip.name.clone(), sha256: sha256_hex(&contents) });
+    };
+    let manifest = Manifest {
+        format: FORMAT_NAME.to_string(),
+        version: BUNDLE_VERSION,
+        snippy_version: env!("CARGO_PKG_VERSION").to_string(),
+        snippets: entries,
+    };
+    zip.start_file(MANIFEST_PATH, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+    zip.finish()?;
+    Ok(())
+}
+
+/// Reads a bundle and checks the checksum of every snippet file, returns the manifest and the snippets in its order
+pub fn read(reader: impl Read + Seek) -> Result<(Manifest, Vec<CodeSnippet>), String> {
+    let mut zip = ZipArchive::new(reader).map_err(|e| format!("not a snippy bundle: {}", e))?;
+    let manifest: Manifest = serde_json::from_slice(&read_file(&mut zip, MANIFEST_PATH)?)
+        .map_err(|e| format!("broken manifest: {}", e))?;
+    if manifest.format != FORMAT_NAME {
+        return Err(format!("not a snippy bundle, the manifest is for '{}'", manifest.format));
+    };
+    if manifest.version > BUNDLE_VERSION {
+        return Err(format!(
+            "the bundle was made by snippy {} (bundle version {}), this snippy reads up to version {}",
+            manifest.snippy_version, manifest.version, BUNDLE_VERSION,
+        ));
+    };
+
+    let mut snippets = vec![];
+    for entry in &manifest.snippets {
+        let contents = read_file(&mut zip, &entry.path)?;
+        if sha256_hex(&contents) != entry.sha256.to_lowercase() {
+            return Err(format!("the checksum of {} ('{}') doesn't match, the bundle is damaged", entry.path, entry.name));
+        };
+        let snip: CodeSnippet = serde_json::from_slice(&contents).map_err(|e| format!("{}: {}", entry.path, e))?;
+        snippets.push(snip);
+    };
+    Ok((manifest, snippets))
+}
+
+/// Sorts the bundled snippets into new ones, ones that are saved already, conflicting ones and ones that come twice
+pub fn compare(app: &App, snippets: Vec<CodeSnippet>) -> Vec<Incoming> {
+    let mut seen: Vec<(String, Option<String>)> = vec![];
+    snippets
+        .into_iter()
+        .map(|snip| {
+            let key = (snip.name.clone(), snip.language.clone());
+            if seen.contains(&key) {
+                return Incoming::Duplicate(snip);
+            };
+            seen.push(key);
+            match app.snippets.iter().find(|s| s.name == snip.name && s.language == snip.language) {
+                None => Incoming::New(snip),
+                Some(existing) if existing.code == snip.code => {
+                    let idx = existing.idx;
+                    Incoming::Identical(snip, idx)
+                },
+                Some(existing) => {
+                    let idx = existing.idx;
+                    Incoming::Conflict(snip, idx)
+                },
+            }
+        })
+        .collect()
+}
+
+/// Adds a bundled snippet as a new snippet, returns its idx
+pub fn add(app: &mut App, mut snip: CodeSnippet) -> usize {
+    snip.idx = app.return_next_idx();
+    let idx = snip.idx;
+    app.add_snippet(snip);
+    idx
+}
+
+/// Replaces the saved snippet with the bundled one, which keeps the idx of the saved snippet
+pub fn overwrite(app: &mut App, mut snip: CodeSnippet, existing_idx: usize) {
+    snip.idx = existing_idx;
+    app.add_snippet(snip);
+}
+
+/// The name with the lowest ` (n)` suffix no saved snippet has
+pub fn unused_name(app: &App, name: &str) -> String {
+    let mut counter = 2;
+    loop {
+        let candidate = format!("{} ({})", name, counter);
+        if !app.snippets.iter().any(|s| s.name == candidate) {
+            return candidate;
+        };
+        counter += 1;
+    }
+}
+
+
+fn read_file(zip: &mut ZipArchive<impl Read + Seek>, path: &str) -> Result<Vec<u8>, String> {
+    let mut file = zip.by_name(path).map_err(|_| format!("{} is missing from the bundle", path))?;
+    let mut contents = vec![];
+    file.read_to_end(&mut contents).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(contents)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn snippet(name: &str, language: &str, code: &str) -> CodeSnippet {
+        let mut snip = CodeSnippet::new(0);
+        snip.name = name.to_string();
+        snip.language = Some(language.to_string());
+        snip.code = code.to_string();
+        snip
+    }
+
+    /// A bundle with the manifest and the files as given
+    fn zip_with(manifest: &Manifest, files: &[(&str, &[u8])]) -> Cursor<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        for (path, contents) in files {
+            zip.start_file(*path, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents).unwrap();
+        };
+        zip.start_file(MANIFEST_PATH, SimpleFileOptions::default()).unwrap();
+        zip.write_all(&serde_json::to_vec(manifest).unwrap()).unwrap();
+        let mut cursor = zip.finish().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    fn manifest(version: u32, entries: Vec<ManifestEntry>) -> Manifest {
+        Manifest { format: FORMAT_NAME.to_string(), version, snippy_version: String::from("9.9.9"), snippets: entries }
+    }
+
+    #[test]
+    fn round_trip() {
+        let snippets = vec![snippet("ls", "bash", "ls -la"), snippet("hello", "python", "print('hello')\n")];
+        let mut cursor = Cursor::new(vec![]);
+        create(&snippets.iter().collect::<Vec<_>>(), &mut cursor).unwrap();
+        cursor.set_position(0);
+        let (manifest, read_snippets) = read(cursor).unwrap();
+        assert_eq!(manifest.version, BUNDLE_VERSION);
+        assert_eq!(manifest.snippets.len(), 2);
+        assert_eq!(read_snippets, snippets);
+    }
+
+    #[test]
+    fn damaged_entry_is_rejected() {
+        let contents = serde_json::to_vec_pretty(&snippet("ls", "bash", "ls -la")).unwrap();
+        let entry = ManifestEntry { path: String::from("snippets/0001.json"), name: String::from("ls"), sha256: sha256_hex(b"other") };
+        let error = read(zip_with(&manifest(BUNDLE_VERSION, vec![entry]), &[("snippets/0001.json", &contents)])).err().unwrap();
+        assert!(error.contains("the bundle is damaged"), "{}", error);
+    }
+
+    #[test]
+    fn newer_bundle_version_is_rejected() {
+        let error = read(zip_with(&manifest(BUNDLE_VERSION + 1, vec![]), &[])).err().unwrap();
+        assert!(error.contains("snippy 9.9.9"), "{}", error);
+    }
+
+    #[test]
+    fn compare_sorts_the_snippets() {
+        let mut app = App::default();
+        app.snippets.clear();
+        let mut saved = snippet("ls", "bash", "ls -la");
+        saved.idx = 4;
+        app.snippets.push(saved);
+        let mut changed = snippet("ls", "bash", "ls -l");
+        changed.idx = 5;
+        changed.language = Some(String::from("zsh"));
+        app.snippets.push(changed);
+
+        let incoming = compare(&app, vec![
+            snippet("ls", "bash", "ls -la"),
+            snippet("ls", "zsh", "ls"),
+            snippet("cd", "bash", "cd -"),
+            snippet("cd", "bash", "cd ~"),
+        ]);
+        assert!(matches!(incoming[0], Incoming::Identical(_, 4)));
+        assert!(matches!(incoming[1], Incoming::Conflict(_, 5)));
+        assert!(matches!(incoming[2], Incoming::New(_)));
+        assert!(matches!(&incoming[3], Incoming::Duplicate(snip) if snip.code == "cd ~"));
+    }
+}
@@ -0,0 +1,1029 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::Path;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clipboard::{ClipboardContext, ClipboardProvider};
+
+use crate::{
+    app::App,
+    bundle::{self, Incoming, Resolution},
+    editor::edit_in_external_editor,
+    interop::{
+        add_imported, collect_files, html, jetbrains, markdown, masscode, navi, pet, sublime, vim, vscode, write_dry_run,
+        write_report, yasnippet, Imported,
+    },
+    language::{detect_language, normalize_language},
+    output::{write_snippets, OutputFormat},
+    picker::{pick_snippets, review_snippets},
+    render::{render_png, render_svg, ImageFormat, RenderOptions},
+    runner::{interpreter_for, missing_interpreter_message, run_snippet},
+    shell::{init_script, Shell},
+    search::search_snippets,
+    snippet::CodeSnippet,
+    storage::save_app_state,
+    SnippyConfig,
+};
+
+/// Longest name that is derived from the first line of the code
+const MAX_INFERRED_NAME_LEN: usize = 50;
+
+
+const EXIT_CODES: &str = "Exit codes:
+  0  success
+  1  an error occurred, or a search found nothing
+  2  invalid arguments
+  3  no snippet with the given id exists
+  130  the picker was cancelled
+`snippy run` exits with the exit code of the snippet instead";
+
+
+/// Snippy is a command line tool for managing code snippets.
+/// Run it without a command to open the interactive interface.
+#[derive(Parser)]
+#[command(name = "snippy", version, about, after_help = EXIT_CODES)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Shared by every command that prints snippets
+#[derive(Args)]
+pub struct FormatArgs {
+    /// Output format, the fields of json, jsonl and tsv are described in docs/output-format.md
+    #[arg(long, value_enum, default_value_t = OutputFormat::Plain)]
+    pub format: OutputFormat,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// List all snippets
+    List {
+        #[command(flatten)]
+        format: FormatArgs,
+    },
+    /// Search snippets by name and tags, terms starting with `-` exclude snippets
+    Search {
+        /// Search terms, all of them have to match. Terms after `--` aren't read as options
+        #[arg(required = true, allow_hyphen_values = true)]
+        query: Vec<String>,
+        #[command(flatten)]
+        format: FormatArgs,
+    },
+    /// Print a snippet
+    Show {
+        id: usize,
+        #[command(flatten)]
+        format: FormatArgs,
+    },
+    /// Add a new snippet. Without --code or --from the code is read from stdin
+    Add {
+        /// Defaults to the file and line range, or the first line of the code
+        #[arg(long)]
+        name: Option<String>,
+        /// Comma separated tags
+        #[arg(long, value_delimiter = ',')]
+        tags: Vec<String>,
+        /// Defaults to the language of the file extension or the shebang
+        #[arg(long)]
+        language: Option<String>,
+        #[arg(long, conflicts_with = "from")]
+        code: Option<String>,
+        /// Take the code from a file, optionally only some lines: `path`, `path:120` or `path:120-180`
+        #[arg(long, value_name = "PATH[:START[-END]]")]
+        from: Option<String>,
+    },
+    /// Delete a snippet
+    Rm {
+        id: usize,
+    },
+    /// Add (+tag) or remove (-tag) tags of a snippet
+    Tag {
+        id: usize,
+        /// e.g. `+foo -bar`, tags without a sign are added
+        #[arg(required = true, allow_hyphen_values = true)]
+        changes: Vec<String>,
+    },
+    /// Edit the code of a snippet in $VISUAL or $EDITOR
+    Edit {
+        id: usize,
+    },
+    /// Run a snippet with the interpreter for its language (see the interpreters in the config) or its shebang
+    Run {
+        id: usize,
+        /// Passed on to the snippet
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Import snippets from the files of other tools
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+    /// Export snippets to the files of other tools
+    Export {
+        #[command(subcommand)]
+        target: ExportTarget,
+    },
+    /// Share snippets as a single file with a manifest and checksums
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
+    /// Draw a snippet as a picture of highlighted code in a window, for slides and chat
+    Render {
+        id: usize,
+        #[arg(long, value_enum, default_value_t = ImageFormat::Svg)]
+        format: ImageFormat,
+        /// File to write, stdout without one
+        #[arg(short, long)]
+        output: Option<String>,
+        #[command(flatten)]
+        options: RenderOptions,
+    },
+    /// Copy the code of a snippet to the clipboard
+    Copy {
+        id: usize,
+    },
+    /// Search and select snippets interactively, the code of the selected snippets is printed to stdout
+    Pick {
+        /// Select several snippets with Tab
+        #[arg(long)]
+        multi: bool,
+        /// Initial search terms. Terms after `--` aren't read as options
+        #[arg(allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+    /// Print the shell integration: Ctrl-G picks a snippet and inserts it into the command line,
+    /// Alt-G saves the command line as a new snippet. Use it with `eval "$(snippy init bash)"`
+    Init {
+        shell: Shell,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BundleAction {
+    /// Write the selected snippets into a bundle. Without ids, tags or search terms every snippet is bundled
+    Create {
+        /// Bundle file to write, e.g. `kubernetes.snippy.zip`
+        #[arg(short, long)]
+        output: String,
+        /// Ids of snippets to bundle, separated by commas
+        #[arg(long, value_delimiter = ',')]
+        ids: Vec<usize>,
+        /// Bundle the snippets with any of these tags, separated by commas
+        #[arg(long, value_delimiter = ',')]
+        tags: Vec<String>,
+        /// Bundle the snippets matching these search terms. Options like --tags have to come before them
+        #[arg(allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+    /// Show what a bundle holds and add its snippets. For snippets named like a saved snippet of the same language
+    /// you're asked whether to skip, rename, overwrite or keep both
+    Import {
+        file: String,
+        /// Resolve every conflict like this instead of asking
+        #[arg(long, value_enum)]
+        on_conflict: Option<Resolution>,
+        #[command(flatten)]
+        dry_run: DryRunArgs,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ImportSource {
+    /// A VS Code snippet file (`.code-snippets` or `<language>.json`)
+    Vscode {
+        file: String,
+        /// Language of the snippets without a scope, e.g. `python` for python.json
+        #[arg(long)]
+        language: Option<String>,
+    },
+    /// UltiSnips `.snippets` files, or directories holding them. The filetypes become tags and languages
+    Ultisnips {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// SnipMate `.snippets` files, or directories holding them. The filetypes become tags and languages
+    Snipmate {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// A yasnippet directory with one directory per mode (or a single mode directory).
+    /// Modes become languages, or tags if snippy doesn't know the language
+    Yasnippet {
+        dir: String,
+    },
+    /// `.sublime-snippet` files, or directories holding them
+    Sublime {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// JetBrains Live Templates files (`<templateSet>`), or directories holding them
+    Jetbrains {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Every fenced code block of a Markdown file, named after the nearest heading.
+    /// The blocks are reviewed before they're imported
+    Markdown {
+        file: String,
+        /// Import every block without reviewing them
+        #[arg(long)]
+        all: bool,
+    },
+    /// pet's `snippet.toml`. Descriptions become names, parameters like `<name=default>` become placeholders
+    Pet {
+        file: String,
+        #[command(flatten)]
+        dry_run: DryRunArgs,
+    },
+    /// navi `.cheat` files, or directories holding them. `%` tags become tags, `<variables>` become placeholders
+    Navi {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        #[command(flatten)]
+        dry_run: DryRunArgs,
+    },
+    /// massCode's `db.json`. Folders and tags become tags, every fragment becomes a snippet
+    Masscode {
+        file: String,
+        #[command(flatten)]
+        dry_run: DryRunArgs,
+    },
+}
+
+/// The imports from other snippet managers first show what they would do, then ask
+#[derive(Args)]
+pub struct DryRunArgs {
+    /// Import right after the report without asking
+    #[arg(short, long)]
+    pub yes: bool,
+    /// Only show the report
+    #[arg(long, conflicts_with = "yes")]
+    pub dry_run: bool,
+}
+
+/// Shared by every export
+#[derive(Args)]
+pub struct ExportArgs {
+    /// Write to this file instead of stdout
+    #[arg(long, short)]
+    pub output: Option<String>,
+    /// Only export the snippets matching these search terms. Options like --output have to come before them
+    #[arg(allow_hyphen_values = true)]
+    pub query: Vec<String>,
+}
+
+/// Shared by the exports that write several files
+#[derive(Args)]
+pub struct ExportDirArgs {
+    /// Directory the files are written to, it's created if it doesn't exist
+    pub dir: String,
+    /// Only export the snippets matching these search terms
+    #[arg(allow_hyphen_values = true)]
+    pub query: Vec<String>,
+}
+
+#[derive(Subcommand)]
+pub enum ExportTarget {
+    /// A VS Code `.code-snippets` file
+    Vscode {
+        #[command(flatten)]
+        args: ExportArgs,
+    },
+    /// UltiSnips `.snippets` files, one per filetype
+    Ultisnips {
+        #[command(flatten)]
+        args: ExportDirArgs,
+    },
+    /// SnipMate `.snippets` files, one per filetype
+    Snipmate {
+        #[command(flatten)]
+        args: ExportDirArgs,
+    },
+    /// A yasnippet directory with one directory per mode
+    Yasnippet {
+        #[command(flatten)]
+        args: ExportDirArgs,
+    },
+    /// `.sublime-snippet` files, one per snippet
+    Sublime {
+        #[command(flatten)]
+        args: ExportDirArgs,
+    },
+    /// A JetBrains Live Templates file, save it as `snippy.xml` in the `templates` directory of the IDE's config
+    Jetbrains {
+        #[command(flatten)]
+        args: ExportArgs,
+    },
+    /// A Markdown document with a heading, the metadata and a code block per snippet
+    Markdown {
+        #[command(flatten)]
+        args: ExportArgs,
+    },
+    /// A static site with search and tag facets that works without a server, open index.html
+    Html {
+        #[command(flatten)]
+        args: ExportDirArgs,
+    },
+}
+
+
+#[derive(Debug)]
+pub enum CliError {
+    NotFound(usize),
+    NoMatches,
+    Cancelled,
+    /// A snippet that was run exited with this code (None when killed by a signal)
+    SnippetFailed(Option<i32>),
+    InvalidInput(String),
+    Clipboard(String),
+    Io(io::Error),
+}
+
+impl CliError {
+    /// The exit code of the process, see `EXIT_CODES`
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::NotFound(_) => 3,
+            CliError::InvalidInput(_) => 2,
+            CliError::Cancelled => 130,
+            CliError::SnippetFailed(code) => code.unwrap_or(1),
+            CliError::NoMatches | CliError::Clipboard(_) | CliError::Io(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::NotFound(id) => write!(f, "no snippet with id {}", id),
+            CliError::NoMatches => write!(f, "no snippets found"),
+            CliError::Cancelled => write!(f, "cancelled"),
+            CliError::SnippetFailed(Some(code)) => write!(f, "the snippet exited with {}", code),
+            CliError::SnippetFailed(None) => write!(f, "the snippet was killed"),
+            CliError::InvalidInput(msg) => write!(f, "{}", msg),
+            CliError::Clipboard(msg) => write!(f, "could not use the clipboard: {}", msg),
+            CliError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(err: io::Error) -> Self {
+        CliError::Io(err)
+    }
+}
+
+
+/// Runs a command without the interactive interface, writing its output to `out`.
+/// Changes to the snippets are saved right away
+pub fn run_command(command: Command, app: &mut App, config: &SnippyConfig, out: &mut impl Write) -> Result<(), CliError> {
+    match command {
+        Command::List { format } => {
+            let snippets: Vec<&CodeSnippet> = app.snippets.iter().collect();
+            write_snippets(out, &snippets, format.format)?;
+        },
+        Command::Search { mut query, mut format } => {
+            if let Some(value) = take_option(&mut query, "--format", true)? {
+                format.format = OutputFormat::from_str(&value, false)
+                    .map_err(|_| CliError::InvalidInput(format!("--format {} isn't one of plain, json, jsonl and tsv", value)))?;
+            };
+            let found: Vec<&CodeSnippet> = search_snippets(&app.snippets, &query.join(" "))
+                .into_iter()
+                .map(|(list_idx, _)| &app.snippets[list_idx])
+                .collect();
+            if found.is_empty() {
+                return Err(CliError::NoMatches);
+            };
+            write_snippets(out, &found, format.format)?;
+        },
+        Command::Show { id, format } if format.format != OutputFormat::Plain => {
+            let snip = find_snippet(app, id)?;
+            write_snippets(out, &[snip], format.format)?;
+        },
+        Command::Show { id, .. } => {
+            let snip = find_snippet(app, id)?;
+            writeln!(out, "# {}", snip.name)?;
+            writeln!(out, "# Tags: {}", snip.tags.join(", "))?;
+            if let Some(language) = &snip.language {
+                writeln!(out, "# Language: {}", language)?;
+            };
+            if let Some(source) = &snip.source {
+                writeln!(out, "# Source: {}", source)?;
+            };
+            if let Some(trigger) = &snip.trigger {
+                writeln!(out, "# Trigger: {}", trigger)?;
+            };
+            if let Some(description) = &snip.description {
+                writeln!(out, "# Description: {}", description)?;
+            };
+            writeln!(out, "{}", snip.code)?;
+        },
+        Command::Add { name, tags, language, code, from } => {
+            let (code, path, source) = match (code, from) {
+                (Some(code), _) => (code, None, None),
+                (None, Some(from)) => {
+                    let (path, code, source) = read_line_range(&from)?;
+                    (code, Some(path), Some(source))
+                },
+                (None, None) => (read_stdin()?, None, None),
+            };
+            // A snippet taken from a file is named after the file and the lines, e.g. `lib.rs:120-180`
+            let name_from_source = source.as_ref().map(|source| {
+                let file_name = Path::new(source).file_name().and_then(|n| n.to_str());
+                file_name.unwrap_or(source).to_string()
+            });
+            if code.trim().is_empty() {
+                return Err(CliError::InvalidInput(String::from("the snippet has no code")));
+            };
+            let mut snip = CodeSnippet::new(app.return_next_idx());
+            snip.name = name
+                .or(name_from_source)
+                .or_else(|| name_from_code(&code))
+                .unwrap_or(snip.name);
+            snip.tags = tags.into_iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+            snip.language = match language {
+                Some(language) => Some(normalize_language(&language)),
+                None => detect_language(path.as_deref(), &code),
+            };
+            snip.source = source;
+            snip.code = code;
+            let id = snip.idx;
+            app.add_snippet(snip);
+            save_app_state(app)?;
+            writeln!(out, "{}", id)?;
+        },
+        Command::Rm { id } => {
+            find_snippet(app, id)?;
+            app.remove_snippet(id);
+            save_app_state(app)?;
+        },
+        Command::Tag { id, changes } => {
+            let mut snip = find_snippet(app, id)?.clone();
+            for change in changes {
+                if let Some(tag) = change.strip_prefix('-') {
+                    snip.tags.retain(|t| t != tag);
+                } else {
+                    let tag = change.strip_prefix('+').unwrap_or(&change);
+                    if tag.is_empty() || tag.contains(char::is_whitespace) {
+                        return Err(CliError::InvalidInput(format!("invalid tag '{}'", change)));
+                    };
+                    if !snip.tags.iter().any(|t| t == tag) {
+                        snip.tags.push(tag.to_string());
+                    };
+                };
+            };
+            writeln!(out, "{}", snip.tags.join(" "))?;
+            app.add_snippet(snip);
+            save_app_state(app)?;
+        },
+        Command::Edit { id } => {
+            let mut snip = find_snippet(app, id)?.clone();
+            snip.code = edit_in_external_editor(&snip)?;
+            app.add_snippet(snip);
+            save_app_state(app)?;
+        },
+        Command::Run { id, args } => {
+            let snip = find_snippet(app, id)?;
+            let interpreter = interpreter_for(snip, &config.interpreters)
+                .ok_or_else(|| CliError::InvalidInput(missing_interpreter_message(snip)))?;
+            let status = run_snippet(snip, &interpreter, &args)?;
+            if !status.success() {
+                return Err(CliError::SnippetFailed(status.code()));
+            };
+        },
+        Command::Import { source } => {
+            let mut dry_run: Option<DryRunArgs> = None;
+            let imported = match source {
+                ImportSource::Vscode { file, language } => {
+                    let text = fs::read_to_string(&file)?;
+                    let imported = vscode::parse(&text, language.as_deref())
+                        .map_err(|e| CliError::InvalidInput(format!("{}: {}", file, e)))?;
+                    with_source(imported, &file)
+                },
+                ImportSource::Ultisnips { paths } => import_files(&paths, "snippets", |text, path, root| {
+                    Ok(vim::parse_ultisnips(text, &vim::filetype_from_path(path, root)))
+                })?,
+                ImportSource::Snipmate { paths } => import_files(&paths, "snippets", |text, path, root| {
+                    Ok(vim::parse_snipmate(text, &vim::filetype_from_path(path, root)))
+                })?,
+                ImportSource::Yasnippet { dir } => yasnippet::read_tree(Path::new(&dir))?,
+                ImportSource::Sublime { paths } => import_files(&paths, "sublime-snippet", |text, path, _| {
+                    sublime::parse(text, &path.file_stem().unwrap_or_default().to_string_lossy())
+                })?,
+                ImportSource::Jetbrains { paths } => import_files(&paths, "xml", |text, _, _| jetbrains::parse(text))?,
+                ImportSource::Markdown { file, all } => {
+                    let text = fs::read_to_string(&file)?;
+                    let imported = markdown::parse(&text, &file);
+                    match all {
+                        true => imported,
+                        false => review_import(imported)?,
+                    }
+                },
+                ImportSource::Pet { file, dry_run: args } => {
+                    dry_run = Some(args);
+                    let text = fs::read_to_string(&file)?;
+                    pet::parse(&text, &file).map_err(|e| CliError::InvalidInput(format!("{}: {}", file, e)))?
+                },
+                ImportSource::Navi { paths, dry_run: args } => {
+                    dry_run = Some(args);
+                    import_files(&paths, "cheat", |text, path, _| Ok(navi::parse(text, &path.to_string_lossy())))?
+                },
+                ImportSource::Masscode { file, dry_run: args } => {
+                    dry_run = Some(args);
+                    let text = fs::read_to_string(&file)?;
+                    masscode::parse(&text, &file).map_err(|e| CliError::InvalidInput(format!("{}: {}", file, e)))?
+                },
+            };
+            if let Some(args) = dry_run {
+                let count = write_dry_run(out, app, &imported)?;
+                if count == 0 || args.dry_run || !(args.yes || confirm_import(out, count)?) {
+                    return Ok(());
+                };
+            };
+            let report = add_imported(app, imported);
+            save_app_state(app)?;
+            write_report(out, &report)?;
+        },
+        Command::Export { target } => {
+            match target {
+                ExportTarget::Vscode { args } => {
+                    let snippets = select_snippets(app, &args.query);
+                    write_export(out, args.output.as_deref(), &vscode::write(&snippets))?;
+                },
+                ExportTarget::Ultisnips { args } => {
+                    let snippets = select_snippets(app, &args.query);
+                    let (files, notes) = vim::write_ultisnips(&snippets);
+                    write_export_files(out, &args.dir, files)?;
+                    for (name, note) in notes {
+                        eprintln!("note '{}': {}", name, note);
+                    };
+                },
+                ExportTarget::Snipmate { args } => {
+                    let snippets = select_snippets(app, &args.query);
+                    write_export_files(out, &args.dir, vim::write_snipmate(&snippets))?;
+                },
+                ExportTarget::Yasnippet { args } => {
+                    let snippets = select_snippets(app, &args.query);
+                    write_export_files(out, &args.dir, yasnippet::write(&snippets))?;
+                },
+                ExportTarget::Sublime { args } => {
+                    let snippets = select_snippets(app, &args.query);
+                    write_export_files(out, &args.dir, sublime::write(&snippets))?;
+                },
+                ExportTarget::Markdown { args } => {
+                    let snippets = select_snippets(app, &args.query);
+                    write_export(out, args.output.as_deref(), &markdown::write(&snippets))?;
+                },
+                ExportTarget::Html { args } => {
+                    let snippets = select_snippets(app, &args.query);
+                    write_export_files(out, &args.dir, html::write(&snippets))?;
+                },
+                ExportTarget::Jetbrains { args } => {
+                    let snippets = select_snippets(app, &args.query);
+                    let (text, notes) = jetbrains::write(&snippets);
+                    write_export(out, args.output.as_deref(), &text)?;
+                    // Notes go to stderr, stdout may hold the exported file
+                    for (name, note) in notes {
+                        eprintln!("note '{}': {}", name, note);
+                    };
+                },
+            };
+        },
+        Command::Bundle { action } => {
+            match action {
+                BundleAction::Create { output, ids, tags, query } => {
+                    let snippets = select_bundled(app, &ids, &tags, &query)?;
+                    if snippets.is_empty() {
+                        return Err(CliError::NoMatches);
+                    };
+                    bundle::create(&snippets, fs::File::create(&output)?)?;
+                    writeln!(out, "bundled {} snippets into {}", snippets.len(), output)?;
+                },
+                BundleAction::Import { file, on_conflict, dry_run } => {
+                    let (manifest, snippets) = bundle::read(fs::File::open(&file)?).map_err(CliError::InvalidInput)?;
+                    writeln!(out, "{}: {} snippets, made by snippy {}", file, snippets.len(), manifest.snippy_version)?;
+                    let incoming = bundle::compare(app, snippets);
+                    write_bundle_preview(out, &incoming)?;
+                    let count = incoming.iter().filter(|i| matches!(i, Incoming::New(..) | Incoming::Conflict(..))).count();
+                    let conflicts = incoming.iter().filter(|i| matches!(i, Incoming::Conflict(..))).count();
+                    if count == 0 {
+                        writeln!(out, "nothing to import, every snippet is saved already")?;
+                        return Ok(());
+                    };
+                    if dry_run.dry_run {
+                        return Ok(());
+                    };
+                    let interactive = io::stdin().is_terminal();
+                    if conflicts > 0 && on_conflict.is_none() && !interactive {
+                        return Err(CliError::InvalidInput(format!(
+                            "{} snippets conflict with saved ones, choose what happens to them with --on-conflict",
+                            conflicts,
+                        )));
+                    };
+                    if !(dry_run.yes || confirm_import(out, count)?) {
+                        return Ok(());
+                    };
+                    import_bundle(out, app, incoming, on_conflict)?;
+                    save_app_state(app)?;
+                },
+            };
+        },
+        Command::Render { id, format, output, options } => {
+            let snip = find_snippet(app, id)?;
+            let svg = render_svg(snip, &options).map_err(CliError::InvalidInput)?;
+            let image = match format {
+                ImageFormat::Svg => svg.into_bytes(),
+                ImageFormat::Png => {
+                    if output.is_none() && io::stdout().is_terminal() {
+                        return Err(CliError::InvalidInput(String::from("a PNG can't be printed, use -o or pipe it into a file")));
+                    };
+                    let (png, warning) = render_png(&svg, &options).map_err(CliError::InvalidInput)?;
+                    if let Some(warning) = warning {
+                        eprintln!("snippy: {}", warning);
+                    };
+                    png
+                },
+            };
+            match output {
+                Some(path) => fs::write(path, image)?,
+                None => out.write_all(&image)?,
+            };
+        },
+        Command::Copy { id } => {
+            let code = find_snippet(app, id)?.code.clone();
+            let mut ctx: ClipboardContext = ClipboardProvider::new().map_err(|e| CliError::Clipboard(e.to_string()))?;
+            ctx.set_contents(code).map_err(|e| CliError::Clipboard(e.to_string()))?;
+        },
+        Command::Init { shell } => {
+            let snippy_bin = std::env::current_exe()?;
+            write!(out, "{}", init_script(shell, &snippy_bin.to_string_lossy()))?;
+        },
+        Command::Pick { mut multi, mut query } => {
+            multi |= take_option(&mut query, "--multi", false)?.is_some();
+            let picked = pick_snippets(app, &query.join(" "), multi)?.ok_or(CliError::Cancelled)?;
+            for idx in picked {
+                writeln!(out, "{}", find_snippet(app, idx)?.code)?;
+            };
+        },
+    };
+    Ok(())
+}
+
+/// clap reads everything after the first search term as a term, so terms like `-foo` can exclude snippets.
+/// Takes the option (and its value) back out of the terms and returns the value, or an empty one for a flag.
+/// The first `--` ends the options, it's left to clap if it comes before the terms
+fn take_option(terms: &mut Vec<String>, name: &str, takes_value: bool) -> Result<Option<String>, CliError> {
+    let mut end = terms.iter().position(|t| t == "--").unwrap_or(terms.len());
+    let mut found = None;
+    let mut i = 0;
+    while i < end {
+        let inline = terms[i].strip_prefix(name).and_then(|rest| rest.strip_prefix('=')).filter(|_| takes_value);
+        if let Some(value) = inline {
+            found = Some(value.to_string());
+        } else if terms[i] == name {
+            if takes_value {
+                if i + 1 >= end {
+                    return Err(CliError::InvalidInput(format!("{} needs a value", name)));
+                };
+                found = Some(terms.remove(i + 1));
+                end -= 1;
+            } else {
+                found = Some(String::new());
+            };
+        } else {
+            i += 1;
+            continue;
+        };
+        terms.remove(i);
+        end -= 1;
+    };
+    if end < terms.len() {
+        terms.remove(end);
+    };
+    Ok(found)
+}
+
+/// Reads `path`, `path:line` or `path:start-end` (1-based, inclusive).
+/// Returns the path, the code and the source to record for the snippet
+fn read_line_range(spec: &str) -> Result<(String, String, String), CliError> {
+    let (path, range) = match spec.rsplit_once(':') {
+        // Windows paths like C:\foo contain a colon too, so only split off something that looks like a range
+        Some((path, range)) if !range.is_empty() && range.chars().all(|c| c.is_ascii_digit() || c == '-') => (path, Some(range)),
+        _ => (spec, None),
+    };
+    let contents = fs::read_to_string(path)?;
+    let Some(range) = range else {
+        return Ok((path.to_string(), contents, path.to_string()));
+    };
+
+    let invalid = || CliError::InvalidInput(format!("invalid line range '{}'", range));
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (start.parse::<usize>().map_err(|_| invalid())?, end.parse::<usize>().map_err(|_| invalid())?),
+        None => {
+            let line = range.parse::<usize>().map_err(|_| invalid())?;
+            (line, line)
+        },
+    };
+    let line_count = contents.lines().count();
+    if start == 0 || end < start || start > line_count {
+        return Err(CliError::InvalidInput(format!("line range '{}' is outside of {} ({} lines)", range, path, line_count)));
+    };
+    let end = end.min(line_count);
+    let code = contents.lines().skip(start - 1).take(end - start + 1).collect::<Vec<&str>>().join("\n");
+    let source = match start == end {
+        true => format!("{}:{}", path, start),
+        false => format!("{}:{}-{}", path, start, end),
+    };
+    Ok((path.to_string(), code, source))
+}
+
+fn read_stdin() -> Result<String, CliError> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        return Err(CliError::InvalidInput(String::from("no code given, use --code, --from or pipe the code into snippy")));
+    };
+    let mut code = String::new();
+    stdin.read_to_string(&mut code)?;
+    Ok(code)
+}
+
+/// The first non-empty line of the code, shortened if it is too long
+fn name_from_code(code: &str) -> Option<String> {
+    let line = code.lines().map(str::trim).find(|l| !l.is_empty() && !l.starts_with("#!"))?;
+    let mut name: String = line.chars().take(MAX_INFERRED_NAME_LEN).collect();
+    if line.chars().count() > MAX_INFERRED_NAME_LEN {
+        name.push_str("...");
+    };
+    Some(name)
+}
+
+/// Records the imported file as the source of the snippets that don't know a more precise one
+fn with_source(mut imported: Imported, path: &str) -> Imported {
+    for snip in imported.snippets.iter_mut().filter(|s| s.source.is_none()) {
+        snip.source = Some(path.to_string());
+    };
+    imported
+}
+
+/// Reads the files with the extension in `paths` (files or directories) with `parse`, which gets the text,
+/// the path of the file and the path it was found in. Files `parse` fails on are reported as skipped
+fn import_files(
+    paths: &[String],
+    extension: &str,
+    parse: impl Fn(&str, &Path, &Path) -> Result<Imported, String>,
+) -> io::Result<Imported> {
+    let mut imported = Imported::default();
+    for root in paths {
+        let root = Path::new(root);
+        for path in collect_files(root, extension)? {
+            let text = fs::read_to_string(&path)?;
+            let file = match parse(&text, &path, root) {
+                Ok(file) => with_source(file, &path.to_string_lossy()),
+                Err(err) => {
+                    imported.skipped.push((path.display().to_string(), err));
+                    continue;
+                },
+            };
+            imported.snippets.extend(file.snippets);
+            imported.skipped.extend(file.skipped);
+            imported.notes.extend(file.notes);
+        };
+    };
+    Ok(imported)
+}
+
+/// Asks whether to go on with the import after the dry run. Without a terminal to ask on, nothing is imported
+fn confirm_import(out: &mut impl Write, count: usize) -> io::Result<bool> {
+    if !io::stdin().is_terminal() {
+        writeln!(out, "nothing imported, run again with --yes to import")?;
+        return Ok(false);
+    };
+    let answer = ask(out, &format!("Import {} snippets? [y/N] ", count))?;
+    let confirmed = matches!(answer.as_str(), "y" | "Y" | "yes");
+    if !confirmed {
+        writeln!(out, "nothing imported")?;
+    };
+    Ok(confirmed)
+}
+
+/// Prints the question and reads the answer from stdin, trimmed
+fn ask(out: &mut impl Write, question: &str) -> io::Result<String> {
+    write!(out, "{}", question)?;
+    out.flush()?;
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer)? == 0 {
+        writeln!(out)?;
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no answer, stdin was closed"));
+    };
+    Ok(answer.trim().to_string())
+}
+
+/// The snippets with one of the ids, one of the tags or matching the search terms, in the order they're saved in.
+/// All snippets if nothing is given
+fn select_bundled<'a>(app: &'a App, ids: &[usize], tags: &[String], query: &[String]) -> Result<Vec<&'a CodeSnippet>, CliError> {
+    if ids.is_empty() && tags.is_empty() && query.is_empty() {
+        return Ok(app.snippets.iter().collect());
+    };
+    for id in ids {
+        find_snippet(app, *id)?;
+    };
+    let matching: Vec<usize> = match query.is_empty() {
+        true => vec![],
+        false => select_snippets(app, query).into_iter().map(|s| s.idx).collect(),
+    };
+    Ok(app
+        .snippets
+        .iter()
+        .filter(|s| ids.contains(&s.idx) || s.tags.iter().any(|t| tags.contains(t)) || matching.contains(&s.idx))
+        .collect())
+}
+
+/// Lists the snippets of a bundle and whether they're new, saved already, conflict with a saved snippet or come twice
+fn write_bundle_preview(out: &mut impl Write, incoming: &[Incoming]) -> io::Result<()> {
+    for item in incoming {
+        let (status, snip, note) = match item {
+            Incoming::New(snip) => ("new", snip, String::new()),
+            Incoming::Identical(snip, idx) => ("identical", snip, format!(", same as snippet {}", idx)),
+            Incoming::Conflict(snip, idx) => ("conflict", snip, format!(", differs from snippet {}", idx)),
+            Incoming::Duplicate(snip) => ("duplicate", snip, String::from(", comes twice in the bundle")),
+        };
+        let language = snip.language.as_deref().map(|l| format!(" ({})", l)).unwrap_or_default();
+        let tags = match snip.tags.is_empty() {
+            true => String::new(),
+            false => format!(" [{}]", snip.tags.join(", ")),
+        };
+        writeln!(out, "  {:<10} '{}'{}{}{}", status, snip.name, language, tags, note)?;
+    };
+    Ok(())
+}
+
+/// Adds the new snippets of a bundle and resolves the conflicts with `on_conflict`, or by asking
+fn import_bundle(out: &mut impl Write, app: &mut App, incoming: Vec<Incoming>, on_conflict: Option<Resolution>) -> io::Result<()> {
+    let (mut added, mut overwritten, mut skipped) = (0, 0, 0);
+    for item in incoming {
+        let (mut snip, existing_idx) = match item {
+            Incoming::New(snip) => {
+                let name = snip.name.clone();
+                writeln!(out, "added '{}' as snippet {}", name, bundle::add(app, snip))?;
+                added += 1;
+                continue;
+            },
+            Incoming::Identical(..) => {
+                skipped += 1;
+                continue;
+            },
+            Incoming::Duplicate(snip) => {
+                writeln!(out, "skipped '{}': comes twice in the bundle", snip.name)?;
+                skipped += 1;
+                continue;
+            },
+            Incoming::Conflict(snip, existing_idx) => (snip, existing_idx),
+        };
+        let resolution = match on_conflict {
+            Some(resolution) => resolution,
+            None => ask_resolution(out, &snip.name, existing_idx)?,
+        };
+        match resolution {
+            Resolution::Skip => {
+                writeln!(out, "skipped '{}'", snip.name)?;
+                skipped += 1;
+            },
+            Resolution::Overwrite => {
+                writeln!(out, "overwrote snippet {} with '{}'", existing_idx, snip.name)?;
+                bundle::overwrite(app, snip, existing_idx);
+                overwritten += 1;
+            },
+            Resolution::Rename | Resolution::KeepBoth => {
+                if resolution == Resolution::Rename {
+                    let unused = bundle::unused_name(app, &snip.name);
+                    snip.name = match on_conflict {
+                        Some(_) => unused,
+                        None => ask_new_name(out, app, unused)?,
+                    };
+                };
+                let name = snip.name.clone();
+                writeln!(out, "added '{}' as snippet {}", name, bundle::add(app, snip))?;
+                added += 1;
+            },
+        };
+    };
+    writeln!(out, "imported {}, overwrote {}, skipped {}", added, overwritten, skipped)
+}
+
+/// Asks for a name no saved snippet has until one is given, an empty answer takes `unused`
+fn ask_new_name(out: &mut impl Write, app: &App, unused: String) -> io::Result<String> {
+    loop {
+        let name = ask(out, &format!("New name [{}]: ", unused))?;
+        if name.is_empty() {
+            return Ok(unused);
+        };
+        match app.snippets.iter().any(|s| s.name == name) {
+            true => writeln!(out, "there is a snippet named '{}' already", name)?,
+            false => return Ok(name),
+        };
+    }
+}
+
+/// Asks until one of the resolutions is chosen
+fn ask_resolution(out: &mut impl Write, name: &str, existing_idx: usize) -> io::Result<Resolution> {
+    loop {
+        let question = format!("'{}' conflicts with snippet {}: [s]kip, [r]ename, [o]verwrite or [k]eep both? ", name, existing_idx);
+        match ask(out, &question)?.as_str() {
+            "s" | "skip" => return Ok(Resolution::Skip),
+            "r" | "rename" => return Ok(Resolution::Rename),
+            "o" | "overwrite" => return Ok(Resolution::Overwrite),
+            "k" | "keep both" => return Ok(Resolution::KeepBoth),
+            _ => {},
+        };
+    }
+}
+
+/// Lets the user unmark the imported snippets they don't want, those are left out of the import
+fn review_import(mut imported: Imported) -> Result<Imported, CliError> {
+    if imported.snippets.is_empty() {
+        return Ok(imported);
+    };
+    if !io::stdin().is_terminal() {
+        return Err(CliError::InvalidInput(String::from("reviewing the import needs a terminal, use --all to import everything")));
+    };
+    let keep = review_snippets(&imported.snippets)?.ok_or(CliError::Cancelled)?;
+    let mut keep = keep.into_iter();
+    imported.snippets.retain(|_| keep.next().unwrap_or(false));
+    Ok(imported)
+}
+
+/// The snippets matching the search terms, or all snippets without search terms
+fn select_snippets<'a>(app: &'a App, query: &[String]) -> Vec<&'a CodeSnippet> {
+    if query.is_empty() {
+        return app.snippets.iter().collect();
+    };
+    search_snippets(&app.snippets, &query.join(" "))
+        .into_iter()
+        .map(|(list_idx, _)| &app.snippets[list_idx])
+        .collect()
+}
+
+/// Writes an exported file to `path`, or to `out` without a path
+fn write_export(out: &mut impl Write, path: Option<&str>, contents: &str) -> io::Result<()> {
+    match path {
+        Some(path) => fs::write(path, contents),
+        None => out.write_all(contents.as_bytes()),
+    }
+}
+
+/// Writes the exported files into `dir` and lists them in `out`
+fn write_export_files(out: &mut impl Write, dir: &str, files: Vec<(String, String)>) -> io::Result<()> {
+    for (file_name, contents) in files {
+        let path = Path::new(dir).join(file_name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        };
+        fs::write(&path, contents)?;
+        writeln!(out, "{}", path.display())?;
+    };
+    Ok(())
+}
+
+fn find_snippet(app: &App, id: usize) -> Result<&CodeSnippet, CliError> {
+    app.get_snippet(id).ok_or(CliError::NotFound(id))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(args: &str) -> Vec<String> {
+        args.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn options_after_search_terms() {
+        let mut query = terms("foo --format json -bar");
+        assert_eq!(take_option(&mut query, "--format", true).unwrap().as_deref(), Some("json"));
+        assert_eq!(query, terms("foo -bar"));
+
+        let mut query = terms("foo --format=tsv");
+        assert_eq!(take_option(&mut query, "--format", true).unwrap().as_deref(), Some("tsv"));
+        assert_eq!(query, terms("foo"));
+
+        let mut query = terms("foo --multi");
+        assert_eq!(take_option(&mut query, "--multi", false).unwrap().as_deref(), Some(""));
+        assert_eq!(query, terms("foo"));
+
+        assert!(take_option(&mut terms("foo --format"), "--format", true).is_err());
+    }
+
+    #[test]
+    fn terms_after_the_separator_stay_terms() {
+        let mut query = terms("foo -- --format json");
+        assert_eq!(take_option(&mut query, "--format", true).unwrap(), None);
+        assert_eq!(query, terms("foo --format json"));
+
+        let mut query = terms("foo --format -- -- bar");
+        assert!(take_option(&mut query, "--format", true).is_err());
+    }
+}
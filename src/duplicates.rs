@@ -0,0 +1,60 @@
+use crate::snippet::CodeSnippet;
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DuplicateKind {
+    /// The code is exactly the same
+    Exact,
+    /// The code only differs in whitespace
+    IgnoringWhitespace,
+}
+
+
+/// The code without any whitespace, two snippets with the same normalized code are near-duplicates
+pub fn normalize_code(code: &str) -> String {
+    code.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Snippets (other than `snippet` itself) that have the same code as `snippet`, exact duplicates first
+pub fn find_duplicates(snippets: &[CodeSnippet], snippet: &CodeSnippet) -> Vec<(usize, DuplicateKind)> {
+    let normalized = normalize_code(&snippet.code);
+    if normalized.is_empty() {
+        return vec![];
+    };
+    let mut duplicates: Vec<(usize, DuplicateKind)> = snippets
+        .iter()
+        .filter(|other| other.idx != snippet.idx)
+        .filter_map(|other| {
+            if other.code == snippet.code {
+                Some((other.idx, DuplicateKind::Exact))
+            } else if normalize_code(&other.code) == normalized {
+                Some((other.idx, DuplicateKind::IgnoringWhitespace))
+            } else {
+                None
+            }
+        })
+        .collect();
+    duplicates.sort_by_key(|(_, kind)| *kind != DuplicateKind::Exact);
+    duplicates
+}
+
+/// Groups of snippet idxs whose code is the same when ignoring whitespace.
+/// Only groups with at least two snippets are returned, in the order they appear in `snippets`
+pub fn group_duplicates(snippets: &[CodeSnippet]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<(String, Vec<usize>)> = vec![];
+    for snip in snippets.iter() {
+        let normalized = normalize_code(&snip.code);
+        if normalized.is_empty() {
+            continue;
+        };
+        match groups.iter_mut().find(|(code, _)| *code == normalized) {
+            Some((_, group)) => group.push(snip.idx),
+            None => groups.push((normalized, vec![snip.idx])),
+        };
+    };
+    groups
+        .into_iter()
+        .map(|(_, group)| group)
+        .filter(|group| group.len() > 1)
+        .collect()
+}
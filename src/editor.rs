@@ -0,0 +1,45 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::process::Command;
+
+use crate::{snippet::CodeSnippet, storage::TempCodeFile};
+
+
+/// The editor from `$VISUAL` or `$EDITOR`, split into program and arguments (e.g. `code --wait`)
+fn editor_command() -> Vec<String> {
+    let editor = env::var("VISUAL")
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .or_else(|| env::var("EDITOR").ok().filter(|e| !e.trim().is_empty()));
+    match editor {
+        Some(editor) => editor.split_whitespace().map(String::from).collect(),
+        None if cfg!(windows) => vec![String::from("notepad")],
+        None => vec![String::from("vi")],
+    }
+}
+
+/// Opens the code of the snippet in the user's editor and returns the edited code.
+///
+/// The code is written to a temporary file with the extension of the snippet's language,
+/// so the editor can highlight it. If the editor can't be started or exits with an error,
+/// an error is returned and the snippet should be left as it is
+pub fn edit_in_external_editor(snippet: &CodeSnippet) -> io::Result<String> {
+    let file = TempCodeFile::new(snippet)?;
+
+    let command = editor_command();
+    let status = Command::new(&command[0]).args(&command[1..]).arg(&file.path).status();
+    let mut code = match status {
+        Ok(status) if status.success() => fs::read_to_string(&file.path),
+        Ok(status) => Err(io::Error::other(format!("{} exited with {}", command[0], status))),
+        Err(err) => Err(io::Error::new(err.kind(), format!("could not start {}: {}", command[0], err))),
+    }?;
+    // Most editors end the file with a newline, which the snippet didn't have before
+    if code.ends_with('\n') && !snippet.code.ends_with('\n') {
+        code.pop();
+        if code.ends_with('\r') {
+            code.pop();
+        };
+    };
+    Ok(code)
+}
@@ -0,0 +1,82 @@
+use std::sync::OnceLock;
+
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Color, FontStyle, Style, Theme, ThemeSet},
+    html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator},
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+use crate::language::extension_for_language;
+
+/// Prefix of the CSS classes of highlighted code, so they don't clash with the classes of the page
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+
+/// The syntaxes bundled with syntect, loaded the first time they're needed
+pub fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+/// The themes bundled with syntect
+pub fn theme_set() -> &'static ThemeSet {
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    THEMES.get_or_init(ThemeSet::load_defaults)
+}
+
+/// The theme with that name, e.g. `base16-ocean.dark` or `InspiredGitHub`
+pub fn find_theme(name: &str) -> Option<&'static Theme> {
+    theme_set().themes.get(name)
+}
+
+/// The syntax of the language (by name or extension), else the one the first line of the code asks for, else plain text
+pub fn syntax_for(language: Option<&str>, code: &str) -> &'static SyntaxReference {
+    let syntaxes = syntax_set();
+    language
+        .and_then(|language| {
+            syntaxes
+                .find_syntax_by_token(language)
+                .or_else(|| syntaxes.find_syntax_by_extension(extension_for_language(Some(language))))
+        })
+        .or_else(|| syntaxes.find_syntax_by_first_line(code))
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text())
+}
+
+/// The code as HTML spans with `hl-` classes, styled by `theme_css`
+pub fn highlight_html(code: &str, language: Option<&str>) -> String {
+    let syntax = syntax_for(language, code);
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, syntax_set(), CLASS_STYLE);
+    for line in LinesWithEndings::from(code) {
+        // Only fails for broken syntax definitions, the line is left out then
+        let _ = generator.parse_html_for_line_which_includes_newline(line);
+    };
+    generator.finalize()
+}
+
+/// CSS for the classes of `highlight_html` in the colors of the theme, `.hl-code` has the background
+pub fn theme_css(theme: &Theme) -> String {
+    css_for_theme_with_class_style(theme, CLASS_STYLE).unwrap_or_default()
+}
+
+/// The lines of the code as (style, text) in the colors of the theme, without the line endings
+pub fn highlight_lines(code: &str, language: Option<&str>, theme: &Theme) -> Vec<Vec<(Style, String)>> {
+    let mut highlighter = HighlightLines::new(syntax_for(language, code), theme);
+    let plain = Style {
+        foreground: theme.settings.foreground.unwrap_or(Color::WHITE),
+        background: theme.settings.background.unwrap_or(Color::BLACK),
+        font_style: FontStyle::empty(),
+    };
+    LinesWithEndings::from(code)
+        .map(|line| {
+            // Only fails for broken syntax definitions, the line isn't highlighted then
+            let ranges = highlighter.highlight_line(line, syntax_set()).unwrap_or_else(|_| vec![(plain, line)]);
+            ranges
+                .into_iter()
+                .map(|(style, text)| (style, text.trim_end_matches(['\n', '\r']).to_string()))
+                .filter(|(_, text)| !text.is_empty())
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,215 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{app::App, snippet::CodeSnippet};
+
+pub mod html;
+pub mod jetbrains;
+pub mod markdown;
+pub mod masscode;
+pub mod navi;
+pub mod pet;
+pub mod sublime;
+pub mod vim;
+pub mod vscode;
+pub mod yasnippet;
+
+
+/// Snippets read from the format of another tool, they get their idx once they're added to the app
+#[derive(Default)]
+pub struct Imported {
+    pub snippets: Vec<CodeSnippet>,
+    /// Name of the entry and why it couldn't be imported
+    pub skipped: Vec<(String, String)>,
+    /// Name of the snippet and what of it couldn't be represented in snippy
+    pub notes: Vec<(String, String)>,
+}
+
+/// What happened to the snippets of an import
+#[derive(Default)]
+pub struct ImportReport {
+    /// Idx and name of every added snippet
+    pub added: Vec<(usize, String)>,
+    /// Name of the entry and why it wasn't added
+    pub skipped: Vec<(String, String)>,
+    /// Name of the snippet and what of it couldn't be represented in snippy
+    pub notes: Vec<(String, String)>,
+}
+
+
+/// Adds the imported snippets to the app. Snippets named like an existing snippet of the same language
+/// are skipped, so importing the same file twice doesn't add everything again
+pub fn add_imported(app: &mut App, imported: Imported) -> ImportReport {
+    let mut report = ImportReport { added: vec![], skipped: imported.skipped, notes: imported.notes };
+    for mut snip in imported.snippets {
+        if let Some(reason) = conflict(app, &snip) {
+            report.skipped.push((snip.name, reason));
+            continue;
+        };
+        snip.idx = app.return_next_idx();
+        report.added.push((snip.idx, snip.name.clone()));
+        app.add_snippet(snip);
+    }
+    report
+}
+
+/// Why the snippet wouldn't be added by `add_imported`, if it wouldn't
+pub fn conflict(app: &App, snip: &CodeSnippet) -> Option<String> {
+    let existing = app.snippets.iter().find(|s| s.name == snip.name && s.language == snip.language)?;
+    match existing.code == snip.code {
+        true => Some(format!("already saved as snippet {}", existing.idx)),
+        false => Some(format!("conflicts with snippet {} of the same name", existing.idx)),
+    }
+}
+
+/// The trigger of the snippet, or one made from its name (or idx, if the name has no letters) for tools that need a trigger
+pub fn trigger_or_name(snippet: &CodeSnippet) -> String {
+    if let Some(trigger) = snippet.trigger.as_ref().filter(|t| !t.trim().is_empty()) {
+        return trigger.clone();
+    };
+    let trigger: String = snippet
+        .name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() {c} else {'_'})
+        .collect();
+    match trigger.trim_matches('_') {
+        "" => format!("snippet{}", snippet.idx),
+        trigger => trigger.to_string(),
+    }
+}
+
+/// `path` itself if it's a file, or else every file with the extension somewhere below the directory `path`
+pub fn collect_files(path: &Path, extension: &str) -> io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    };
+    let mut files = vec![];
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?.map(|e| e.map(|e| e.path())).collect::<io::Result<_>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            files.extend(collect_files(&entry, extension)?);
+        } else if entry.extension().is_some_and(|e| e == extension) {
+            files.push(entry);
+        };
+    }
+    Ok(files)
+}
+
+/// Escapes the text for XML, in attributes newlines and tabs are escaped too so they survive
+pub fn escape_xml(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            '\n' if attribute => escaped.push_str("&#10;"),
+            '\t' if attribute => escaped.push_str("&#9;"),
+            '\r' if attribute => (),
+            _ => escaped.push(c),
+        };
+    };
+    escaped
+}
+
+/// Whether a `$` followed by the text would be read as a placeholder, like in `$1` or `${1:name}`
+pub fn starts_placeholder(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some('{') => chars.next().is_some_and(|c| c.is_ascii_digit()),
+        Some(c) => c.is_ascii_digit(),
+        None => false,
+    }
+}
+
+/// Escapes the dollar signs of the text that would be read as placeholders, so shell code like `$1` stays as it is
+pub fn escape_placeholders(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        if c == '$' && starts_placeholder(&text[i + 1..]) {
+            escaped.push('\\');
+        };
+        escaped.push(c);
+    };
+    escaped
+}
+
+/// Whether the code is written with placeholders like `${1:name}`: snippets imported from editors (they have a trigger)
+/// and from tools whose parameters became placeholders. Other code is taken as it is
+pub fn uses_placeholders(snippet: &CodeSnippet) -> bool {
+    if snippet.trigger.is_some() {
+        return true;
+    };
+    snippet.code.match_indices("${").any(|(i, _)| {
+        let rest = snippet.code[i + 2..].trim_start_matches(|c: char| c.is_ascii_digit());
+        let digits = snippet.code.len() - i - 2 - rest.len();
+        // `${1:-default}` and the like are shell parameter expansions
+        digits > 0 && (rest.starts_with('|') || (rest.starts_with(':') && !rest[1..].starts_with(['-', '=', '+', '?'])))
+    })
+}
+
+/// Escapes every `$` and `\` of the text, so editors insert it as it is instead of reading variables and placeholders
+pub fn escape_snippet_syntax(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '$' || c == '\\' {
+            escaped.push('\\');
+        };
+        escaped.push(c);
+    };
+    escaped
+}
+
+/// Lists what the import would do without doing it: every snippet with where it comes from and what it maps to,
+/// then the entries that would be skipped and what couldn't be represented. Returns how many snippets would be added
+pub fn write_dry_run(out: &mut impl Write, app: &App, imported: &Imported) -> io::Result<usize> {
+    let mut added = 0;
+    let mut skipped: Vec<(String, String)> = imported.skipped.clone();
+    for (i, snip) in imported.snippets.iter().enumerate() {
+        if let Some(reason) = conflict(app, snip) {
+            skipped.push((snip.name.clone(), reason));
+            continue;
+        };
+        if imported.snippets[..i].iter().any(|s| s.name == snip.name && s.language == snip.language) {
+            skipped.push((snip.name.clone(), String::from("comes twice in the import")));
+            continue;
+        };
+        added += 1;
+        writeln!(out, "{} -> '{}'", snip.source.as_deref().unwrap_or("?"), snip.name)?;
+        if let Some(language) = &snip.language {
+            writeln!(out, "    language: {}", language)?;
+        };
+        if !snip.tags.is_empty() {
+            writeln!(out, "    tags: {}", snip.tags.join(", "))?;
+        };
+        if let Some(description) = &snip.description {
+            writeln!(out, "    description: {}", description)?;
+        };
+        for (key, value) in &snip.extra {
+            writeln!(out, "    {}: {}", key, value.replace('\n', " "))?;
+        };
+    };
+    for (name, reason) in &skipped {
+        writeln!(out, "would skip '{}': {}", name, reason)?;
+    };
+    for (name, note) in &imported.notes {
+        writeln!(out, "note '{}': {}", name, note)?;
+    };
+    writeln!(out, "dry run: would import {} snippets, skip {}", added, skipped.len())?;
+    Ok(added)
+}
+
+/// Lists the skipped entries and what couldn't be represented, and sums up the import
+pub fn write_report(out: &mut impl Write, report: &ImportReport) -> io::Result<()> {
+    for (name, reason) in &report.skipped {
+        writeln!(out, "skipped '{}': {}", name, reason)?;
+    };
+    for (name, note) in &report.notes {
+        writeln!(out, "note '{}': {}", name, note)?;
+    };
+    writeln!(out, "imported {} snippets, skipped {}", report.added.len(), report.skipped.len())
+}
@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+
+use serde_json::json;
+
+use crate::{
+    highlight::{find_theme, highlight_html, theme_css},
+    interop::escape_xml,
+    snippet::CodeSnippet,
+};
+
+/// Title of the site
+const SITE_TITLE: &str = "Snippets";
+
+/// Directory of the stylesheets and scripts, relative to the site
+const ASSETS_DIR: &str = "assets";
+/// Directory of the snippet pages, relative to the site
+const PAGES_DIR: &str = "snippets";
+
+// Highlighting themes for light and dark system themes
+const LIGHT_THEME: &str = "InspiredGitHub";
+const DARK_THEME: &str = "base16-ocean.dark";
+
+// How much a match of a search term in each field counts
+const NAME_WEIGHT: u32 = 4;
+const TAG_WEIGHT: u32 = 3;
+const DESCRIPTION_WEIGHT: u32 = 2;
+const CODE_WEIGHT: u32 = 1;
+
+const STYLE: &str = include_str!("html/style.css");
+const SCRIPT: &str = include_str!("html/snippy.js");
+
+
+/// The files of a static site as (path relative to the site, contents): an index with tag facets, a page per snippet
+/// and a prebuilt search index. Everything is loaded with plain `<script>` and `<link>` tags, so it works from `file://`
+pub fn write(snippets: &[&CodeSnippet]) -> Vec<(String, String)> {
+    let mut files = vec![
+        (String::from("index.html"), index_page(snippets)),
+        (format!("{}/style.css", ASSETS_DIR), STYLE.to_string()),
+        (format!("{}/highlight.css", ASSETS_DIR), highlight_css()),
+        (format!("{}/snippy.js", ASSETS_DIR), SCRIPT.to_string()),
+        (format!("{}/search-index.js", ASSETS_DIR), search_index(snippets)),
+    ];
+    for snip in snippets {
+        files.push((page_path(snip), snippet_page(snip)));
+    };
+    files
+}
+
+
+fn page_path(snip: &CodeSnippet) -> String {
+    format!("{}/{}.html", PAGES_DIR, snip.idx)
+}
+
+/// Start of every page, `root` leads from the page to the root of the site
+fn page_head(title: &str, root: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n\
+         <link rel=\"stylesheet\" href=\"{root}{assets}/style.css\">\n\
+         <link rel=\"stylesheet\" href=\"{root}{assets}/highlight.css\">\n\
+         </head>\n<body>\n",
+        escape_xml(title, false),
+        root = root,
+        assets = ASSETS_DIR,
+    )
+}
+
+fn index_page(snippets: &[&CodeSnippet]) -> String {
+    let mut tag_counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for tag in snippets.iter().flat_map(|s| &s.tags) {
+        *tag_counts.entry(tag).or_default() += 1;
+    };
+
+    let mut html = page_head(SITE_TITLE, "");
+    html.push_str(&format!(
+        "<header>\n<h1>{}</h1>\n<input id=\"search\" type=\"search\" placeholder=\"Search {} snippets\" autocomplete=\"off\" autofocus>\n</header>\n",
+        SITE_TITLE,
+        snippets.len(),
+    ));
+    html.push_str("<main class=\"index\">\n<nav class=\"facets\">\n<h2>Tags</h2>\n<ul>\n");
+    for (tag, count) in &tag_counts {
+        html.push_str(&format!(
+            "<li><button class=\"facet\" data-tag=\"{}\">{} <span class=\"count\">{}</span></button></li>\n",
+            escape_xml(tag, true),
+            escape_xml(tag, false),
+            count,
+        ));
+    };
+    html.push_str("</ul>\n</nav>\n<section>\n<ul id=\"results\" class=\"snippets\">\n");
+    for snip in snippets {
+        html.push_str(&format!(
+            "<li data-id=\"{}\">\n<a href=\"{}\">{}</a>\n",
+            snip.idx,
+            page_path(snip),
+            escape_xml(&snip.name, false),
+        ));
+        if let Some(language) = &snip.language {
+            html.push_str(&format!("<span class=\"language\">{}</span>\n", escape_xml(language, false)));
+        };
+        for tag in &snip.tags {
+            html.push_str(&format!("<span class=\"tag\">{}</span>\n", escape_xml(tag, false)));
+        };
+        if let Some(description) = &snip.description {
+            html.push_str(&format!("<p>{}</p>\n", escape_xml(description, false)));
+        };
+        html.push_str("</li>\n");
+    };
+    html.push_str("</ul>\n<p id=\"no-results\" hidden>No snippets found</p>\n</section>\n</main>\n");
+    html.push_str(&format!(
+        "<script src=\"{assets}/search-index.js\"></script>\n<script src=\"{assets}/snippy.js\"></script>\n</body>\n</html>\n",
+        assets = ASSETS_DIR,
+    ));
+    html
+}
+
+fn snippet_page(snip: &CodeSnippet) -> String {
+    let mut html = page_head(&snip.name, "../");
+    html.push_str(&format!(
+        "<header>\n<a class=\"back\" href=\"../index.html\">All snippets</a>\n<h1>{}</h1>\n</header>\n<main class=\"snippet\">\n",
+        escape_xml(&snip.name, false),
+    ));
+    if let Some(description) = &snip.description {
+        html.push_str(&format!("<p class=\"description\">{}</p>\n", escape_xml(description, false)));
+    };
+
+    html.push_str("<dl class=\"metadata\">\n");
+    if let Some(language) = &snip.language {
+        html.push_str(&format!("<dt>Language</dt><dd>{}</dd>\n", escape_xml(language, false)));
+    };
+    if !snip.tags.is_empty() {
+        // The index reads the tag from the fragment and selects its facet
+        let tags: Vec<String> = snip
+            .tags
+            .iter()
+            .map(|t| format!("<a class=\"tag\" href=\"../index.html#tag={}\">{}</a>", escape_xml(&percent_encode(t), true), escape_xml(t, false)))
+            .collect();
+        html.push_str(&format!("<dt>Tags</dt><dd>{}</dd>\n", tags.join(" ")));
+    };
+    if let Some(trigger) = &snip.trigger {
+        html.push_str(&format!("<dt>Trigger</dt><dd><code>{}</code></dd>\n", escape_xml(trigger, false)));
+    };
+    if let Some(source) = &snip.source {
+        html.push_str(&format!("<dt>Source</dt><dd>{}</dd>\n", escape_xml(source, false)));
+    };
+    html.push_str("</dl>\n");
+
+    html.push_str(&format!(
+        "<div class=\"code-block\">\n<button class=\"copy\" data-target=\"code\">Copy</button>\n<pre class=\"hl-code\" id=\"code\"><code>{}</code></pre>\n</div>\n</main>\n",
+        highlight_html(&snip.code, snip.language.as_deref()),
+    ));
+    html.push_str(&format!("<script src=\"../{}/snippy.js\"></script>\n</body>\n</html>\n", ASSETS_DIR));
+    html
+}
+
+/// The light theme, and the dark theme for dark system themes
+fn highlight_css() -> String {
+    let light = find_theme(LIGHT_THEME).map(theme_css).unwrap_or_default();
+    let dark = find_theme(DARK_THEME).map(theme_css).unwrap_or_default();
+    format!("{}\n@media (prefers-color-scheme: dark) {{\n{}}}\n", light, dark)
+}
+
+/// A script setting `window.SNIPPY_INDEX`, a script and not JSON because browsers don't let pages fetch from `file://`.
+/// It holds the snippets in the order of the index page and every search term with the snippets it's found in
+/// as [position, weight]
+fn search_index(snippets: &[&CodeSnippet]) -> String {
+    let mut terms: BTreeMap<String, BTreeMap<usize, u32>> = BTreeMap::new();
+    for (position, snip) in snippets.iter().enumerate() {
+        let mut add = |text: &str, weight: u32, min_len: usize| {
+            for term in search_terms(text).filter(|t| t.chars().count() >= min_len) {
+                *terms.entry(term).or_default().entry(position).or_default() += weight;
+            };
+        };
+        add(&snip.name, NAME_WEIGHT, 1);
+        for tag in &snip.tags {
+            add(tag, TAG_WEIGHT, 1);
+        };
+        if let Some(language) = &snip.language {
+            add(language, TAG_WEIGHT, 1);
+        };
+        if let Some(trigger) = &snip.trigger {
+            add(trigger, TAG_WEIGHT, 1);
+        };
+        if let Some(description) = &snip.description {
+            add(description, DESCRIPTION_WEIGHT, 1);
+        };
+        // Single characters of code would only bloat the index
+        add(&snip.code, CODE_WEIGHT, 2);
+    };
+
+    let index = json!({
+        "snippets": snippets.iter().map(|s| json!({"id": s.idx, "tags": s.tags})).collect::<Vec<_>>(),
+        "terms": terms
+            .into_iter()
+            .map(|(term, found)| (term, found.into_iter().map(|(position, weight)| [position as u32, weight]).collect::<Vec<_>>()))
+            .collect::<BTreeMap<_, _>>(),
+    });
+    format!("window.SNIPPY_INDEX = {};\n", index)
+}
+
+/// Lowercase words, split the same way snippy.js splits the query
+fn search_terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|term| !term.is_empty())
+        .map(|term| term.to_lowercase())
+}
+
+/// Percent-encodes everything but unreserved characters, for tags in URLs
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        };
+    };
+    encoded
+}
@@ -0,0 +1,156 @@
+// Copy buttons of the snippet pages, and search and tag facets of the index.
+// Written by `snippy export html`, the search index comes from search-index.js
+(function () {
+    "use strict";
+
+    // Copy buttons
+
+    function copyText(text) {
+        if (navigator.clipboard && window.isSecureContext) {
+            return navigator.clipboard.writeText(text);
+        }
+        // Older browsers, and pages that aren't a secure context
+        var area = document.createElement("textarea");
+        area.value = text;
+        area.setAttribute("readonly", "");
+        area.style.position = "fixed";
+        area.style.opacity = "0";
+        document.body.appendChild(area);
+        area.select();
+        var copied = document.execCommand("copy");
+        document.body.removeChild(area);
+        return copied ? Promise.resolve() : Promise.reject(new Error("copy failed"));
+    }
+
+    document.querySelectorAll("button.copy").forEach(function (button) {
+        button.addEventListener("click", function () {
+            var code = document.getElementById(button.dataset.target);
+            copyText(code.textContent).then(
+                function () { button.textContent = "Copied"; },
+                function () { button.textContent = "Press Ctrl+C"; selectContents(code); }
+            );
+            setTimeout(function () { button.textContent = "Copy"; }, 1500);
+        });
+    });
+
+    function selectContents(element) {
+        var range = document.createRange();
+        range.selectNodeContents(element);
+        var selection = window.getSelection();
+        selection.removeAllRanges();
+        selection.addRange(range);
+    }
+
+    // Search and facets
+
+    var index = window.SNIPPY_INDEX;
+    var search = document.getElementById("search");
+    var results = document.getElementById("results");
+    if (!index || !search || !results) {
+        return;
+    }
+
+    var items = {};
+    results.querySelectorAll("li[data-id]").forEach(function (item) {
+        items[item.dataset.id] = item;
+    });
+    var facets = document.querySelectorAll("button.facet");
+    var terms = Object.keys(index.terms);
+    var selectedTags = [];
+
+    // Split like the search terms of the index
+    function words(text) {
+        return text.toLowerCase().split(/[^\p{L}\p{N}_]+/u).filter(function (w) { return w.length > 0; });
+    }
+
+    // Score per position for the query, every word has to be the start of a term. Null without a query
+    function scores(query) {
+        var queryWords = words(query);
+        if (queryWords.length === 0) {
+            return null;
+        }
+        var total = null;
+        queryWords.forEach(function (word) {
+            var found = {};
+            terms.forEach(function (term) {
+                if (term.lastIndexOf(word, 0) !== 0) {
+                    return;
+                }
+                // Whole words count more than prefixes
+                var factor = term === word ? 2 : 1;
+                index.terms[term].forEach(function (entry) {
+                    found[entry[0]] = (found[entry[0]] || 0) + entry[1] * factor;
+                });
+            });
+            if (total === null) {
+                total = found;
+                return;
+            }
+            Object.keys(total).forEach(function (position) {
+                if (position in found) {
+                    total[position] += found[position];
+                } else {
+                    delete total[position];
+                }
+            });
+        });
+        return total;
+    }
+
+    function hasSelectedTags(snippet) {
+        return selectedTags.every(function (tag) { return snippet.tags.indexOf(tag) !== -1; });
+    }
+
+    function update() {
+        var found = scores(search.value);
+        var positions = index.snippets.map(function (_, position) { return position; });
+        if (found !== null) {
+            positions = positions.filter(function (p) { return p in found; });
+            positions.sort(function (a, b) { return found[b] - found[a] || a - b; });
+        }
+        positions = positions.filter(function (p) { return hasSelectedTags(index.snippets[p]); });
+
+        // Show the matches in order and hide the rest
+        Object.keys(items).forEach(function (id) { items[id].hidden = true; });
+        var counts = {};
+        positions.forEach(function (position) {
+            var snippet = index.snippets[position];
+            var item = items[snippet.id];
+            item.hidden = false;
+            results.appendChild(item);
+            snippet.tags.forEach(function (tag) { counts[tag] = (counts[tag] || 0) + 1; });
+        });
+        document.getElementById("no-results").hidden = positions.length > 0;
+
+        facets.forEach(function (facet) {
+            var tag = facet.dataset.tag;
+            var selected = selectedTags.indexOf(tag) !== -1;
+            facet.classList.toggle("selected", selected);
+            facet.disabled = !selected && !counts[tag];
+            facet.querySelector(".count").textContent = counts[tag] || 0;
+        });
+    }
+
+    facets.forEach(function (facet) {
+        facet.addEventListener("click", function () {
+            var tag = facet.dataset.tag;
+            var position = selectedTags.indexOf(tag);
+            if (position === -1) {
+                selectedTags.push(tag);
+            } else {
+                selectedTags.splice(position, 1);
+            }
+            update();
+        });
+    });
+    search.addEventListener("input", update);
+
+    // Tag links of the snippet pages lead to index.html#tag=<tag>
+    location.hash.replace(/^#/, "").split("&").forEach(function (part) {
+        var pair = part.split("=");
+        if (pair[0] === "tag" && pair[1]) {
+            selectedTags.push(decodeURIComponent(pair[1]));
+        }
+    });
+    update();
+})();
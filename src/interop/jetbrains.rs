@@ -0,0 +1,394 @@
+use crate::{
+    interop::{escape_xml, starts_placeholder, trigger_or_name, Imported},
+    snippet::CodeSnippet,
+};
+
+/// Context options of the languages, as (prefix of the options on import, option written on export, language)
+const CONTEXTS: &[(&str, &str, &str)] = &[
+    ("JAVA_SCRIPT", "JAVA_SCRIPT", "javascript"),
+    ("TYPESCRIPT", "TypeScript", "typescript"),
+    ("JAVA", "JAVA_CODE", "java"),
+    ("KOTLIN", "KOTLIN", "kotlin"),
+    ("PYTHON", "Python", "python"),
+    ("SHELL_SCRIPT", "SHELL_SCRIPT", "bash"),
+    ("HTML", "HTML", "html"),
+    ("CSS", "CSS", "css"),
+    ("SQL", "SQL", "sql"),
+    ("GO", "GO", "go"),
+    ("RUST", "RUST_ITEM", "rust"),
+    ("PHP", "PHP", "php"),
+    ("RUBY", "RUBY", "ruby"),
+];
+
+/// Context of the exported templates without a language
+const FALLBACK_CONTEXT: &str = "OTHER";
+/// Group of the exported template set, JetBrains IDEs expect the file to be named like it
+pub const EXPORT_GROUP: &str = "snippy";
+
+// Keys of `CodeSnippet::extra`
+/// Comma separated names of the context options that were enabled
+const CONTEXT_KEY: &str = "jetbrains.context";
+const REFORMAT_KEY: &str = "jetbrains.toReformat";
+const SHORTEN_KEY: &str = "jetbrains.toShortenFQNames";
+
+
+/// A `<variable>` of a template
+struct Variable {
+    name: String,
+    expression: String,
+    default_value: String,
+}
+
+
+/// Reads a Live Templates file (`<templateSet>`). Variables become placeholders: `$END$` is `$0`,
+/// the others are numbered in the order of their `<variable>` elements. Expressions that aren't
+/// a plain string or `enum(...)` can't be represented and are reported
+pub fn parse(text: &str) -> Result<Imported, String> {
+    let document = roxmltree::Document::parse(text).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    if !root.has_tag_name("templateSet") {
+        return Err(format!("expected <templateSet>, found <{}>", root.tag_name().name()));
+    };
+    let group = root.attribute("group").map(|g| g.split_whitespace().collect::<Vec<&str>>().join("-"));
+
+    let mut imported = Imported::default();
+    for template in root.children().filter(|n| n.has_tag_name("template")) {
+        let abbreviation = template.attribute("name").unwrap_or_default().to_string();
+        let Some(value) = template.attribute("value") else {
+            imported.skipped.push((abbreviation, String::from("has no value")));
+            continue;
+        };
+        let variables: Vec<Variable> = template
+            .children()
+            .filter(|n| n.has_tag_name("variable"))
+            .map(|n| Variable {
+                name: n.attribute("name").unwrap_or_default().to_string(),
+                expression: n.attribute("expression").unwrap_or_default().to_string(),
+                default_value: n.attribute("defaultValue").unwrap_or_default().to_string(),
+            })
+            .collect();
+
+        let mut snip = CodeSnippet::new(0);
+        snip.trigger = Some(abbreviation.clone()).filter(|a| !a.is_empty());
+        snip.description = template.attribute("description").map(String::from).filter(|d| !d.is_empty());
+        snip.name = snip.description.clone().unwrap_or(abbreviation);
+        let (code, notes) = placeholders_from_template(value, &variables);
+        snip.code = code;
+        imported.notes.extend(notes.into_iter().map(|note| (snip.name.clone(), note)));
+
+        let enabled_options: Vec<&str> = template
+            .descendants()
+            .filter(|n| n.has_tag_name("option") && n.attribute("value") == Some("true"))
+            .filter_map(|n| n.attribute("name"))
+            .collect();
+        snip.language = enabled_options.iter().find_map(|option| {
+            CONTEXTS
+                .iter()
+                .find(|(prefix, _, _)| option.to_uppercase().starts_with(prefix))
+                .map(|(_, _, language)| language.to_string())
+        });
+        if !enabled_options.is_empty() {
+            snip.extra.insert(CONTEXT_KEY.to_string(), enabled_options.join(","));
+        };
+        for (attribute, key) in [("toReformat", REFORMAT_KEY), ("toShortenFQNames", SHORTEN_KEY)] {
+            if let Some(value) = template.attribute(attribute) {
+                snip.extra.insert(key.to_string(), value.to_string());
+            };
+        };
+        if let Some(group) = &group {
+            snip.tags.push(group.clone());
+        };
+        imported.snippets.push(snip);
+    }
+    Ok(imported)
+}
+
+/// A Live Templates file holding the snippets in the group `EXPORT_GROUP`,
+/// and what of the snippets couldn't be represented as (name, note)
+pub fn write(snippets: &[&CodeSnippet]) -> (String, Vec<(String, String)>) {
+    let mut notes = vec![];
+    let mut text = format!("<templateSet group=\"{}\">\n", EXPORT_GROUP);
+    for snip in snippets {
+        let (value, variables, snip_notes) = template_from_placeholders(&snip.code);
+        notes.extend(snip_notes.into_iter().map(|note| (snip.name.clone(), note)));
+        let description = snip.description.as_deref().unwrap_or(&snip.name);
+        let reformat = snip.extra.get(REFORMAT_KEY).map(String::as_str).unwrap_or("false");
+        let shorten = snip.extra.get(SHORTEN_KEY).map(String::as_str).unwrap_or("true");
+        text.push_str(&format!(
+            "  <template name=\"{}\" value=\"{}\" description=\"{}\" toReformat=\"{}\" toShortenFQNames=\"{}\">\n",
+            escape_xml(&trigger_or_name(snip), true),
+            escape_xml(&value, true),
+            escape_xml(description, true),
+            reformat,
+            shorten,
+        ));
+        for variable in variables {
+            text.push_str(&format!(
+                "    <variable name=\"{}\" expression=\"{}\" defaultValue=\"{}\" alwaysStopAt=\"true\" />\n",
+                variable.name,
+                escape_xml(&variable.expression, true),
+                escape_xml(&variable.default_value, true),
+            ));
+        };
+        text.push_str("    <context>\n");
+        for option in context_options(snip) {
+            text.push_str(&format!("      <option name=\"{}\" value=\"true\" />\n", escape_xml(&option, true)));
+        };
+        text.push_str("    </context>\n  </template>\n");
+    };
+    text.push_str("</templateSet>\n");
+    (text, notes)
+}
+
+
+/// Turns `$NAME$` variables into snippy placeholders, returns the code and what couldn't be represented
+fn placeholders_from_template(value: &str, variables: &[Variable]) -> (String, Vec<String>) {
+    let mut notes = vec![];
+    // Variables used in the value without a <variable> element (like $SELECTION$) get the next numbers
+    let mut numbered: Vec<String> = variables.iter().map(|v| v.name.clone()).collect();
+    let mut seen: Vec<String> = vec![];
+    let mut code = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        code.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        // `$$` is a literal dollar sign
+        if let Some(after) = rest.strip_prefix('$') {
+            if starts_placeholder(after) {
+                code.push('\\');
+            };
+            code.push('$');
+            rest = after;
+            continue;
+        };
+        let Some(end) = rest.find('$') else {
+            code.push('$');
+            continue;
+        };
+        let name = &rest[..end];
+        rest = &rest[end + 1..];
+        if name == "END" {
+            code.push_str("$0");
+            continue;
+        };
+        let number = match numbered.iter().position(|n| n == name) {
+            Some(position) => position + 1,
+            None => {
+                notes.push(format!("${}$ is a predefined variable of the IDE, it became a plain placeholder", name));
+                numbered.push(name.to_string());
+                numbered.len()
+            },
+        };
+        if seen.iter().any(|s| s == name) {
+            code.push_str(&format!("${}", number));
+            continue;
+        };
+        seen.push(name.to_string());
+        let variable = variables.iter().find(|v| v.name == name);
+        let expression = variable.map(|v| v.expression.trim()).unwrap_or_default();
+        let default_value = variable.map(|v| v.default_value.trim()).unwrap_or_default();
+        if let Some(choices) = expression.strip_prefix("enum(").and_then(|e| e.strip_suffix(')')) {
+            let choices: Vec<String> = choices.split(',').map(|c| unquote(c.trim()).unwrap_or(c.trim().to_string())).collect();
+            code.push_str(&format!("${{{}|{}|}}", number, choices.join(",")));
+            continue;
+        };
+        if !expression.is_empty() {
+            notes.push(format!("the expression {} of ${}$ can't be evaluated by snippy", expression, name));
+        };
+        let default_text = match unquote(default_value) {
+            Some(text) => text,
+            None if default_value.is_empty() => String::new(),
+            None => {
+                notes.push(format!("the default value {} of ${}$ is an expression snippy can't evaluate", default_value, name));
+                String::new()
+            },
+        };
+        match default_text.is_empty() {
+            true => code.push_str(&format!("${}", number)),
+            false => code.push_str(&format!("${{{}:{}}}", number, default_text)),
+        };
+    }
+    code.push_str(rest);
+    (code, notes)
+}
+
+/// Turns snippy placeholders into `$VARn$` variables, returns the template value, its variables
+/// and what couldn't be represented. Shell variables like `$HOME` or `${HOME}` are kept as text
+fn template_from_placeholders(code: &str) -> (String, Vec<Variable>, Vec<String>) {
+    let mut notes: Vec<String> = vec![];
+    let mut variables: Vec<Variable> = vec![];
+    let mut value = String::new();
+    let chars: Vec<char> = code.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        // Escaped characters like `\$` are plain text
+        if let Some(escaped) = chars.get(i + 1).filter(|n| c == '\\' && "$}\\".contains(**n)) {
+            match escaped {
+                '$' => value.push_str("$$"),
+                _ => value.push(*escaped),
+            };
+            i += 2;
+            continue;
+        };
+        if c != '$' {
+            value.push(c);
+            i += 1;
+            continue;
+        };
+        // $1
+        let digits: String = chars[i + 1..].iter().take_while(|c| c.is_ascii_digit()).collect();
+        if !digits.is_empty() {
+            value.push_str(&add_variable(&mut variables, &digits, String::new(), String::new()));
+            i += 1 + digits.len();
+            continue;
+        };
+        // ${1}, ${1:default}, ${1|a,b|}
+        let digits: String = chars[i + 1..].iter().skip(1).take_while(|c| c.is_ascii_digit()).collect();
+        if chars.get(i + 1) == Some(&'{') && !digits.is_empty() {
+            let Some(close) = matching_brace(&chars, i + 1) else {
+                value.push_str("$$");
+                i += 1;
+                continue;
+            };
+            let inner: String = chars[i + 2 + digits.len()..close].iter().collect();
+            let placeholder = match inner.chars().next() {
+                None => add_variable(&mut variables, &digits, String::new(), String::new()),
+                Some(':') => {
+                    let default = &inner[1..];
+                    if default.contains('$') {
+                        notes.push(format!("the placeholders inside ${{{}:...}} became plain text", digits));
+                    };
+                    if digits == "0" && !default.is_empty() {
+                        notes.push(String::from("$END$ can't have a default value, it was dropped"));
+                    };
+                    add_variable(&mut variables, &digits, String::new(), format!("\"{}\"", default.replace('"', "\\\"")))
+                },
+                Some('|') => {
+                    let choices: Vec<String> = inner.trim_matches('|').split(',').map(|c| format!("\"{}\"", c)).collect();
+                    add_variable(&mut variables, &digits, format!("enum({})", choices.join(", ")), String::new())
+                },
+                Some(_) => {
+                    notes.push(format!("the transformation of placeholder {} was dropped", digits));
+                    add_variable(&mut variables, &digits, String::new(), String::new())
+                },
+            };
+            value.push_str(&placeholder);
+            i = close + 1;
+            continue;
+        };
+        value.push_str("$$");
+        i += 1;
+    }
+    (value, variables, notes)
+}
+
+/// `$END$` for placeholder 0, else `$VARn$`, adding the variable the first time the placeholder is used
+fn add_variable(variables: &mut Vec<Variable>, number: &str, expression: String, default_value: String) -> String {
+    if number.parse::<usize>() == Ok(0) {
+        return String::from("$END$");
+    };
+    let name = format!("VAR{}", number.trim_start_matches('0'));
+    match variables.iter_mut().find(|v| v.name == name) {
+        Some(variable) => {
+            // `${1:default}` can come after a plain `$1`
+            if variable.default_value.is_empty() {
+                variable.default_value = default_value;
+            };
+            if variable.expression.is_empty() {
+                variable.expression = expression;
+            };
+        },
+        None => variables.push(Variable { name: name.clone(), expression, default_value }),
+    };
+    format!("${}$", name)
+}
+
+/// Index of the `}` closing the `{` at `open`
+fn matching_brace(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                };
+            },
+            _ => (),
+        };
+        i += 1;
+    }
+    None
+}
+
+/// The text of a string literal like `"name"`
+fn unquote(expression: &str) -> Option<String> {
+    let text = expression.strip_prefix('"')?.strip_suffix('"')?;
+    Some(text.replace("\\\"", "\""))
+}
+
+/// The context options the snippet was imported with, else the option of its language
+fn context_options(snip: &CodeSnippet) -> Vec<String> {
+    if let Some(options) = snip.extra.get(CONTEXT_KEY) {
+        return options.split(',').map(String::from).collect();
+    };
+    let option = snip.language.as_deref().and_then(|language| {
+        CONTEXTS.iter().find(|(_, _, l)| *l == language).map(|(_, option, _)| *option)
+    });
+    vec![option.unwrap_or(FALLBACK_CONTEXT).to_string()]
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variable(name: &str, expression: &str, default_value: &str) -> Variable {
+        Variable { name: name.to_string(), expression: expression.to_string(), default_value: default_value.to_string() }
+    }
+
+    #[test]
+    fn placeholders_round_trip() {
+        let code = "for ${1:i} in ${2|a,b|} {\n\t$1 costs \\$5 of $HOME ${3:{x}}$0\n}";
+        let (value, variables, notes) = template_from_placeholders(code);
+        assert!(notes.is_empty());
+        assert_eq!(value, "for $VAR1$ in $VAR2$ {\n\t$VAR1$ costs $$5 of $$HOME $VAR3$$END$\n}");
+        assert_eq!(variables[1].expression, "enum(\"a\", \"b\")");
+        assert_eq!(placeholders_from_template(&value, &variables), (code.to_string(), vec![]));
+    }
+
+    #[test]
+    fn templates_round_trip() {
+        let mut snip = CodeSnippet::new(3);
+        snip.name = String::from("Print a value");
+        snip.description = Some(String::from("Print a value"));
+        snip.trigger = Some(String::from("pv"));
+        snip.language = Some(String::from("python"));
+        snip.code = String::from("print(\"${1:value}\" < $2)$0");
+        let (text, notes) = write(&[&snip]);
+        assert!(notes.is_empty());
+        let imported = parse(&text).unwrap();
+        let parsed = &imported.snippets[0];
+        assert_eq!((&parsed.name, &parsed.trigger, &parsed.language), (&snip.name, &snip.trigger, &snip.language));
+        assert_eq!(parsed.code, snip.code);
+        assert!(imported.notes.is_empty());
+    }
+
+    #[test]
+    fn unsupported_expressions_are_noted() {
+        let variables = [variable("USER", "user()", "\"me\""), variable("NOW", "", "date()")];
+        let (code, notes) = placeholders_from_template("$USER$ $NOW$ $SELECTION$", &variables);
+        assert_eq!(code, "${1:me} $2 $3");
+        assert_eq!(notes.len(), 3);
+        assert!(notes[0].contains("user()"));
+        assert!(notes[1].contains("date()"));
+        assert!(notes[2].contains("$SELECTION$"));
+
+        let (_, _, notes) = template_from_placeholders("${1/a/b/} ${0:end}");
+        assert_eq!(notes.len(), 2);
+    }
+}
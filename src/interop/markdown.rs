@@ -0,0 +1,234 @@
+use std::path::Path;
+
+use crate::{interop::Imported, language::normalize_language, snippet::CodeSnippet};
+
+/// Title of the exported document
+const DOCUMENT_TITLE: &str = "Snippets";
+
+/// Metadata lines written above the code block, read back when the document is imported
+const TAGS_LINE: &str = "- Tags: ";
+const TRIGGER_LINE: &str = "- Trigger: ";
+const DESCRIPTION_LINE: &str = "- Description: ";
+const SOURCE_LINE: &str = "- Source: ";
+
+
+/// Fence of a code block that is still open
+struct Fence {
+    character: char,
+    length: usize,
+    indent: usize,
+    info: String,
+    /// 1-based line of the first code line
+    start: usize,
+    lines: Vec<String>,
+}
+
+/// Harvests every fenced code block of the document. A block is named after the nearest heading above it
+/// (numbered if the heading has several blocks) or after the file, the info string becomes the language.
+/// The tags, trigger, description and source lines of documents written by `write` are read too,
+/// when they come right below the heading and before the block
+pub fn parse(text: &str, file_name: &str) -> Imported {
+    let mut imported = Imported::default();
+    let mut heading: Option<String> = None;
+    let mut blocks_under_heading = 0;
+    let mut metadata = CodeSnippet::new(0);
+    // Below a heading until other text or a code block comes
+    let mut in_metadata = false;
+    let mut previous_line = "";
+    let mut fence: Option<Fence> = None;
+    let file_stem = Path::new(file_name).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if let Some(open) = fence.as_mut() {
+            if is_closing_fence(line, open) {
+                let open = fence.take().unwrap();
+                blocks_under_heading += 1;
+                let base_name = heading.clone().unwrap_or_else(|| file_stem.clone());
+                let mut snip = CodeSnippet::new(0);
+                snip.name = match blocks_under_heading {
+                    1 => base_name,
+                    n => format!("{} ({})", base_name, n),
+                };
+                snip.code = open.lines.join("\n");
+                snip.language = open.info.split_whitespace().next().map(normalize_language);
+                snip.tags = std::mem::take(&mut metadata.tags);
+                snip.trigger = metadata.trigger.take();
+                snip.description = metadata.description.take();
+                snip.source = metadata.source.take().or_else(|| Some(match open.lines.len() {
+                    0 | 1 => format!("{}:{}", file_name, open.start),
+                    n => format!("{}:{}-{}", file_name, open.start, open.start + n - 1),
+                }));
+                imported.snippets.push(snip);
+                previous_line = "";
+            } else {
+                // Lines of an indented fence lose as much of their indentation as the fence has
+                let indent = line.len() - line.trim_start_matches(' ').len();
+                open.lines.push(line[indent.min(open.indent)..].to_string());
+            };
+            continue;
+        };
+
+        if let Some(open) = opening_fence(line, i + 2) {
+            fence = Some(open);
+            in_metadata = false;
+        } else if let Some(title) = atx_heading(line) {
+            heading = Some(title);
+            blocks_under_heading = 0;
+            metadata = CodeSnippet::new(0);
+            in_metadata = true;
+        } else if is_setext_underline(line) && !previous_line.trim().is_empty() && atx_heading(previous_line).is_none() {
+            heading = Some(previous_line.trim().to_string());
+            blocks_under_heading = 0;
+            metadata = CodeSnippet::new(0);
+            in_metadata = true;
+        } else if let (true, Some(tags)) = (in_metadata, line.strip_prefix(TAGS_LINE)) {
+            metadata.tags = tags.split(',').map(|t| t.trim().trim_matches('`').to_string()).filter(|t| !t.is_empty()).collect();
+        } else if let (true, Some(trigger)) = (in_metadata, line.strip_prefix(TRIGGER_LINE)) {
+            metadata.trigger = Some(trigger.trim().trim_matches('`').to_string());
+        } else if let (true, Some(description)) = (in_metadata, line.strip_prefix(DESCRIPTION_LINE)) {
+            metadata.description = Some(description.trim().to_string());
+        } else if let (true, Some(source)) = (in_metadata, line.strip_prefix(SOURCE_LINE)) {
+            metadata.source = Some(source.trim().to_string());
+        } else if !line.trim().is_empty() {
+            // Text between the heading and the block, the list lines belong to it
+            metadata = CodeSnippet::new(0);
+            in_metadata = false;
+        };
+        previous_line = line;
+    };
+
+    if let Some(open) = fence {
+        let name = heading.unwrap_or(file_stem);
+        imported.skipped.push((name, format!("the code block starting at line {} is never closed", open.start - 1)));
+    };
+    imported
+}
+
+/// A document with a heading per snippet, its metadata and its code in a fenced block
+pub fn write(snippets: &[&CodeSnippet]) -> String {
+    let mut text = format!("# {}\n", DOCUMENT_TITLE);
+    for snip in snippets {
+        text.push_str(&format!("\n## {}\n\n", snip.name.replace('\n', " ")));
+        let mut has_metadata = false;
+        if !snip.tags.is_empty() {
+            let tags: Vec<String> = snip.tags.iter().map(|t| format!("`{}`", t)).collect();
+            text.push_str(&format!("{}{}\n", TAGS_LINE, tags.join(", ")));
+            has_metadata = true;
+        };
+        if let Some(trigger) = &snip.trigger {
+            text.push_str(&format!("{}`{}`\n", TRIGGER_LINE, trigger));
+            has_metadata = true;
+        };
+        if let Some(description) = &snip.description {
+            text.push_str(&format!("{}{}\n", DESCRIPTION_LINE, description.replace('\n', " ")));
+            has_metadata = true;
+        };
+        if let Some(source) = &snip.source {
+            text.push_str(&format!("{}{}\n", SOURCE_LINE, source));
+            has_metadata = true;
+        };
+        if has_metadata {
+            text.push('\n');
+        };
+
+        // The fence has to be longer than any run of backticks in the code
+        let longest_run = snip.code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+        let fence = "`".repeat(std::cmp::max(3, longest_run + 1));
+        text.push_str(&format!("{}{}\n{}\n{}\n", fence, snip.language.as_deref().unwrap_or(""), snip.code, fence));
+    };
+    text
+}
+
+
+/// The fence if the line opens a fenced code block, `start` is the line the code starts in
+fn opening_fence(line: &str, start: usize) -> Option<Fence> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    };
+    let rest = &line[indent..];
+    let character = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = rest.len() - rest.trim_start_matches(character).len();
+    let info = rest[length..].trim();
+    // Backtick fences can't have backticks in the info string, otherwise it's inline code
+    if length < 3 || (character == '`' && info.contains('`')) {
+        return None;
+    };
+    Some(Fence { character, length, indent, info: info.to_string(), start, lines: vec![] })
+}
+
+fn is_closing_fence(line: &str, fence: &Fence) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return false;
+    };
+    let length = trimmed.len() - trimmed.trim_start_matches(fence.character).len();
+    length >= fence.length && trimmed[length..].trim().is_empty()
+}
+
+/// The text of a `#` to `######` heading, without the closing `#`s
+fn atx_heading(line: &str) -> Option<String> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    };
+    let level = trimmed.len() - trimmed.trim_start_matches('#').len();
+    let rest = &trimmed[level..];
+    if level == 0 || level > 6 || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    };
+    // Closing `#`s need a space before them, `C#` stays `C#`
+    let title = rest.trim();
+    let without_closing = title.trim_end_matches('#');
+    let title = match without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        true => without_closing.trim_end(),
+        false => title,
+    };
+    match title.is_empty() {
+        true => None,
+        false => Some(title.to_string()),
+    }
+}
+
+/// A line of `=` or `-` that turns the line above into a heading
+fn is_setext_underline(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && (trimmed.chars().all(|c| c == '=') || trimmed.chars().all(|c| c == '-'))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut snip = CodeSnippet::new(0);
+        snip.name = String::from("List pods");
+        snip.tags = vec![String::from("k8s"), String::from("cli")];
+        snip.trigger = Some(String::from("kgp"));
+        snip.description = Some(String::from("Pods of every namespace"));
+        snip.source = Some(String::from("notes/k8s.md:12-14"));
+        snip.language = Some(String::from("bash"));
+        snip.code = String::from("kubectl get pods \\\n  --all-namespaces");
+        let imported = parse(&write(&[&snip]), "snippets.md");
+        assert_eq!(imported.snippets, vec![snip]);
+    }
+
+    #[test]
+    fn metadata_only_right_below_the_heading() {
+        let text = "## Deploy\n\nRun it like this:\n- Tags: `prod`\n\n```sh\nmake deploy\n```\n\n- Tags: `later`\n\n```sh\nmake clean\n```\n";
+        let imported = parse(text, "notes.md");
+        assert_eq!(imported.snippets.len(), 2);
+        assert!(imported.snippets.iter().all(|s| s.tags.is_empty()));
+        assert_eq!(imported.snippets[0].source.as_deref(), Some("notes.md:7"));
+
+        let text = "## Deploy\n\n- Tags: `prod`\n- Source: Makefile:3\n\n```sh\nmake deploy\n```\n\n```sh\nmake clean\n```\n";
+        let imported = parse(text, "notes.md");
+        assert_eq!(imported.snippets[0].tags, vec![String::from("prod")]);
+        assert_eq!(imported.snippets[0].source.as_deref(), Some("Makefile:3"));
+        assert!(imported.snippets[1].tags.is_empty());
+        assert_eq!(imported.snippets[1].source.as_deref(), Some("notes.md:11"));
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{interop::Imported, language::normalize_language, snippet::CodeSnippet};
+
+/// Languages massCode uses for fragments without syntax
+const PLAIN_TEXT: &[&str] = &["text", "plain_text", "plaintext"];
+
+/// Tag of the snippets marked as favorites
+const FAVORITE_TAG: &str = "favorite";
+
+
+/// Reads massCode's `db.json`. Every fragment of a snippet becomes a snippet of its own, the folder (and its parent folders)
+/// and the tags become tags. Snippets in the trash are skipped
+pub fn parse(text: &str, path: &str) -> Result<Imported, String> {
+    let db: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let snippets = db["snippets"].as_array().ok_or("no \"snippets\", this doesn't look like a massCode db.json")?;
+    let tags: HashMap<&str, &str> = db["tags"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|t| Some((t["id"].as_str()?, t["name"].as_str()?)))
+        .collect();
+    let folders: HashMap<&str, &Value> = db["folders"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|f| Some((f["id"].as_str()?, f)))
+        .collect();
+
+    let mut imported = Imported::default();
+    for entry in snippets {
+        let name = entry["name"].as_str().filter(|n| !n.trim().is_empty()).unwrap_or("Untitled snippet");
+        if entry["isDeleted"].as_bool() == Some(true) {
+            imported.skipped.push((name.to_string(), String::from("is in the trash")));
+            continue;
+        };
+        let id = entry["id"].as_str().unwrap_or_default();
+
+        let mut snippet_tags: Vec<String> = folder_path(&folders, entry["folderId"].as_str());
+        for tag_id in entry["tagsIds"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+            match tags.get(tag_id) {
+                Some(tag) => snippet_tags.push(tag.to_string()),
+                None => imported.notes.push((name.to_string(), format!("the tag {} isn't in the database", tag_id))),
+            };
+        };
+        if entry["isFavorites"].as_bool() == Some(true) {
+            snippet_tags.push(FAVORITE_TAG.to_string());
+        };
+        snippet_tags.dedup();
+
+        let fragments = entry["content"].as_array().map(Vec::as_slice).unwrap_or_default();
+        if fragments.is_empty() {
+            imported.skipped.push((name.to_string(), String::from("has no fragments")));
+            continue;
+        };
+        for (i, fragment) in fragments.iter().enumerate() {
+            let mut snip = CodeSnippet::new(0);
+            snip.name = match (fragments.len(), fragment["label"].as_str()) {
+                (1, _) => name.to_string(),
+                (_, Some(label)) if !label.trim().is_empty() => format!("{} - {}", name, label.trim()),
+                _ => format!("{} - Fragment {}", name, i + 1),
+            };
+            snip.code = fragment["value"].as_str().unwrap_or_default().to_string();
+            snip.language = fragment["language"]
+                .as_str()
+                .filter(|l| !PLAIN_TEXT.contains(l))
+                .map(normalize_language);
+            snip.tags = snippet_tags.clone();
+            snip.description = entry["description"].as_str().filter(|d| !d.trim().is_empty()).map(String::from);
+            snip.source = Some(format!("{}#{}", path, id));
+            imported.snippets.push(snip);
+        };
+    };
+    Ok(imported)
+}
+
+
+/// Names of the folder and its parents, outermost first
+fn folder_path(folders: &HashMap<&str, &Value>, folder_id: Option<&str>) -> Vec<String> {
+    let mut names = vec![];
+    let mut next = folder_id;
+    while let Some(folder) = next.and_then(|id| folders.get(id)) {
+        // A folder that is its own ancestor would never end
+        if names.len() > folders.len() {
+            break;
+        };
+        if let Some(name) = folder["name"].as_str() {
+            names.insert(0, name.to_string());
+        };
+        next = folder["parentId"].as_str();
+    }
+    names
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DB: &str = r#"{
+        "folders": [{"id": "f1", "name": "Work", "parentId": null}, {"id": "f2", "name": "Shell", "parentId": "f1"}],
+        "tags": [{"id": "t1", "name": "ops"}],
+        "snippets": [
+            {
+                "id": "s1", "name": "Deploy", "folderId": "f2", "tagsIds": ["t1"], "isFavorites": true, "isDeleted": false,
+                "content": [
+                    {"label": "Build", "language": "python", "value": "build()"},
+                    {"label": " ", "language": "text", "value": "notes"}
+                ]
+            },
+            {"id": "s2", "name": "Old", "isDeleted": true, "content": [{"label": "x", "language": "js", "value": "1"}]},
+            {"id": "s3", "name": "", "content": [{"label": "Only", "language": "rust", "value": "fn main() {}"}]}
+        ]
+    }"#;
+
+    #[test]
+    fn fragments_become_snippets() {
+        let imported = parse(DB, "db.json").unwrap();
+        let names: Vec<&str> = imported.snippets.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Deploy - Build", "Deploy - Fragment 2", "Untitled snippet"]);
+        let tags = vec![String::from("Work"), String::from("Shell"), String::from("ops"), String::from("favorite")];
+        assert_eq!(imported.snippets[0].tags, tags);
+        assert_eq!(imported.snippets[0].language.as_deref(), Some("python"));
+        assert_eq!(imported.snippets[1].language, None);
+        assert_eq!(imported.snippets[1].source.as_deref(), Some("db.json#s1"));
+    }
+
+    #[test]
+    fn trashed_snippets_are_skipped() {
+        let imported = parse(DB, "db.json").unwrap();
+        assert_eq!(imported.skipped, vec![(String::from("Old"), String::from("is in the trash"))]);
+        assert!(imported.snippets.iter().all(|s| !s.name.starts_with("Old")));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{
+    interop::{escape_placeholders, Imported},
+    snippet::CodeSnippet,
+};
+
+/// Prefix of the keys of `CodeSnippet::extra` holding the command that suggests the values of a variable
+const VARIABLE_PREFIX: &str = "navi.var.";
+
+/// Language of every imported command
+const LANGUAGE: &str = "bash";
+
+
+/// A command of the cheatsheet, `start` is its first line (counted from 1)
+struct Command {
+    description: Option<String>,
+    lines: Vec<String>,
+    start: usize,
+}
+
+/// A `%` block of the cheatsheet, its variables apply to all of its commands
+#[derive(Default)]
+struct Section {
+    tags: Vec<String>,
+    commands: Vec<Command>,
+    /// Name and the command after `$ name:`
+    variables: Vec<(String, String)>,
+    extends: Vec<String>,
+}
+
+
+/// Reads a navi `.cheat` file: `%` lines hold the tags, `#` lines describe the command below them, which ends at a blank line,
+/// `$` lines tell where the values of a variable come from and `;` lines are comments.
+/// Variables like `<branch>` become placeholders, their `$` lines are kept but snippy can't run them
+pub fn parse(text: &str, path: &str) -> Imported {
+    let mut sections: Vec<Section> = vec![Section::default()];
+    let mut description: Option<String> = None;
+    let mut command: Option<Command> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let section = sections.last_mut().unwrap();
+        let trimmed = line.trim();
+        let is_command_line = !trimmed.is_empty() && !trimmed.starts_with(['%', '#', '$', ';', '@']);
+        if !is_command_line {
+            if let Some(finished) = command.take() {
+                section.commands.push(finished);
+            };
+        };
+
+        if trimmed.is_empty() {
+            description = None;
+        } else if let Some(tags) = trimmed.strip_prefix('%') {
+            sections.push(Section {
+                tags: tags.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect(),
+                ..Default::default()
+            });
+            description = None;
+        } else if let Some(text) = trimmed.strip_prefix('#') {
+            description = Some(text.trim().to_string());
+        } else if let Some(variable) = trimmed.strip_prefix('$') {
+            if let Some((name, source)) = variable.split_once(':') {
+                section.variables.push((name.trim().to_string(), source.trim().to_string()));
+            };
+        } else if let Some(extends) = trimmed.strip_prefix('@') {
+            section.extends.push(extends.trim().to_string());
+        } else if trimmed.starts_with(';') {
+            continue;
+        } else {
+            let open = command.get_or_insert_with(|| Command { description: description.take(), lines: vec![], start: i + 1 });
+            open.lines.push(line.to_string());
+        };
+    };
+    if let Some(finished) = command {
+        sections.last_mut().unwrap().commands.push(finished);
+    };
+
+    let mut imported = Imported::default();
+    for section in sections {
+        for command in section.commands {
+            let code = command.lines.join("\n");
+            let mut snip = CodeSnippet::new(0);
+            snip.name = command.description.clone().unwrap_or_else(|| command.lines[0].trim().to_string());
+            snip.tags = section.tags.clone();
+            snip.language = Some(LANGUAGE.to_string());
+            snip.source = Some(format!("{}:{}", path, command.start));
+            let (placeholder_code, variables) = placeholders_from_variables(&code);
+            snip.code = placeholder_code;
+            for variable in variables {
+                let Some((_, source)) = section.variables.iter().find(|(name, _)| *name == variable) else {
+                    continue;
+                };
+                imported.notes.push((snip.name.clone(), format!("the values of <{}> come from `{}`, snippy can't suggest them", variable, source)));
+                snip.extra.insert(format!("{}{}", VARIABLE_PREFIX, variable), source.clone());
+            };
+            imported.snippets.push(snip);
+        };
+        for extends in &section.extends {
+            let name = section.tags.join(", ");
+            imported.notes.push((name, format!("the variables of the cheats tagged '{}' (@) aren't imported", extends)));
+        };
+    };
+    imported
+}
+
+
+/// Turns `<name>` variables into placeholders, returns the code and the variables in order
+fn placeholders_from_variables(code: &str) -> (String, Vec<String>) {
+    let mut names: Vec<String> = vec![];
+    let mut result = String::new();
+    let mut rest = code;
+    while let Some(start) = rest.find('<') {
+        result.push_str(&escape_placeholders(&rest[..start]));
+        let after = &rest[start + 1..];
+        let name = after.find('>').map(|end| &after[..end]).filter(|n| is_variable_name(n));
+        let Some(name) = name else {
+            result.push('<');
+            rest = after;
+            continue;
+        };
+        rest = &after[name.len() + 1..];
+        match names.iter().position(|n| n == name) {
+            Some(position) => result.push_str(&format!("${}", position + 1)),
+            None => {
+                names.push(name.to_string());
+                result.push_str(&format!("${{{}:{}}}", names.len(), name));
+            },
+        };
+    }
+    result.push_str(&escape_placeholders(rest));
+    (result, names)
+}
+
+/// Navi variables start with a letter or `_`, then letters, digits, `_` and `-`
+fn is_variable_name(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHEAT: &str = "% git, vcs
+
+# Checkout a branch
+git checkout <branch>
+  && git log -1 <branch>
+
+$ branch: git branch | awk '{print $NF}'
+
+git status
+";
+
+    #[test]
+    fn commands_end_at_a_blank_line() {
+        let imported = parse(CHEAT, "git.cheat");
+        assert_eq!(imported.snippets.len(), 2);
+        let checkout = &imported.snippets[0];
+        assert_eq!(checkout.name, "Checkout a branch");
+        assert_eq!(checkout.code, "git checkout ${1:branch}\n  && git log -1 $1");
+        assert_eq!(checkout.tags, vec![String::from("git"), String::from("vcs")]);
+        assert_eq!(checkout.source.as_deref(), Some("git.cheat:4"));
+        let status = &imported.snippets[1];
+        assert_eq!((status.name.as_str(), status.source.as_deref()), ("git status", Some("git.cheat:9")));
+    }
+
+    #[test]
+    fn variables_become_notes() {
+        let imported = parse(CHEAT, "git.cheat");
+        assert_eq!(imported.notes.len(), 1);
+        assert_eq!(imported.notes[0].0, "Checkout a branch");
+        assert!(imported.notes[0].1.contains("git branch | awk '{print $NF}'"));
+        let source = imported.snippets[0].extra.get("navi.var.branch");
+        assert_eq!(source.map(String::as_str), Some("git branch | awk '{print $NF}'"));
+    }
+}
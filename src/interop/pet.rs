@@ -0,0 +1,172 @@
+use serde_derive::Deserialize;
+
+use crate::{
+    interop::{escape_placeholders, Imported},
+    snippet::CodeSnippet,
+};
+
+/// Key of `CodeSnippet::extra` holding the example output pet shows with the command
+const OUTPUT_KEY: &str = "pet.output";
+
+/// Language of every imported command
+const LANGUAGE: &str = "bash";
+
+
+#[derive(Deserialize)]
+struct PetFile {
+    #[serde(default)]
+    snippets: Vec<PetSnippet>,
+}
+
+#[derive(Deserialize)]
+struct PetSnippet {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    command: String,
+    #[serde(default)]
+    tag: Vec<String>,
+    #[serde(default)]
+    output: String,
+}
+
+
+/// Reads pet's `snippet.toml`. The description becomes the name, parameters like `<name>`, `<name=default>`
+/// and `<name=|_one_||_two_|>` become placeholders. The source of each snippet is the line of its `[[snippets]]`
+pub fn parse(text: &str, path: &str) -> Result<Imported, String> {
+    let file: PetFile = toml::from_str(text).map_err(|e| e.to_string())?;
+    let header_lines: Vec<usize> = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.trim() == "[[snippets]]")
+        .map(|(i, _)| i + 1)
+        .collect();
+
+    // Sources only get a line if every entry has a header to point to
+    let entry_count = file.snippets.len();
+    let mut imported = Imported::default();
+    for (i, entry) in file.snippets.into_iter().enumerate() {
+        let name = match entry.description.trim() {
+            "" => entry.command.lines().next().unwrap_or_default().trim().to_string(),
+            description => description.to_string(),
+        };
+        if entry.command.trim().is_empty() {
+            imported.skipped.push((name, String::from("has no command")));
+            continue;
+        };
+        let mut snip = CodeSnippet::new(0);
+        snip.name = name;
+        snip.code = placeholders_from_parameters(&entry.command);
+        snip.tags = entry.tag;
+        snip.language = Some(LANGUAGE.to_string());
+        if !entry.output.trim().is_empty() {
+            snip.extra.insert(OUTPUT_KEY.to_string(), entry.output);
+        };
+        snip.source = Some(match (header_lines.len() == entry_count, header_lines.get(i)) {
+            (true, Some(line)) => format!("{}:{}", path, line),
+            _ => path.to_string(),
+        });
+        imported.snippets.push(snip);
+    };
+    Ok(imported)
+}
+
+
+/// Turns pet parameters into placeholders, a parameter used twice gets the same number.
+/// Like pet, `<` and `>` with spaces inside them (like redirections) aren't parameters
+fn placeholders_from_parameters(command: &str) -> String {
+    let mut names: Vec<String> = vec![];
+    let mut code = String::new();
+    let mut rest = command;
+    while let Some(start) = rest.find('<') {
+        code.push_str(&escape_placeholders(&rest[..start]));
+        let after = &rest[start + 1..];
+        let parameter = after.find('>').map(|end| &after[..end]).filter(|p| is_parameter(p));
+        let Some(parameter) = parameter else {
+            code.push('<');
+            rest = after;
+            continue;
+        };
+        rest = &after[parameter.len() + 1..];
+
+        let (name, default) = match parameter.split_once('=') {
+            Some((name, default)) => (name, Some(default)),
+            None => (parameter, None),
+        };
+        if let Some(position) = names.iter().position(|n| n == name) {
+            code.push_str(&format!("${}", position + 1));
+            continue;
+        };
+        names.push(name.to_string());
+        let number = names.len();
+        let choices = default.and_then(|d| d.strip_prefix("|_")).and_then(|d| d.strip_suffix("_|"));
+        match (choices, default) {
+            (Some(choices), _) => {
+                let choices: Vec<String> = choices.split("_||_").map(|c| escape_in_placeholder(c, ",|")).collect();
+                code.push_str(&format!("${{{}|{}|}}", number, choices.join(",")));
+            },
+            (None, Some(default)) if !default.is_empty() => {
+                code.push_str(&format!("${{{}:{}}}", number, escape_in_placeholder(default, "}")));
+            },
+            _ => code.push_str(&format!("${{{}:{}}}", number, escape_in_placeholder(name, "}"))),
+        };
+    }
+    code.push_str(&escape_placeholders(rest));
+    code
+}
+
+fn is_parameter(text: &str) -> bool {
+    !text.is_empty()
+        && !text.contains(['<', '\n'])
+        && !text.starts_with(char::is_whitespace)
+        && !text.ends_with(char::is_whitespace)
+}
+
+/// Escapes `$`, `\` and the characters that end the part of the placeholder the text goes into
+fn escape_in_placeholder(text: &str, special: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '$' || c == '\\' || special.contains(c) {
+            escaped.push('\\');
+        };
+        escaped.push(c);
+    };
+    escaped
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNIPPETS: &str = r#"
+[[snippets]]
+  description = "Copy a file"
+  command = "scp <file> <host=|_web_||_db_|>:<file> > /dev/null && echo $1"
+  tag = ["net"]
+  output = ""
+
+[[snippets]]
+  description = "Empty"
+  command = " "
+"#;
+
+    #[test]
+    fn parameters_become_placeholders() {
+        let imported = parse(SNIPPETS, "snippet.toml").unwrap();
+        let snip = &imported.snippets[0];
+        assert_eq!(snip.name, "Copy a file");
+        // The second <file> is the same placeholder, `$1` of the shell stays as it is
+        assert_eq!(snip.code, "scp ${1:file} ${2|web,db|}:$1 > /dev/null && echo \\$1");
+        assert_eq!(snip.tags, vec![String::from("net")]);
+        assert_eq!(snip.source.as_deref(), Some("snippet.toml:2"));
+        assert_eq!(imported.skipped, vec![(String::from("Empty"), String::from("has no command"))]);
+    }
+
+    #[test]
+    fn defaults_and_special_characters() {
+        assert_eq!(placeholders_from_parameters("ls <dir=~/$HOME>"), "ls ${1:~/\\$HOME}");
+        assert_eq!(placeholders_from_parameters("echo <a=|_x,y_||_z_|>"), "echo ${1|x\\,y,z|}");
+        assert_eq!(placeholders_from_parameters("cat < in > out"), "cat < in > out");
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    interop::{escape_xml, trigger_or_name, Imported},
+    language::known_language,
+    snippet::CodeSnippet,
+};
+
+/// Sublime scopes of the languages whose scope isn't `source.<language>`, as (language, scope)
+const SCOPES: &[(&str, &str)] = &[
+    ("bash", "source.shell.bash"),
+    ("javascript", "source.js"),
+    ("typescript", "source.ts"),
+    ("cpp", "source.c++"),
+    ("csharp", "source.cs"),
+    ("html", "text.html"),
+    ("markdown", "text.html.markdown"),
+];
+
+/// Key of `CodeSnippet::extra` holding the scope selector, which can say more than the language
+const SCOPE_KEY: &str = "sublime.scope";
+
+
+/// Reads a `.sublime-snippet` file. Sublime uses the same placeholders as snippy (`$1`, `${2:name}`),
+/// so the content is taken as it is. Snippets without a description are named after the trigger or the file
+pub fn parse(text: &str, file_stem: &str) -> Result<Imported, String> {
+    let document = roxmltree::Document::parse(text).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    if !root.has_tag_name("snippet") {
+        return Err(format!("expected <snippet>, found <{}>", root.tag_name().name()));
+    };
+    let child_text = |name: &str| {
+        root.children()
+            .find(|n| n.has_tag_name(name))
+            .map(|n| n.children().filter_map(|c| c.text()).collect::<String>())
+    };
+
+    let mut imported = Imported::default();
+    let Some(content) = child_text("content") else {
+        imported.skipped.push((file_stem.to_string(), String::from("has no <content>")));
+        return Ok(imported);
+    };
+    let mut snip = CodeSnippet::new(0);
+    snip.code = content;
+    snip.trigger = child_text("tabTrigger").map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    snip.description = child_text("description").map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+    snip.name = snip.description.clone().or(snip.trigger.clone()).unwrap_or_else(|| file_stem.to_string());
+    if let Some(scope) = child_text("scope").map(|s| s.trim().to_string()).filter(|s| !s.is_empty()) {
+        snip.language = language_from_scope(&scope);
+        if snip.language.is_none() {
+            imported.notes.push((snip.name.clone(), format!("the scope '{}' has no language snippy knows, it's only kept for exporting", scope)));
+        };
+        snip.extra.insert(SCOPE_KEY.to_string(), scope);
+    };
+    imported.snippets.push(snip);
+    Ok(imported)
+}
+
+/// One `.sublime-snippet` file per snippet, as (file name, contents)
+pub fn write(snippets: &[&CodeSnippet]) -> Vec<(String, String)> {
+    let mut files: Vec<(String, String)> = vec![];
+    for snip in snippets {
+        let trigger = trigger_or_name(snip);
+        let mut text = String::from("<snippet>\n");
+        // `]]>` would end the CDATA section, so it's split across two sections
+        text.push_str(&format!("    <content><![CDATA[{}]]></content>\n", snip.code.replace("]]>", "]]]]><![CDATA[>")));
+        text.push_str(&format!("    <tabTrigger>{}</tabTrigger>\n", escape_xml(&trigger, false)));
+        if let Some(scope) = scope_for(snip) {
+            text.push_str(&format!("    <scope>{}</scope>\n", escape_xml(&scope, false)));
+        };
+        let description = snip.description.as_deref().unwrap_or(&snip.name);
+        text.push_str(&format!("    <description>{}</description>\n", escape_xml(description, false)));
+        text.push_str("</snippet>\n");
+
+        let stem: String = trigger.chars().map(|c| if c.is_alphanumeric() || "-_".contains(c) {c} else {'_'}).collect();
+        let mut file_name = format!("{}.sublime-snippet", stem);
+        let mut counter = 2;
+        while files.iter().any(|(name, _)| *name == file_name) {
+            file_name = format!("{}-{}.sublime-snippet", stem, counter);
+            counter += 1;
+        }
+        files.push((file_name, text));
+    };
+    files
+}
+
+
+/// The language of the first scope in the selector, e.g. `bash` for `source.shell.bash` or `html` for `text.html.basic`
+fn language_from_scope(selector: &str) -> Option<String> {
+    let scope = selector.split([',', ' ']).find(|s| s.starts_with("source.") || s.starts_with("text."))?;
+    if let Some((language, _)) = SCOPES.iter().find(|(_, s)| *s == scope) {
+        return Some(language.to_string());
+    };
+    let parts: Vec<&str> = scope.split('.').skip(1).collect();
+    parts.into_iter().rev().find_map(known_language).map(String::from)
+}
+
+fn scope_for(snip: &CodeSnippet) -> Option<String> {
+    if let Some(scope) = snip.extra.get(SCOPE_KEY) {
+        return Some(scope.clone());
+    };
+    let language = snip.language.as_deref()?;
+    let scope = SCOPES
+        .iter()
+        .find(|(l, _)| *l == language)
+        .map(|(_, scope)| scope.to_string())
+        .unwrap_or_else(|| format!("source.{}", language));
+    Some(scope)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_from_scopes() {
+        assert_eq!(language_from_scope("source.python").as_deref(), Some("python"));
+        assert_eq!(language_from_scope("source.shell.bash").as_deref(), Some("bash"));
+        assert_eq!(language_from_scope("text.html.markdown").as_deref(), Some("markdown"));
+        // The first source or text scope of a selector tells
+        assert_eq!(language_from_scope("meta.function source.rust, source.go").as_deref(), Some("rust"));
+        assert_eq!(language_from_scope("source.python.django").as_deref(), Some("python"));
+        assert_eq!(language_from_scope("source.made-up"), None);
+        assert_eq!(language_from_scope("meta.block"), None);
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::{
+    interop::{trigger_or_name, Imported},
+    language::normalize_language,
+    snippet::CodeSnippet,
+};
+
+/// Vim filetypes that differ from the language names snippy uses, as (filetype, language)
+const FILETYPES: &[(&str, &str)] = &[
+    ("sh", "bash"),
+    ("cs", "csharp"),
+    ("make", "makefile"),
+    ("ps1", "powershell"),
+];
+
+/// Filetype of the snippets that are available everywhere
+const ALL_FILETYPES: &str = "all";
+
+// Keys of `CodeSnippet::extra`
+const ULTISNIPS_OPTIONS: &str = "ultisnips.options";
+const ULTISNIPS_PRIORITY: &str = "ultisnips.priority";
+/// `context`, `pre_expand`, `post_expand` and `post_jump` lines in front of the snippet
+const ULTISNIPS_ACTIONS: &str = "ultisnips.actions";
+/// `extends`, `clearsnippets` and `global` blocks of the file the snippet came from
+const ULTISNIPS_HEADER: &str = "ultisnips.header";
+/// `extends` and `version` lines of the file the snippet came from
+const SNIPMATE_HEADER: &str = "snipmate.header";
+
+/// Characters that can quote an UltiSnips trigger containing whitespace
+const TRIGGER_QUOTES: &[char] = &['"', '!', '|', '\'', '/', '%', '#'];
+
+/// Exported files as (file name, contents)
+type Files = Vec<(String, String)>;
+
+// Comment lines in front of a snippet, for what the snippet formats have no place for
+const NAME_COMMENT: &str = "# name:";
+const TAGS_COMMENT: &str = "# tags:";
+
+
+/// The filetype of a `.snippets` file: the name of the directory in `ft/foo.snippets`,
+/// else the file name up to the first `_` (`ft.snippets` and `ft_foo.snippets`)
+pub fn filetype_from_path(path: &Path, root: &Path) -> String {
+    let in_subdirectory = path.parent().is_some_and(|parent| parent != root && parent.starts_with(root));
+    let name = match in_subdirectory {
+        true => path.parent().and_then(|p| p.file_name()),
+        false => path.file_stem(),
+    };
+    let name = name.and_then(|n| n.to_str()).unwrap_or(ALL_FILETYPES);
+    name.split('_').next().unwrap_or(name).to_string()
+}
+
+/// Reads an UltiSnips `.snippets` file. The snippets get the filetype as tag and (unless it's `all`) as language.
+/// Options, priorities and the global blocks of the file are kept in `CodeSnippet::extra`
+pub fn parse_ultisnips(text: &str, filetype: &str) -> Imported {
+    let mut imported = Imported::default();
+    let mut header = vec![];
+    let mut priority: Option<String> = None;
+    let mut actions = vec![];
+    let mut comments = Comments::default();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        if line == "snippet" || line.starts_with("snippet ") || line.starts_with("snippet\t") {
+            let (body, closed) = take_block(&mut lines, "endsnippet");
+            let (trigger, description, options) = match parse_ultisnips_header(&line["snippet".len()..]) {
+                Ok(header) => header,
+                Err(reason) => {
+                    imported.skipped.push((line.to_string(), reason));
+                    continue;
+                },
+            };
+            if !closed {
+                imported.skipped.push((trigger, String::from("endsnippet is missing")));
+                continue;
+            };
+            let mut snip = snippet_from(&trigger, description, filetype);
+            std::mem::take(&mut comments).apply(&mut snip);
+            snip.code = body.join("\n");
+            if !options.is_empty() {
+                snip.extra.insert(ULTISNIPS_OPTIONS.to_string(), options);
+            };
+            if let Some(priority) = &priority {
+                snip.extra.insert(ULTISNIPS_PRIORITY.to_string(), priority.clone());
+            };
+            if !actions.is_empty() {
+                snip.extra.insert(ULTISNIPS_ACTIONS.to_string(), actions.join("\n"));
+                actions.clear();
+            };
+            imported.snippets.push(snip);
+        } else if line.starts_with("global ") {
+            let (body, _) = take_block(&mut lines, "endglobal");
+            let mut block = vec![line];
+            block.extend(body);
+            block.push("endglobal");
+            header.push(block.join("\n"));
+        } else if line.starts_with("extends ") || line.starts_with("clearsnippets") {
+            header.push(line.to_string());
+        } else if let Some(value) = line.strip_prefix("priority ") {
+            priority = Some(value.trim().to_string());
+        } else if ["context ", "pre_expand ", "post_expand ", "post_jump "].iter().any(|a| line.starts_with(a)) {
+            actions.push(line.to_string());
+        } else {
+            comments.read(line);
+        };
+    }
+    if !header.is_empty() {
+        for snip in imported.snippets.iter_mut() {
+            snip.extra.insert(ULTISNIPS_HEADER.to_string(), header.join("\n"));
+        };
+    };
+    imported
+}
+
+/// Reads a SnipMate `.snippets` file, where the body of every snippet is indented with a tab
+pub fn parse_snipmate(text: &str, filetype: &str) -> Imported {
+    let mut imported = Imported::default();
+    let mut header = vec![];
+    let mut current: Option<(CodeSnippet, Vec<&str>)> = None;
+    let mut comments = Comments::default();
+    // Empty lines without a tab belong to the snippet if more of its lines follow
+    let mut blank = 0;
+    for line in text.lines() {
+        if let Some((_, body)) = current.as_mut() {
+            if let Some(body_line) = line.strip_prefix('\t') {
+                body.extend(std::iter::repeat_n("", blank));
+                body.push(body_line);
+                blank = 0;
+                continue;
+            };
+            if line.trim().is_empty() {
+                blank += 1;
+                continue;
+            };
+            imported.snippets.extend(finish_snipmate(current.take()));
+            blank = 0;
+        };
+        if let Some(definition) = line.strip_prefix("snippet ") {
+            let definition = definition.trim();
+            let (trigger, description) = match definition.split_once(char::is_whitespace) {
+                Some((trigger, description)) => (trigger, Some(description.trim().to_string())),
+                None => (definition, None),
+            };
+            if trigger.is_empty() {
+                imported.skipped.push((line.to_string(), String::from("has no trigger")));
+                continue;
+            };
+            let mut snip = snippet_from(trigger, description, filetype);
+            std::mem::take(&mut comments).apply(&mut snip);
+            current = Some((snip, vec![]));
+        } else if line.starts_with("extends ") || line.starts_with("version ") {
+            header.push(line.to_string());
+        } else {
+            comments.read(line);
+        };
+    }
+    imported.snippets.extend(finish_snipmate(current.take()));
+    if !header.is_empty() {
+        for snip in imported.snippets.iter_mut() {
+            snip.extra.insert(SNIPMATE_HEADER.to_string(), header.join("\n"));
+        };
+    };
+    imported
+}
+
+/// One UltiSnips `.snippets` file per filetype, as (file name, contents), and the name of every snippet
+/// with what of it couldn't be exported
+pub fn write_ultisnips(snippets: &[&CodeSnippet]) -> (Files, Vec<(String, String)>) {
+    let mut files = vec![];
+    let mut notes = vec![];
+    for (filetype, snippets) in group_by_filetype(snippets) {
+        let mut text = file_header(&snippets, ULTISNIPS_HEADER);
+        let mut priority = None;
+        for snip in snippets {
+            // UltiSnips has no way to escape it, the snippet would end there
+            if snip.code.lines().any(|line| line.trim_end() == "endsnippet") {
+                notes.push((snip.name.clone(), String::from("has an endsnippet line in its code, it wasn't exported")));
+                continue;
+            };
+            let snip_priority = snip.extra.get(ULTISNIPS_PRIORITY);
+            if snip_priority != priority {
+                text.push_str(&format!("priority {}\n\n", snip_priority.map(String::as_str).unwrap_or("0")));
+                priority = snip_priority;
+            };
+            if let Some(actions) = snip.extra.get(ULTISNIPS_ACTIONS) {
+                text.push_str(actions);
+                text.push('\n');
+            };
+            let options = snip.extra.get(ULTISNIPS_OPTIONS).map(String::as_str).unwrap_or("");
+            let trigger = trigger_or_name(snip);
+            let mut definition = format!("snippet {}", quote_trigger(&trigger, options));
+            // The description ends at the last quote, so quotes in it can't be escaped
+            let description = export_description(snip, &trigger).map(|d| d.replace('"', "'"));
+            if description.as_deref() != export_description(snip, &trigger) {
+                notes.push((snip.name.clone(), String::from("the double quotes of the description became single quotes")));
+            };
+            text.push_str(&comments_for(snip, description.as_deref().unwrap_or(&trigger), &filetype));
+            match &description {
+                Some(description) => definition.push_str(&format!(" \"{}\"", description)),
+                None if !options.is_empty() => definition.push_str(" \"\""),
+                None => (),
+            };
+            if !options.is_empty() {
+                definition.push(' ');
+                definition.push_str(options);
+            };
+            text.push_str(&format!("{}\n{}\nendsnippet\n\n", definition, snip.code));
+        };
+        files.push((format!("{}.snippets", filetype), text));
+    };
+    (files, notes)
+}
+
+/// One SnipMate `.snippets` file per filetype, as (file name, contents)
+pub fn write_snipmate(snippets: &[&CodeSnippet]) -> Files {
+    let mut files = vec![];
+    for (filetype, snippets) in group_by_filetype(snippets) {
+        let mut text = file_header(&snippets, SNIPMATE_HEADER);
+        for snip in snippets {
+            let trigger: String = trigger_or_name(snip).split_whitespace().collect::<Vec<&str>>().join("_");
+            let description = export_description(snip, &trigger);
+            text.push_str(&comments_for(snip, description.unwrap_or(&trigger), &filetype));
+            match description {
+                Some(description) => text.push_str(&format!("snippet {} {}\n", trigger, description)),
+                None => text.push_str(&format!("snippet {}\n", trigger)),
+            };
+            for line in snip.code.split('\n') {
+                text.push_str(&format!("\t{}\n", line));
+            };
+            text.push('\n');
+        };
+        files.push((format!("{}.snippets", filetype), text));
+    };
+    files
+}
+
+
+/// Lines up to the line closing the block, and whether that line was found
+fn take_block<'a>(lines: &mut impl Iterator<Item = &'a str>, end: &str) -> (Vec<&'a str>, bool) {
+    let mut block = vec![];
+    for line in lines.by_ref() {
+        if line.trim_end() == end {
+            return (block, true);
+        };
+        block.push(line);
+    }
+    (block, false)
+}
+
+/// Splits what follows `snippet` into trigger, description and options, the way UltiSnips does
+fn parse_ultisnips_header(definition: &str) -> Result<(String, Option<String>, String), String> {
+    let mut remain = definition.trim();
+    let mut options = "";
+    let words: Vec<&str> = remain.split_whitespace().collect();
+    if words.len() > 2 && !words[words.len() - 1].contains('"') && words[words.len() - 2].ends_with('"') {
+        options = words[words.len() - 1];
+        remain = remain[..remain.len() - options.len()].trim_end();
+    };
+
+    let mut description = None;
+    if remain.split_whitespace().count() > 1 && remain.ends_with('"') {
+        if let Some(left) = remain[..remain.len() - 1].rfind('"').filter(|left| *left > 0) {
+            description = Some(remain[left + 1..remain.len() - 1].to_string());
+            remain = &remain[..left];
+        };
+    };
+
+    let mut trigger = remain.trim();
+    if trigger.is_empty() {
+        return Err(String::from("has no trigger"));
+    };
+    if trigger.split_whitespace().count() > 1 || options.contains('r') {
+        let quote = trigger.chars().next().unwrap_or_default();
+        if trigger.len() < 2 || !trigger.ends_with(quote) {
+            return Err(format!("the trigger {} isn't quoted", trigger));
+        };
+        trigger = &trigger[quote.len_utf8()..trigger.len() - quote.len_utf8()];
+    };
+    Ok((trigger.to_string(), description, options.to_string()))
+}
+
+/// Quotes the trigger if UltiSnips needs it to be quoted: when it contains whitespace or is a regex
+fn quote_trigger(trigger: &str, options: &str) -> String {
+    if !trigger.contains(char::is_whitespace) && !options.contains('r') {
+        return trigger.to_string();
+    };
+    let quote = TRIGGER_QUOTES.iter().find(|q| !trigger.contains(**q)).unwrap_or(&'"');
+    format!("{}{}{}", quote, trigger, quote)
+}
+
+fn finish_snipmate(current: Option<(CodeSnippet, Vec<&str>)>) -> Option<CodeSnippet> {
+    let (mut snip, body) = current?;
+    snip.code = body.join("\n");
+    Some(snip)
+}
+
+/// Name and tags from the comments in front of a snippet
+#[derive(Default)]
+struct Comments {
+    name: Option<String>,
+    tags: Option<Vec<String>>,
+}
+
+impl Comments {
+    fn read(&mut self, line: &str) {
+        if let Some(name) = line.strip_prefix(NAME_COMMENT) {
+            self.name = Some(name.trim().to_string());
+        } else if let Some(tags) = line.strip_prefix(TAGS_COMMENT) {
+            self.tags = Some(tags.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect());
+        };
+    }
+
+    fn apply(self, snip: &mut CodeSnippet) {
+        if let Some(name) = self.name.filter(|n| !n.is_empty()) {
+            snip.name = name;
+        };
+        if let Some(tags) = self.tags {
+            snip.tags = tags;
+        };
+    }
+}
+
+/// The comments for a name the snippet wouldn't get back from the file, and for tags besides the filetype
+fn comments_for(snippet: &CodeSnippet, read_name: &str, filetype: &str) -> String {
+    let mut comments = String::new();
+    if snippet.name != read_name {
+        comments.push_str(&format!("{} {}\n", NAME_COMMENT, snippet.name));
+    };
+    if snippet.tags != [filetype] {
+        comments.push_str(&format!("{} {}\n", TAGS_COMMENT, snippet.tags.join(", ")));
+    };
+    comments
+}
+
+/// A snippet named after its description (or trigger), tagged with the filetype
+fn snippet_from(trigger: &str, description: Option<String>, filetype: &str) -> CodeSnippet {
+    let mut snip = CodeSnippet::new(0);
+    snip.name = description.clone().filter(|d| !d.is_empty()).unwrap_or_else(|| trigger.to_string());
+    snip.trigger = Some(trigger.to_string());
+    snip.description = description.filter(|d| !d.is_empty());
+    snip.tags = vec![filetype.to_string()];
+    if filetype != ALL_FILETYPES {
+        let language = FILETYPES
+            .iter()
+            .find(|(ft, _)| *ft == filetype)
+            .map(|(_, language)| language.to_string());
+        snip.language = Some(language.unwrap_or_else(|| normalize_language(filetype)));
+    };
+    snip
+}
+
+/// The description, or the name if that says more than the trigger
+fn export_description<'a>(snippet: &'a CodeSnippet, trigger: &str) -> Option<&'a str> {
+    match &snippet.description {
+        Some(description) => Some(description),
+        None if snippet.name != trigger => Some(&snippet.name),
+        None => None,
+    }
+}
+
+/// Snippets without a language end up in `all.snippets`, and so do languages that can't be a file name in the export directory
+fn group_by_filetype<'a>(snippets: &[&'a CodeSnippet]) -> BTreeMap<String, Vec<&'a CodeSnippet>> {
+    let mut groups: BTreeMap<String, Vec<&CodeSnippet>> = BTreeMap::new();
+    for snip in snippets {
+        let filetype = match snip.language.as_deref() {
+            Some(language) => FILETYPES
+                .iter()
+                .find(|(_, l)| *l == language)
+                .map(|(ft, _)| *ft)
+                .unwrap_or(language),
+            None => ALL_FILETYPES,
+        };
+        let is_file_name = filetype.chars().all(|c| c.is_alphanumeric() || "-_+.".contains(c)) && !filetype.starts_with('.');
+        let filetype = if is_file_name {filetype} else {ALL_FILETYPES};
+        groups.entry(filetype.to_string()).or_default().push(snip);
+    };
+    groups
+}
+
+/// The file level lines the snippets were imported with, each one only once
+fn file_header(snippets: &[&CodeSnippet], key: &str) -> String {
+    let mut headers: Vec<&str> = vec![];
+    for snip in snippets {
+        if let Some(header) = snip.extra.get(key) {
+            if !headers.contains(&header.as_str()) {
+                headers.push(header);
+            };
+        };
+    };
+    match headers.is_empty() {
+        true => String::new(),
+        false => format!("{}\n\n", headers.join("\n")),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(name: &str, trigger: Option<&str>, description: Option<&str>, tags: &[&str], code: &str) -> CodeSnippet {
+        let mut snip = CodeSnippet::new(0);
+        snip.name = name.to_string();
+        snip.trigger = trigger.map(String::from);
+        snip.description = description.map(String::from);
+        snip.tags = tags.iter().map(|t| t.to_string()).collect();
+        snip.language = Some(String::from("python"));
+        snip.code = code.to_string();
+        snip
+    }
+
+    fn samples() -> Vec<CodeSnippet> {
+        vec![
+            snippet("Print a line", Some("pr"), Some("Print a line"), &["python"], "print(${1:text})"),
+            snippet("main", Some("main"), None, &["python", "cli"], "if __name__ == '__main__':\n\tmain()"),
+            snippet("Docstring", Some("doc"), Some("Documents the function"), &["web"], "\"\"\"${1}\"\"\"\n\n"),
+            snippet("Untagged", Some("u"), Some("Does nothing"), &[], "\n\npass\n"),
+        ]
+    }
+
+    fn parse_files(files: Vec<(String, String)>, parse: fn(&str, &str) -> Imported) -> Vec<CodeSnippet> {
+        let mut snippets = vec![];
+        for (file_name, text) in files {
+            let imported = parse(&text, file_name.trim_end_matches(".snippets"));
+            assert!(imported.skipped.is_empty(), "{:?}", imported.skipped);
+            snippets.extend(imported.snippets);
+        };
+        snippets
+    }
+
+    #[test]
+    fn ultisnips_round_trip() {
+        let snippets = samples();
+        let (files, notes) = write_ultisnips(&snippets.iter().collect::<Vec<_>>());
+        assert!(notes.is_empty());
+        assert_eq!(parse_files(files, parse_ultisnips), snippets);
+    }
+
+    #[test]
+    fn snipmate_round_trip() {
+        let snippets = samples();
+        let files = write_snipmate(&snippets.iter().collect::<Vec<_>>());
+        assert_eq!(parse_files(files, parse_snipmate), snippets);
+    }
+
+    #[test]
+    fn ultisnips_quotes_in_the_description() {
+        let snip = snippet("say", Some("say"), Some("says \"hi\""), &["python"], "print('hi')");
+        let (files, notes) = write_ultisnips(&[&snip]);
+        assert_eq!(notes.len(), 1);
+        let parsed = parse_files(files, parse_ultisnips);
+        assert_eq!(parsed[0].description.as_deref(), Some("says 'hi'"));
+        assert_eq!(parsed[0].name, "say");
+        assert_eq!(parsed[0].code, snip.code);
+    }
+
+    #[test]
+    fn ultisnips_skips_code_with_endsnippet() {
+        let snip = snippet("nested", Some("n"), None, &["python"], "snippet x\nendsnippet");
+        let (files, notes) = write_ultisnips(&[&snip]);
+        assert_eq!(notes.len(), 1);
+        assert!(parse_files(files, parse_ultisnips).is_empty());
+    }
+
+    #[test]
+    fn trigger_is_never_empty() {
+        let mut snip = snippet("!!!", None, None, &["python"], "pass");
+        snip.idx = 7;
+        assert_eq!(trigger_or_name(&snip), "snippet7");
+        let (files, _) = write_ultisnips(&[&snip]);
+        let parsed = parse_files(files, parse_ultisnips);
+        assert_eq!(parsed[0].trigger.as_deref(), Some("snippet7"));
+        assert_eq!(parsed[0].name, "!!!");
+    }
+
+    #[test]
+    fn languages_stay_in_the_export_directory() {
+        let mut snip = snippet("x", Some("x"), None, &[], "x");
+        snip.language = Some(String::from("../../.config/x"));
+        assert_eq!(write_snipmate(&[&snip])[0].0, "all.snippets");
+        snip.language = Some(String::from(".hidden"));
+        assert_eq!(write_ultisnips(&[&snip]).0[0].0, "all.snippets");
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    interop::{escape_snippet_syntax, uses_placeholders, Imported},
+    language::normalize_language,
+    snippet::CodeSnippet,
+};
+
+/// VS Code language ids that differ from the language names snippy uses, as (VS Code id, language).
+/// The export uses the first id of a language
+const LANGUAGE_IDS: &[(&str, &str)] = &[
+    ("shellscript", "bash"),
+    ("javascript", "javascript"),
+    ("typescript", "typescript"),
+    ("javascriptreact", "javascript"),
+    ("typescriptreact", "typescript"),
+];
+
+// Keys of `CodeSnippet::extra`
+/// The prefixes after the first one, which is the trigger, one per line
+const PREFIXES_KEY: &str = "vscode.prefixes";
+/// The language ids of the scope after the first one, which is the language
+const SCOPES_KEY: &str = "vscode.scopes";
+
+
+/// `prefix`, `body` and `description` can be a string or an array of strings
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize)]
+struct VsCodeEntry {
+    prefix: Option<OneOrMany>,
+    body: Option<OneOrMany>,
+    description: Option<OneOrMany>,
+    /// Comma separated VS Code language ids
+    scope: Option<String>,
+}
+
+#[derive(Serialize)]
+struct VsCodeSnippet<'a> {
+    prefix: OneOrMany,
+    body: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+}
+
+
+/// Reads a VS Code snippet file (`.code-snippets` or `<language>.json`), an object mapping snippet names
+/// to `prefix`, `body`, `description` and `scope`. Placeholders like `$1` or `${2:name}` stay in the code.
+/// `default_language` is used for snippets without a scope, e.g. for the snippets in `python.json`
+pub fn parse(text: &str, default_language: Option<&str>) -> Result<Imported, String> {
+    let entries: BTreeMap<String, serde_json::Value> = serde_json::from_str(&strip_comments(text))
+        .map_err(|e| format!("not a VS Code snippet file: {}", e))?;
+    let mut imported = Imported::default();
+    for (name, value) in entries {
+        let entry = match serde_json::from_value::<VsCodeEntry>(value) {
+            Ok(entry) => entry,
+            Err(err) => {
+                imported.skipped.push((name, format!("not a snippet: {}", err)));
+                continue;
+            },
+        };
+        let Some(body) = entry.body else {
+            imported.skipped.push((name, String::from("has no body")));
+            continue;
+        };
+
+        let mut snip = CodeSnippet::new(0);
+        snip.code = join_lines(body);
+        let mut prefixes = match entry.prefix {
+            Some(OneOrMany::One(prefix)) => vec![prefix],
+            Some(OneOrMany::Many(prefixes)) => prefixes,
+            None => vec![],
+        };
+        prefixes.retain(|p| !p.is_empty());
+        if prefixes.len() > 1 {
+            snip.extra.insert(PREFIXES_KEY.to_string(), prefixes[1..].join("\n"));
+        };
+        snip.trigger = prefixes.into_iter().next();
+        snip.description = entry.description.map(join_lines).filter(|d| !d.is_empty());
+        let scopes: Vec<&str> = entry.scope.as_deref().unwrap_or("").split(',').map(str::trim).filter(|s| !s.is_empty()).collect();
+        if scopes.len() > 1 {
+            snip.extra.insert(SCOPES_KEY.to_string(), scopes[1..].join(","));
+        };
+        snip.language = scopes.first().map(|id| language_from_id(id)).or(default_language.map(normalize_language));
+        snip.name = name;
+        imported.snippets.push(snip);
+    }
+    Ok(imported)
+}
+
+/// A `.code-snippets` file holding the snippets. Snippets without a trigger use their name as prefix.
+/// Code that doesn't use placeholders gets its `$` escaped, so e.g. `$HOME` isn't read as a VS Code variable
+pub fn write(snippets: &[&CodeSnippet]) -> String {
+    let mut entries = BTreeMap::new();
+    for snip in snippets {
+        let mut name = snip.name.clone();
+        if entries.contains_key(&name) {
+            name = format!("{} ({})", snip.name, snip.idx);
+        };
+        let mut prefixes = vec![snip.trigger.clone().unwrap_or_else(|| snip.name.clone())];
+        if let Some(more) = snip.extra.get(PREFIXES_KEY) {
+            prefixes.extend(more.lines().map(String::from));
+        };
+        let mut scopes: Vec<&str> = snip.language.as_deref().map(id_from_language).into_iter().collect();
+        if let Some(more) = snip.extra.get(SCOPES_KEY) {
+            scopes.extend(more.split(','));
+        };
+        let code = match uses_placeholders(snip) {
+            true => snip.code.clone(),
+            false => escape_snippet_syntax(&snip.code),
+        };
+        let entry = VsCodeSnippet {
+            prefix: match prefixes.len() {
+                1 => OneOrMany::One(prefixes.remove(0)),
+                _ => OneOrMany::Many(prefixes),
+            },
+            body: code.split('\n').map(|l| l.strip_suffix('\r').unwrap_or(l).to_string()).collect(),
+            description: snip.description.as_deref(),
+            scope: Some(scopes.join(",")).filter(|s| !s.is_empty()),
+        };
+        entries.insert(name, entry);
+    };
+    let mut text = serde_json::to_string_pretty(&entries).unwrap();
+    text.push('\n');
+    text
+}
+
+
+fn join_lines(value: OneOrMany) -> String {
+    match value {
+        OneOrMany::One(text) => text,
+        OneOrMany::Many(lines) => lines.join("\n"),
+    }
+}
+
+fn language_from_id(id: &str) -> String {
+    LANGUAGE_IDS
+        .iter()
+        .find(|(vscode_id, _)| vscode_id.eq_ignore_ascii_case(id))
+        .map(|(_, language)| language.to_string())
+        .unwrap_or_else(|| normalize_language(id))
+}
+
+fn id_from_language(language: &str) -> &str {
+    LANGUAGE_IDS
+        .iter()
+        .find(|(_, l)| *l == language)
+        .map(|(vscode_id, _)| *vscode_id)
+        .unwrap_or(language)
+}
+
+/// VS Code allows comments and trailing commas in snippet files, serde_json doesn't.
+/// Removes both, leaving strings untouched
+fn strip_comments(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            stripped.push(c);
+            match c {
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        stripped.push(escaped);
+                    };
+                },
+                '"' => in_string = false,
+                _ => (),
+            };
+            continue;
+        };
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                stripped.push(c);
+            },
+            ('/', Some('/')) => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            },
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    };
+                    previous = c;
+                }
+            },
+            ('}' | ']', _) => {
+                let trimmed_len = stripped.trim_end().len();
+                if stripped[..trimmed_len].ends_with(',') {
+                    stripped.truncate(trimmed_len - 1);
+                };
+                stripped.push(c);
+            },
+            _ => stripped.push(c),
+        };
+    }
+    stripped
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_every_prefix_and_scope() {
+        let text = r#"{"Log": {"prefix": ["log", "clg"], "body": "console.log($1)", "scope": "javascript,typescript"}}"#;
+        let imported = parse(text, None).unwrap();
+        let snip = &imported.snippets[0];
+        assert_eq!(snip.trigger.as_deref(), Some("log"));
+        assert_eq!(snip.language.as_deref(), Some("javascript"));
+        let exported: serde_json::Value = serde_json::from_str(&write(&[snip])).unwrap();
+        assert_eq!(exported["Log"]["prefix"], serde_json::json!(["log", "clg"]));
+        assert_eq!(exported["Log"]["scope"], "javascript,typescript");
+        assert_eq!(parse(&write(&[snip]), None).unwrap().snippets[0], *snip);
+    }
+
+    #[test]
+    fn escapes_code_without_placeholders() {
+        let mut snip = CodeSnippet::new(1);
+        snip.name = String::from("home");
+        snip.code = String::from("echo \"$HOME\" $1 \\$x ${1:-default}");
+        let exported: serde_json::Value = serde_json::from_str(&write(&[&snip])).unwrap();
+        assert_eq!(exported["home"]["body"][0], "echo \"\\$HOME\" \\$1 \\\\\\$x \\${1:-default}");
+
+        snip.trigger = Some(String::from("home"));
+        let exported: serde_json::Value = serde_json::from_str(&write(&[&snip])).unwrap();
+        assert_eq!(exported["home"]["body"][0], snip.code);
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    interop::{trigger_or_name, Imported},
+    language::known_language,
+    snippet::CodeSnippet,
+};
+
+/// Emacs modes (without `-mode`) that aren't named like the language, as (mode, language)
+const MODES: &[(&str, &str)] = &[
+    ("sh", "bash"),
+    ("c++", "cpp"),
+    ("js", "javascript"),
+    ("js2", "javascript"),
+    ("cperl", "perl"),
+    ("rustic", "rust"),
+];
+
+/// Mode of the exported snippets that have neither a language nor a tag
+const FALLBACK_MODE: &str = "fundamental-mode";
+
+/// First line of every snippet file, so Emacs opens it in snippet-mode
+const MODE_LINE: &str = "# -*- mode: snippet -*-";
+
+/// Keys of `CodeSnippet::extra`, every other header is kept as `yasnippet.<header>`
+const MODE_KEY: &str = "yasnippet.mode";
+/// Contents of the `.yas-parents` file of the mode
+const PARENTS_KEY: &str = "yasnippet.parents";
+/// Path of the snippet file in the mode directory, like `loops/for`, so the export has the same layout
+const PATH_KEY: &str = "yasnippet.path";
+const HEADER_PREFIX: &str = "yasnippet.";
+
+
+/// Reads a yasnippet tree: a directory holding one directory per mode (e.g. `python-mode`), or a single mode directory.
+/// Known modes become the language of their snippets, other modes become a tag. Files without a `# --` line
+/// (like a README) are skipped
+pub fn read_tree(root: &Path) -> io::Result<Imported> {
+    let is_mode_dir = |path: &Path| path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.ends_with("-mode"));
+    let mode_dirs = match is_mode_dir(root) {
+        true => vec![root.to_path_buf()],
+        false => {
+            let mut dirs: Vec<PathBuf> = fs::read_dir(root)?
+                .map(|e| e.map(|e| e.path()))
+                .collect::<io::Result<Vec<PathBuf>>>()?
+                .into_iter()
+                .filter(|p| p.is_dir() && !is_hidden(p))
+                .collect();
+            dirs.sort();
+            dirs
+        },
+    };
+
+    let mut imported = Imported::default();
+    for dir in mode_dirs {
+        let mode = dir.file_name().and_then(|n| n.to_str()).unwrap_or(FALLBACK_MODE).to_string();
+        let parents = fs::read_to_string(dir.join(".yas-parents")).ok().map(|p| p.trim().to_string());
+        for path in snippet_files(&dir)? {
+            let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+            let text = match fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(err) => {
+                    imported.skipped.push((path.display().to_string(), err.to_string()));
+                    continue;
+                },
+            };
+            if !text.starts_with("# --") && !text.contains("\n# --") {
+                imported.skipped.push((path.display().to_string(), String::from("has no # -- line, it's not a snippet")));
+                continue;
+            };
+            let mut snip = parse(&text, &file_name);
+            apply_mode(&mut snip, &mode);
+            if let Ok(relative) = path.strip_prefix(&dir) {
+                let relative: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+                snip.extra.insert(PATH_KEY.to_string(), relative.join("/"));
+            };
+            if let Some(parents) = parents.as_ref().filter(|p| !p.is_empty()) {
+                snip.extra.insert(PARENTS_KEY.to_string(), parents.clone());
+            };
+            snip.source = Some(path.display().to_string());
+            imported.snippets.push(snip);
+        };
+    };
+    Ok(imported)
+}
+
+/// Reads a snippet file: `# name:`, `# key:` and other headers up to `# --`, then the body.
+/// A file without `# --` is all body and named after the file
+pub fn parse(text: &str, file_name: &str) -> CodeSnippet {
+    let mut snip = CodeSnippet::new(0);
+    let mut has_name = false;
+    let (header, body) = match text.split_once("\n# --") {
+        Some((header, body)) => (header, body.split_once('\n').map(|(_, body)| body).unwrap_or("")),
+        None if text.starts_with("# --") => ("", text.split_once('\n').map(|(_, body)| body).unwrap_or("")),
+        None => ("", text),
+    };
+    for line in header.lines() {
+        let Some((key, value)) = line.trim_start_matches('#').split_once(':') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if key.starts_with("-*-") {
+            continue;
+        };
+        match key {
+            "name" => {
+                snip.name = value.to_string();
+                has_name = true;
+            },
+            "key" => snip.trigger = Some(value.to_string()),
+            _ => {
+                snip.extra.insert(format!("{}{}", HEADER_PREFIX, key), value.to_string());
+            },
+        };
+    };
+    if !has_name {
+        snip.name = snip.trigger.clone().unwrap_or_else(|| file_name.to_string());
+    };
+    // Editors end the file with a newline that isn't part of the snippet
+    let body = body.strip_suffix('\n').unwrap_or(body);
+    snip.code = body.strip_suffix('\r').unwrap_or(body).to_string();
+    snip
+}
+
+/// The files of a yasnippet tree as (path relative to the tree, contents). Imported snippets keep their path
+pub fn write(snippets: &[&CodeSnippet]) -> Vec<(String, String)> {
+    let mut modes: BTreeMap<String, Vec<&CodeSnippet>> = BTreeMap::new();
+    for snip in snippets {
+        modes.entry(mode_for(snip)).or_default().push(snip);
+    };
+
+    let mut files = vec![];
+    for (mode, snippets) in modes {
+        if let Some(parents) = snippets.iter().find_map(|s| s.extra.get(PARENTS_KEY)) {
+            files.push((format!("{}/.yas-parents", mode), format!("{}\n", parents)));
+        };
+        let mut file_names: Vec<String> = vec![];
+        for snip in snippets {
+            let trigger = trigger_or_name(snip);
+            let mut text = format!("{}\n# name: {}\n# key: {}\n", MODE_LINE, snip.name, trigger);
+            for (key, value) in &snip.extra {
+                match key.strip_prefix(HEADER_PREFIX) {
+                    Some(header) if ![MODE_KEY, PARENTS_KEY, PATH_KEY].contains(&key.as_str()) => {
+                        text.push_str(&format!("# {}: {}\n", header, value));
+                    },
+                    _ => (),
+                };
+            };
+            text.push_str(&format!("# --\n{}\n", snip.code));
+
+            let path = match snip.extra.get(PATH_KEY) {
+                Some(path) => path.split('/').map(file_name_for).collect::<Vec<String>>().join("/"),
+                None => file_name_for(&trigger),
+            };
+            let file_name = unique_file_name(&path, &file_names);
+            files.push((format!("{}/{}", mode, file_name), text));
+            file_names.push(file_name);
+        };
+    };
+    files
+}
+
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with('.'))
+}
+
+/// Every snippet file in the mode directory, including the ones in group directories
+fn snippet_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?.map(|e| e.map(|e| e.path())).collect::<io::Result<_>>()?;
+    entries.sort();
+    let mut files = vec![];
+    for entry in entries.into_iter().filter(|e| !is_hidden(e) && !e.to_string_lossy().ends_with('~')) {
+        if entry.is_dir() {
+            files.extend(snippet_files(&entry)?);
+        } else {
+            files.push(entry);
+        };
+    }
+    Ok(files)
+}
+
+fn apply_mode(snip: &mut CodeSnippet, mode: &str) {
+    let mode_name = mode.strip_suffix("-mode").unwrap_or(mode);
+    let language = MODES
+        .iter()
+        .find(|(m, _)| *m == mode_name)
+        .map(|(_, language)| *language)
+        .or_else(|| known_language(mode_name));
+    match language {
+        Some(language) => snip.language = Some(language.to_string()),
+        None => snip.tags.push(mode_name.to_string()),
+    };
+    snip.extra.insert(MODE_KEY.to_string(), mode.to_string());
+}
+
+/// The mode the snippet was imported from, else the mode of its language or first tag
+fn mode_for(snip: &CodeSnippet) -> String {
+    if let Some(mode) = snip.extra.get(MODE_KEY) {
+        return file_name_for(mode);
+    };
+    if let Some(language) = &snip.language {
+        let mode = MODES.iter().find(|(_, l)| l == language).map(|(m, _)| *m).unwrap_or(language);
+        return file_name_for(&format!("{}-mode", mode));
+    };
+    match snip.tags.first() {
+        Some(tag) => format!("{}-mode", file_name_for(tag)),
+        None => FALLBACK_MODE.to_string(),
+    }
+}
+
+/// Replaces the characters that can't be in a file name
+fn file_name_for(text: &str) -> String {
+    let name: String = text
+        .chars()
+        .map(|c| if c.is_alphanumeric() || "-_+.".contains(c) {c} else {'_'})
+        .collect();
+    let name = name.trim_start_matches('.');
+    match name.is_empty() {
+        true => String::from("snippet"),
+        false => name.to_string(),
+    }
+}
+
+fn unique_file_name(name: &str, taken: &[String]) -> String {
+    let mut unique = name.to_string();
+    let mut counter = 2;
+    while taken.contains(&unique) {
+        unique = format!("{}-{}", name, counter);
+        counter += 1;
+    }
+    unique
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_keeps_the_layout_and_the_group() {
+        let text = "# name: for loop\n# key: for\n# group: loops\n# --\nfor x in xs:\n    $0\n";
+        let mut snip = parse(text, "for");
+        apply_mode(&mut snip, "python-mode");
+        snip.extra.insert(PATH_KEY.to_string(), String::from("loops/for"));
+        let other = parse("# key: for\n# --\nfor (;;) {}\n", "for");
+
+        let files = write(&[&snip, &snip, &other]);
+        let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, vec!["fundamental-mode/for", "python-mode/loops/for", "python-mode/loops/for-2"]);
+        assert_eq!(files[1].1, format!("{}\n{}", MODE_LINE, text));
+        assert!(!files[1].1.contains("# path:"));
+    }
+
+    #[test]
+    fn path_segments_stay_in_the_mode_directory() {
+        let mut snip = parse("# --\nx", "x");
+        snip.extra.insert(PATH_KEY.to_string(), String::from("../../etc/x"));
+        assert_eq!(write(&[&snip])[0].0, "fundamental-mode/snippet/snippet/etc/x");
+    }
+
+    #[test]
+    fn modes_stay_in_the_export_directory() {
+        let mut snip = parse("# --\nx", "x");
+        snip.extra.insert(MODE_KEY.to_string(), String::from("../../.config/x"));
+        assert_eq!(write(&[&snip])[0].0, "_.._.config_x/x");
+
+        let mut snip = parse("# --\nx", "x");
+        snip.language = Some(String::from("../../.config/x"));
+        assert_eq!(write(&[&snip])[0].0, "_.._.config_x-mode/x");
+    }
+}
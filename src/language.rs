@@ -0,0 +1,107 @@
+use std::path::Path;
+
+
+/// Known languages as (name, file extensions, interpreters named in a shebang).
+/// The first extension is the one used when writing a snippet to a file
+const LANGUAGES: &[(&str, &[&str], &[&str])] = &[
+    ("bash", &["sh", "bash"], &["sh", "bash", "dash"]),
+    ("zsh", &["zsh"], &["zsh"]),
+    ("fish", &["fish"], &["fish"]),
+    ("powershell", &["ps1"], &["pwsh", "powershell"]),
+    ("python", &["py", "pyw"], &["python", "python3", "python2"]),
+    ("ruby", &["rb"], &["ruby"]),
+    ("perl", &["pl", "pm"], &["perl"]),
+    ("php", &["php"], &["php"]),
+    ("lua", &["lua"], &["lua"]),
+    ("javascript", &["js", "mjs", "cjs"], &["node", "deno"]),
+    ("typescript", &["ts", "tsx"], &["ts-node"]),
+    ("rust", &["rs"], &[]),
+    ("go", &["go"], &[]),
+    ("c", &["c", "h"], &[]),
+    ("cpp", &["cpp", "cc", "cxx", "hpp", "hh"], &[]),
+    ("csharp", &["cs"], &[]),
+    ("java", &["java"], &[]),
+    ("kotlin", &["kt", "kts"], &[]),
+    ("swift", &["swift"], &[]),
+    ("gdscript", &["gd"], &[]),
+    ("html", &["html", "htm"], &[]),
+    ("css", &["css"], &[]),
+    ("sql", &["sql"], &[]),
+    ("json", &["json"], &[]),
+    ("yaml", &["yaml", "yml"], &[]),
+    ("toml", &["toml"], &[]),
+    ("markdown", &["md"], &[]),
+    ("dockerfile", &["dockerfile"], &[]),
+    ("makefile", &["mk"], &[]),
+];
+
+
+/// Guesses the language from the file name (if there is one) or else from a shebang in the first line
+pub fn detect_language(path: Option<&str>, code: &str) -> Option<String> {
+    path.and_then(language_from_path)
+        .or_else(|| language_from_shebang(code))
+        .map(String::from)
+}
+
+pub fn language_from_path(path: &str) -> Option<&'static str> {
+    let path = Path::new(path);
+    let file_name = path.file_name()?.to_str()?.to_lowercase();
+    match file_name.as_str() {
+        "dockerfile" => return Some("dockerfile"),
+        "makefile" => return Some("makefile"),
+        _ => (),
+    };
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(_, extensions, _)| extensions.contains(&extension.as_str()))
+        .map(|(name, _, _)| *name)
+}
+
+/// Reads the interpreter from a shebang like `#!/bin/bash` or `#!/usr/bin/env python3`
+pub fn language_from_shebang(code: &str) -> Option<&'static str> {
+    let interpreter = shebang_interpreter(code)?;
+    LANGUAGES
+        .iter()
+        .find(|(_, _, interpreters)| interpreters.contains(&interpreter.as_str()))
+        .map(|(name, _, _)| *name)
+}
+
+/// The program named in the shebang of `code`, without its path and (for `env`) without `env`
+pub fn shebang_interpreter(code: &str) -> Option<String> {
+    let shebang = code.lines().next()?.strip_prefix("#!")?;
+    let mut parts = shebang.split_whitespace();
+    let mut program = parts.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = parts.find(|p| !p.starts_with('-'))?;
+    };
+    Some(program.to_string())
+}
+
+/// Lowercases the language and turns extensions like `rs` into the language name
+pub fn normalize_language(language: &str) -> String {
+    let language = language.trim().to_lowercase();
+    known_language(&language).map(String::from).unwrap_or(language)
+}
+
+/// The name of a known language, given its name or one of its extensions
+pub fn known_language(language: &str) -> Option<&'static str> {
+    let language = language.trim().to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(name, extensions, _)| *name == language || extensions.contains(&language.as_str()))
+        .map(|(name, _, _)| *name)
+}
+
+/// File extension (without the dot) for snippets of the given language, `txt` for unknown languages
+pub fn extension_for_language(language: Option<&str>) -> &'static str {
+    let Some(language) = language else {
+        return "txt";
+    };
+    let language = language.to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(name, extensions, _)| *name == language || extensions.contains(&language.as_str()))
+        .map(|(_, extensions, _)| extensions[0])
+        .unwrap_or("txt")
+}
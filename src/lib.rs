@@ -1,15 +1,58 @@
 use std::collections::HashMap;
 
+use serde::{Serializer, Deserializer};
 use serde_derive::{Serialize, Deserialize};
 use tui::widgets::ListState;
+
+use runner::DEFAULT_INTERPRETERS;
 pub mod snippet;
 pub mod app;
+pub mod bundle;
+pub mod cli;
+pub mod duplicates;
+pub mod editor;
+pub mod highlight;
+pub mod interop;
+pub mod language;
+pub mod output;
+pub mod picker;
+pub mod render;
+pub mod runner;
+pub mod search;
+pub mod shell;
+pub mod similarity;
+pub mod storage;
+pub mod textarea;
+pub mod undo;
+pub mod unicode;
+pub mod vim;
 
 #[derive(Serialize, Deserialize)]
 pub struct SnippyConfig {
     pub keys: HashMap<String, char>,
+    /// Command that runs snippets of a language, the path of the code is appended to it.
+    /// The defaults are only used if the config has no interpreters, so removed ones stay removed
+    #[serde(default = "default_interpreters")]
+    pub interpreters: HashMap<String, String>,
+    #[serde(default)]
+    pub keymap: Keymap,
+}
+
+/// Keys of the snippet list and the code editor
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Keymap {
+    #[default]
+    Default,
+    /// Modal editing in the code editor and `j`/`k`, `gg`/`G`, `/` and `dd` in the snippet list, like in vim
+    Vim,
 }
 
+/// Keys the snippet list takes for itself with the vim keymap: `j`/`k`, `gg`/`G`, `dd` and `/`
+pub const VIM_LIST_KEYS: [char; 6] = ['j', 'k', 'g', 'G', 'd', '/'];
+/// Where the default bindings on VIM_LIST_KEYS go with the vim keymap
+const VIM_MOVED_KEYS: [(&str, char); 2] = [("KEY_DUPLICATES", 'D'), ("KEY_RUN", 'R')];
+
 impl Default for SnippyConfig {
     fn default() -> Self {
         let mut keys = HashMap::new();
@@ -18,12 +61,67 @@ impl Default for SnippyConfig {
         keys.insert("KEY_SAVESNIPPET".to_string(), 's');
         keys.insert("KEY_COPY".to_string(), 'c');
         keys.insert("KEY_DELETE".to_string(), 'x');
+        keys.insert("KEY_SAVEDSEARCHES".to_string(), 'l');
+        keys.insert("KEY_TAGS".to_string(), 't');
+        keys.insert("KEY_RELATED".to_string(), 'r');
+        keys.insert("KEY_DUPLICATES".to_string(), 'd');
+        keys.insert("KEY_EXTERNALEDIT".to_string(), 'e');
+        keys.insert("KEY_RUN".to_string(), 'g');
         SnippyConfig {
             keys,
+            interpreters: default_interpreters(),
+            keymap: Keymap::Default,
         }
     }
 }
 
+fn default_interpreters() -> HashMap<String, String> {
+    DEFAULT_INTERPRETERS
+        .iter()
+        .map(|(language, interpreter)| (language.to_string(), interpreter.to_string()))
+        .collect()
+}
+
+impl SnippyConfig {
+    /// Adds the default binding for every key missing in the config, so config files written by older versions keep working
+    pub fn fill_missing_keys(&mut self) {
+        let default = SnippyConfig::default();
+        for (name, key) in default.keys {
+            self.keys.entry(name).or_insert(key);
+        };
+    }
+
+    /// The key bindings in effect. With the vim keymap every binding on one of VIM_LIST_KEYS moves to a free key,
+    /// so a mistyped `gg` or `dd` doesn't run or open anything. Also returns every moved binding with its new key
+    pub fn active_keys(&self) -> (HashMap<String, char>, Vec<(String, char)>) {
+        let mut keys = self.keys.clone();
+        let mut moved = vec![];
+        if self.keymap != Keymap::Vim {
+            return (keys, moved);
+        };
+        // KEY_SAVESNIPPET is pressed with CTRL
+        let mut names: Vec<String> = keys
+            .iter()
+            .filter(|(name, key)| *name != "KEY_SAVESNIPPET" && VIM_LIST_KEYS.contains(key))
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        for name in names {
+            let preferred = VIM_MOVED_KEYS.iter().filter(|(n, _)| *n == name).map(|(_, key)| *key);
+            let free = preferred
+                .chain([keys[&name].to_ascii_uppercase()])
+                .chain('a'..='z')
+                .chain('A'..='Z')
+                .find(|c| !VIM_LIST_KEYS.contains(c) && !keys.values().any(|key| key == c));
+            if let Some(key) = free {
+                keys.insert(name.clone(), key);
+                moved.push((name, key));
+            };
+        };
+        (keys, moved)
+    }
+}
+
 
 
 
@@ -82,6 +180,20 @@ impl<T> StatefulList<T> {
     }
 }
 
+// Only the items are stored, the selection starts out empty after loading
+impl<T: serde::Serialize> serde::Serialize for StatefulList<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.items.serialize(serializer)
+    }
+}
+
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for StatefulList<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let items = Vec::<T>::deserialize(deserializer)?;
+        Ok(StatefulList::with_items(items))
+    }
+}
+
 impl<T> Default for StatefulList<T> {
     fn default() -> Self {
         StatefulList {
@@ -89,4 +201,38 @@ impl<T> Default for StatefulList<T> {
             items: Vec::<T>::new(),
         }
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_interpreters_stay_removed() {
+        let config: SnippyConfig = serde_json::from_str(r#"{"keys": {}}"#).unwrap();
+        assert_eq!(config.interpreters, default_interpreters());
+
+        let mut config: SnippyConfig = serde_json::from_str(r#"{"keys": {}, "interpreters": {"python": "python3"}}"#).unwrap();
+        config.fill_missing_keys();
+        assert_eq!(config.interpreters.len(), 1);
+        assert_eq!(config.keys.len(), SnippyConfig::default().keys.len());
+    }
+
+    #[test]
+    fn vim_keymap_moves_colliding_keys() {
+        let mut config = SnippyConfig::default();
+        assert!(config.active_keys().1.is_empty());
+
+        config.keymap = Keymap::Vim;
+        config.keys.insert("KEY_NEW".to_string(), 'j');
+        let (keys, moved) = config.active_keys();
+        assert_eq!(moved, vec![
+            (String::from("KEY_DUPLICATES"), 'D'),
+            (String::from("KEY_NEW"), 'J'),
+            (String::from("KEY_RUN"), 'R'),
+        ]);
+        assert!(keys.values().all(|key| !VIM_LIST_KEYS.contains(key)));
+        assert_eq!(keys.len(), config.keys.len());
+    }
+}
@@ -3,35 +3,60 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::{error::Error, io};
+use std::{error::Error, io, time::Duration};
 use tui::{
     backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout, Rect, Margin, Alignment},
     style::{Color, Modifier, Style},
     text::{Span, Spans, Text},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Clear, BorderType, Wrap},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Clear, BorderType, Wrap},
     Frame, Terminal,
 };
-extern crate serde_json;
-extern crate serde;
-use serde::Serialize;
-
-use std::fs::File;
-use std::io::prelude::*;
-use std::path::Path;
 extern crate clipboard;
 use clipboard::ClipboardProvider;
 use clipboard::ClipboardContext;
-use std::env;
+use clap::Parser;
 
-use snippy::{app::{App, InputMode, NewSnippetMode}, snippet::CodeSnippet, SnippyConfig};
+use snippy::{
+    app::{App, DuplicatesMode, InputMode, NewSnippetMode, SavedSearchMode, TagManagerMode},
+    cli::{run_command, Cli, CliError},
+    editor::edit_in_external_editor,
+    runner::{interpreter_for, missing_interpreter_message, RunOutput, RunningSnippet},
+    duplicates::{find_duplicates, DuplicateKind},
+    snippet::CodeSnippet,
+    search::search_snippets,
+    storage::{load_app_state, load_config_state, save_app_state, save_config_state},
+    textarea::TextArea,
+    undo::EditKind,
+    unicode::{display_width, expand_tabs, pop_grapheme},
+    vim::{ExCommand, ListAction, Vim},
+    Keymap, SnippyConfig,
+};
 
 const ORANGE: Color = Color::Rgb(252, 141, 0);
-const SAVEFILE_PATH: &str = "savestate.snippy";
-const CONFIG_PATH: &str = "config.snippy";
+/// How many related snippets are shown in the preview
+const RELATED_SNIPPETS: usize = 5;
+/// How often the output pane is redrawn while a snippet runs
+const RUN_OUTPUT_REFRESH: Duration = Duration::from_millis(100);
 
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    let config = load_config_state()?;
+    let mut app = load_app_state()?;
+
+    // Commands run without the interactive interface
+    if let Some(command) = cli.command {
+        let mut stdout = io::stdout();
+        if let Err(err) = run_command(command, &mut app, &config, &mut stdout) {
+            if !matches!(err, CliError::Cancelled) {
+                eprintln!("snippy: {}", err);
+            };
+            std::process::exit(err.exit_code());
+        };
+        return Ok(());
+    };
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -39,34 +64,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // load config if that exists, else create new App
-    let path = build_path_with_str(CONFIG_PATH);
-    let config = match Path::new(path.as_str()).exists() {
-        true => {
-            let contents = load_string_from_file(path.as_str());
-            let config_deserialized : SnippyConfig = serde_json::from_str(contents.as_str()).unwrap();
-            config_deserialized
-        },
-        false => {
-            SnippyConfig::default()
-        },
-    };
-    
-    // load app state from save file if that exists, else create new App
-    let path = build_path_with_str(SAVEFILE_PATH);
-    let mut app = match Path::new(path.as_str()).exists() {
-        true => {
-            let contents = load_string_from_file(path.as_str());
-            let app_deserialized : App = serde_json::from_str(contents.as_str()).unwrap();
-            app_deserialized
-        },
-        false => {
-            App::default()
-        },
-    };
     let res = run_app(&mut terminal, &mut app, &config);
-    save_app_state(&app);
-    save_config_state(&config);
 
     // restore terminal
     disable_raw_mode()?;
@@ -78,6 +76,10 @@ fn main() -> Result<(), Box<dyn Error>> {
     )?;
     terminal.show_cursor()?;
 
+    // Saved once the terminal is back, so a failed save is readable
+    save_app_state(&app)?;
+    save_config_state(&config)?;
+
     if let Err(err) = res {
         println!("{:?}", err)
     }
@@ -86,67 +88,81 @@ fn main() -> Result<(), Box<dyn Error>> {
 }
 
 
-fn save_app_state(app: &App) {
-    let path = build_path_with_str(SAVEFILE_PATH);
-    let file = File::create(path).unwrap();
-    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
-    let mut ser = serde_json::Serializer::with_formatter(file, formatter);
-    app.serialize(&mut ser).unwrap();
-}
-
-fn save_config_state(config: &SnippyConfig) {
-    let path = build_path_with_str(CONFIG_PATH);
-    let file = File::create(path).unwrap();
-    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
-    let mut ser = serde_json::Serializer::with_formatter(file, formatter);
-    config.serialize(&mut ser).unwrap();
-}
-
-
-fn build_path_with_str(filename: &str) -> String {
-    let path = env::current_exe().unwrap();
-    let release_folder = path.parent().unwrap();
-    let target_folder = release_folder.parent().unwrap();
-    let main_folder = target_folder.parent().unwrap();
-    let full_path = main_folder.join(filename);
-    let path_str = full_path.to_str().unwrap();
-    String::from(path_str)
-}
-
-
-fn load_string_from_file(path: &str) -> String {
-    let mut file = File::open(path).unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents
-}
-
 
-
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: &mut App, config: &SnippyConfig) -> io::Result<()> {
-    let k_new = config.keys.get(&"KEY_NEW".to_string()).unwrap();
-    let k_find = config.keys.get(&"KEY_FIND".to_string()).unwrap();
-    let k_copy = config.keys.get(&"KEY_COPY".to_string()).unwrap();
-    let k_delete = config.keys.get(&"KEY_DELETE".to_string()).unwrap();
-    let k_save = config.keys.get(&"KEY_SAVESNIPPET".to_string()).unwrap();
+fn run_app<B: Backend + io::Write>(terminal: &mut Terminal<B>, app: &mut App, config: &SnippyConfig) -> io::Result<()> {
+    let (keys, moved_keys) = config.active_keys();
+    if !moved_keys.is_empty() {
+        let moved: Vec<String> = moved_keys.iter().map(|(name, key)| format!("{} is {}", name, key)).collect();
+        app.status_message = format!("With the vim keymap {}", moved.join(", "));
+    };
+    let k_new = keys.get("KEY_NEW").unwrap();
+    let k_find = keys.get("KEY_FIND").unwrap();
+    let k_copy = keys.get("KEY_COPY").unwrap();
+    let k_delete = keys.get("KEY_DELETE").unwrap();
+    let k_save = keys.get("KEY_SAVESNIPPET").unwrap();
+    let k_saved_searches = keys.get("KEY_SAVEDSEARCHES").unwrap();
+    let k_tags = keys.get("KEY_TAGS").unwrap();
+    let k_related = keys.get("KEY_RELATED").unwrap();
+    let k_duplicates = keys.get("KEY_DUPLICATES").unwrap();
+    let k_external_edit = keys.get("KEY_EXTERNALEDIT").unwrap();
+    let k_run = keys.get("KEY_RUN").unwrap();
     // Editing this is optional
-    let k_edit = config.keys.get(&"KEY_EDIT".to_string());
+    let k_edit = keys.get("KEY_EDIT");
+    app.vim = (config.keymap == Keymap::Vim).then(Vim::default);
+    // The snippet running in the background, its output goes into app.run_output
+    let mut running: Option<RunningSnippet> = None;
+    // Drawn once before the first key, so the keys the vim keymap moved are shown
+    terminal.draw(|f| ui(f, app))?;
     
     loop {
+        // While a snippet runs, its output is shown as it comes in instead of only after the next key press
+        if let (Some(run), Some(output)) = (running.as_mut(), app.run_output.as_mut()) {
+            let finished = run.update(output)?;
+            if finished {
+                running = None;
+            };
+            if finished || !event::poll(RUN_OUTPUT_REFRESH)? {
+                terminal.draw(|f| ui(f, app))?;
+                continue;
+            };
+        };
+
         let mut new_input_mode = app.input_mode.clone();
         let mut clear_found_snippets = false;
         let mut push_current_snippet = false;
+        // Set once the user confirmed saving a duplicate
+        let mut allow_duplicate = false;
         // (list idx, snippet idx)
         let mut found_indices = Vec::<(usize, usize)>::new();
         let mut delete_snippet = None;
+        // Snippet idx that gets selected in the list once it's rebuilt
+        let mut jump_to_snippet = None;
+        // Snippet idx that gets opened in $EDITOR
+        let mut edit_externally = None;
         
         if let Event::Key(key) = event::read()? {
+            app.status_message.clear();
             match app.input_mode {
                 InputMode::Normal => {
-                    if key.code == KeyCode::Char(*k_new) {
+                    // The vim keys come first, so `d` and `g` wait for the second key of `dd` and `gg`
+                    let vim_action = app.vim.as_mut().and_then(|vim| vim.list_input(key, &mut app.found_snippets));
+                    if let Some(action) = vim_action {
+                        match action {
+                            // Moved in the list, or waiting for the second key
+                            ListAction::Done => (),
+                            ListAction::Search => {
+                                new_input_mode = InputMode::Search;
+                                clear_found_snippets = true;
+                            },
+                            ListAction::Delete => new_input_mode = confirm_delete_selected(app, new_input_mode),
+                        };
+                    // A KEY_EDIT the user bound wins over the default keys it may collide with
+                    } else if k_edit.is_some_and(|editkey| key.code == KeyCode::Char(*editkey)) {
+                        new_input_mode = edit_snippet_from_list(app, new_input_mode);
+                    } else if key.code == KeyCode::Char(*k_new) {
                         new_input_mode = InputMode::NewSnippet(NewSnippetMode::TypeName);
-                        app.current_snippet = Some(CodeSnippet::new(app.return_next_idx()));
-                        app.input = String::new();
+                        let new_snippet = CodeSnippet::new(app.return_next_idx());
+                        app.start_editing(new_snippet);
                     } else if key.code == KeyCode::Char(*k_find) {
                         new_input_mode = InputMode::Search;
                         clear_found_snippets = true;
@@ -157,21 +173,44 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: &mut App, config: &S
                             let snip = &app.found_snippets.items[selected_snip_idx];
                             ctx.set_contents(snip.code.clone().to_owned()).unwrap();
                         }
+                    } else if key.code == KeyCode::Char(*k_saved_searches) {
+                        new_input_mode = InputMode::SavedSearches(SavedSearchMode::Browse);
+                        if app.saved_searches.state.selected().is_none() {
+                            app.saved_searches.next();
+                        };
+                    } else if key.code == KeyCode::Char(*k_related) {
+                        if let Some(selected) = app.found_snippets.state.selected() {
+                            let snip_idx = app.found_snippets.items[selected].idx;
+                            if !app.related_snippets(snip_idx, RELATED_SNIPPETS).is_empty() {
+                                new_input_mode = InputMode::Related(0);
+                            };
+                        };
+                    } else if key.code == KeyCode::Char(*k_duplicates) {
+                        new_input_mode = InputMode::Duplicates(DuplicatesMode::Browse);
+                        app.refresh_duplicate_groups();
+                    } else if key.code == KeyCode::Char(*k_external_edit) {
+                        if let Some(selected) = app.found_snippets.state.selected() {
+                            edit_externally = Some(app.found_snippets.items[selected].idx);
+                        };
+                    } else if key.code == KeyCode::Char(*k_run) {
+                        if let Some(selected) = app.found_snippets.state.selected() {
+                            let snip = &app.found_snippets.items[selected];
+                            match interpreter_for(snip, &config.interpreters) {
+                                Some(interpreter) => {
+                                    new_input_mode = InputMode::ConfirmRun(snip.idx);
+                                    app.run_output = Some(RunOutput::new(snip, &interpreter));
+                                },
+                                None => app.status_message = missing_interpreter_message(snip),
+                            };
+                        };
+                    } else if key.code == KeyCode::Char(*k_tags) {
+                        new_input_mode = InputMode::TagManager(TagManagerMode::Browse);
+                        app.refresh_tag_list();
                     } else if key.code == KeyCode::Char(*k_delete) {
-                        let selected_snippet = app.found_snippets.state.selected();
-                        if let Some(selected_snip_idx) = selected_snippet {
-                            if !app.snippets.is_empty() {
-                                let snip = &app.found_snippets.items[selected_snip_idx];
-                                new_input_mode = InputMode::ConfirmDelete(snip.idx);
-                            }
-                        }
+                        new_input_mode = confirm_delete_selected(app, new_input_mode);
                     } else {
-                        if let Some(editkey) = k_edit {
-                            if key.code == KeyCode::Char(*editkey) {
-                                new_input_mode = edit_snippet_from_list(&mut app, new_input_mode);
-                            };
-                        } else if key.code == KeyCode::Enter {
-                            new_input_mode = edit_snippet_from_list(&mut app, new_input_mode);
+                        if k_edit.is_none() && key.code == KeyCode::Enter {
+                            new_input_mode = edit_snippet_from_list(app, new_input_mode);
                         } else {
                             match key.code {
                                 KeyCode::Esc => {
@@ -193,19 +232,45 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: &mut App, config: &S
                 }
 
                 InputMode::NewSnippet(new_mode) => {
-                    let snip = app.current_snippet.as_mut();
-                    if let Some(snip) = snip {
-                        let input_field = match new_mode {
+                    let tag_suggestion = match new_mode {
+                        NewSnippetMode::TypeTags => tag_completion(app),
+                        _ => None,
+                    };
+                    // Every key but ESC and saving goes to the code editor, with the vim keymap ESC does too
+                    let is_save_key = key.code == KeyCode::Char(*k_save) && key.modifiers == KeyModifiers::CONTROL;
+                    let edits_code = new_mode == NewSnippetMode::TypeCode && (key.code != KeyCode::Esc || app.vim.is_some()) && !is_save_key;
+                    if let (true, Some(snip)) = (edits_code, &mut app.current_snippet) {
+                        match app.vim.as_mut() {
+                            Some(vim) => {
+                                match vim.editor_input(&mut app.code_editor, key, code_editor_input) {
+                                    Some(ExCommand::Save) => {
+                                        push_current_snippet = true;
+                                        new_input_mode = InputMode::Normal;
+                                    },
+                                    Some(ExCommand::Close) => new_input_mode = InputMode::Normal,
+                                    None => (),
+                                };
+                                snip.code = app.code_editor.text();
+                            },
+                            None => {
+                                if code_editor_input(&mut app.code_editor, key) {
+                                    snip.code = app.code_editor.text();
+                                };
+                            },
+                        };
+                    } else if let Some(snip) = app.current_snippet.as_mut() {
+                        let (input_field, mut history) = match new_mode {
                             NewSnippetMode::TypeName => {
-                                &mut snip.name
+                                (&mut snip.name, Some(&mut app.name_history))
                             },
                             NewSnippetMode::TypeTags => {
-                                &mut app.input
+                                (&mut app.input, Some(&mut app.tags_history))
                             },
                             NewSnippetMode::TypeCode => {
-                                &mut snip.code
+                                (&mut snip.code, None)
                             },
                         };
+                        let before_edit = input_field.clone();
                         let mut did_paste_something = false;
                         let paste_key = KeyEvent::new(KeyCode::Char('v'), KeyModifiers::CONTROL);
                         if key == paste_key {
@@ -222,27 +287,26 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: &mut App, config: &S
                                 KeyCode::Esc => {
                                     new_input_mode = InputMode::Normal;
                                 },
+                                KeyCode::Char('z') if key.modifiers == KeyModifiers::CONTROL => {
+                                    if let Some(before) = history.as_mut().and_then(|h| h.undo(input_field.clone())) {
+                                        *input_field = before;
+                                    };
+                                }
+                                KeyCode::Char('y') if key.modifiers == KeyModifiers::CONTROL => {
+                                    if let Some(after) = history.as_mut().and_then(|h| h.redo(input_field.clone())) {
+                                        *input_field = after;
+                                    };
+                                }
                                 KeyCode::Char(c) => {
                                     if c == *k_save && key.modifiers == KeyModifiers::CONTROL {
                                         push_current_snippet = true;
                                         new_input_mode = InputMode::Normal;
-                                        
-                                        // Split up tags string and make it into the tags vector
-                                        if let Some(current_snip) = &mut app.current_snippet {
-                                            let tag_split: Vec<&str> = app.input.split(' ').collect();
-                                            let mut new_tags = vec![];
-                                            for t in tag_split {
-                                                new_tags.push(String::from(t));
-                                            };
-                                            current_snip.tags = new_tags;
-                                        };
-                                        app.input = String::new();
                                     } else {
                                         input_field.push(c);
                                     }
                                 }
                                 KeyCode::Backspace => {
-                                    input_field.pop();
+                                    pop_grapheme(input_field);
                                 },
                                 KeyCode::Enter => {
                                     match new_mode {
@@ -252,9 +316,16 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: &mut App, config: &S
                                         NewSnippetMode::TypeTags => {
                                             new_input_mode = InputMode::NewSnippet(NewSnippetMode::TypeCode);
                                         },
-                                        NewSnippetMode::TypeCode => {
-                                            input_field.push('\n');
-                                        },
+                                        NewSnippetMode::TypeCode => (),
+                                    };
+                                }
+                                KeyCode::Tab if new_mode == NewSnippetMode::TypeTags => {
+                                    // The whole word gets replaced, so the completed tag is spelled exactly like the existing one
+                                    if let Some(completion) = tag_suggestion {
+                                        let last_word_len = input_field.rsplit(' ').next().unwrap_or("").len();
+                                        input_field.truncate(input_field.len() - last_word_len);
+                                        input_field.push_str(&completion);
+                                        input_field.push(' ');
                                     };
                                 }
                                 KeyCode::Tab => {
@@ -278,24 +349,63 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: &mut App, config: &S
                                 _ => {}
                             }
                         };
+
+                        // Undo and redo aren't edits themselves
+                        let is_undo_key = key.modifiers == KeyModifiers::CONTROL && matches!(key.code, KeyCode::Char('z') | KeyCode::Char('y'));
+                        if let (Some(history), false) = (history, is_undo_key) {
+                            if *input_field != before_edit {
+                                let kind = match key.code {
+                                    _ if did_paste_something => EditKind::Other,
+                                    KeyCode::Char(c) => EditKind::typed(c),
+                                    KeyCode::Backspace | KeyCode::BackTab => EditKind::Delete,
+                                    _ => EditKind::Other,
+                                };
+                                history.record(before_edit, kind);
+                            };
+                        };
                     } else {
                         new_input_mode = InputMode::Normal;
                     }
+
+                    if push_current_snippet {
+                        // Split up tags string and make it into the tags vector
+                        if let Some(current_snip) = &mut app.current_snippet {
+                            let tag_split: Vec<&str> = app.input.split_whitespace().collect();
+                            let mut new_tags = vec![];
+                            for t in tag_split {
+                                new_tags.push(String::from(t));
+                            };
+                            current_snip.tags = new_tags;
+                        };
+                        app.input = String::new();
+                    };
                 },
                 InputMode::Search => {
                     match key.code {
                         KeyCode::Enter => {
-                            new_input_mode = edit_snippet_from_list(&mut app, new_input_mode);
+                            app.add_to_search_history(&app.input.clone());
+                            new_input_mode = edit_snippet_from_list(app, new_input_mode);
+                        }
+                        KeyCode::Char('r') if key.modifiers == KeyModifiers::CONTROL => {
+                            app.prompt = String::new();
+                            new_input_mode = InputMode::HistorySearch(0);
+                        }
+                        KeyCode::Char(c) if c == *k_save && key.modifiers == KeyModifiers::CONTROL && !app.input.trim().is_empty() => {
+                            app.prompt = String::new();
+                            new_input_mode = InputMode::SavedSearches(SavedSearchMode::Name(None));
                         }
+                        // Nothing to save yet
+                        KeyCode::Char(c) if c == *k_save && key.modifiers == KeyModifiers::CONTROL => (),
                         KeyCode::Char(c) => {
                             app.input.push(c);
                         }
                         KeyCode::Backspace => {
-                            app.input.pop();
+                            pop_grapheme(&mut app.input);
                             if app.input.is_empty() {
                                 app.found_snippets.items = vec![];
                             };
                         }
+                        // Leaving the search doesn't submit it, so it stays out of the history
                         KeyCode::Esc => {
                             new_input_mode = InputMode::Normal;
                         }
@@ -311,7 +421,251 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: &mut App, config: &S
                         _ => (),
                     };
                     if !app.input.is_empty() {
-                        found_indices = search_snippets(&mut app.snippets, &app.input);
+                        found_indices = search_snippets(&app.snippets, &app.input);
+                    };
+                },
+                InputMode::HistorySearch(match_idx) => {
+                    let match_count = app.search_history_matches(&app.prompt).len();
+                    match key.code {
+                        // Like in a shell, pressing CTRL-R again goes to the next older match
+                        KeyCode::Char('r') if key.modifiers == KeyModifiers::CONTROL => {
+                            new_input_mode = InputMode::HistorySearch((match_idx + 1).min(match_count.saturating_sub(1)));
+                        }
+                        KeyCode::Char('s') if key.modifiers == KeyModifiers::CONTROL => {
+                            new_input_mode = InputMode::HistorySearch(match_idx.saturating_sub(1));
+                        }
+                        KeyCode::Char(c) => {
+                            app.prompt.push(c);
+                            new_input_mode = InputMode::HistorySearch(0);
+                        }
+                        KeyCode::Backspace => {
+                            pop_grapheme(&mut app.prompt);
+                            new_input_mode = InputMode::HistorySearch(0);
+                        }
+                        KeyCode::Enter => {
+                            if let Some(entry) = app.search_history_matches(&app.prompt).get(match_idx) {
+                                app.input = entry.to_string();
+                                app.found_snippets.unselect();
+                            };
+                            new_input_mode = InputMode::Search;
+                        }
+                        KeyCode::Esc => {
+                            new_input_mode = InputMode::Search;
+                        }
+                        _ => (),
+                    };
+                    if !app.input.is_empty() {
+                        found_indices = search_snippets(&app.snippets, &app.input);
+                    };
+                },
+                InputMode::Related(related_idx) => {
+                    let related: Vec<usize> = match app.found_snippets.state.selected() {
+                        Some(selected) => app
+                            .related_snippets(app.found_snippets.items[selected].idx, RELATED_SNIPPETS)
+                            .iter()
+                            .map(|s| s.idx)
+                            .collect(),
+                        None => vec![],
+                    };
+                    match key.code {
+                        KeyCode::Esc => {
+                            new_input_mode = InputMode::Normal;
+                        }
+                        KeyCode::Up => {
+                            new_input_mode = InputMode::Related(related_idx.saturating_sub(1));
+                        }
+                        KeyCode::Down => {
+                            new_input_mode = InputMode::Related(std::cmp::min(related_idx + 1, related.len().saturating_sub(1)));
+                        }
+                        KeyCode::Enter => {
+                            jump_to_snippet = related.get(related_idx).copied();
+                            new_input_mode = InputMode::Normal;
+                        }
+                        _ => (),
+                    };
+                },
+                InputMode::TagManager(TagManagerMode::Browse) => {
+                    let selected = app.tag_list.state.selected();
+                    match key.code {
+                        KeyCode::Esc => {
+                            new_input_mode = InputMode::Normal;
+                        }
+                        KeyCode::Up => {
+                            app.tag_list.previous();
+                        }
+                        KeyCode::Down => {
+                            app.tag_list.next();
+                        }
+                        KeyCode::Char('r') => {
+                            if let Some(selected) = selected {
+                                app.prompt = app.tag_list.items[selected].clone();
+                                new_input_mode = InputMode::TagManager(TagManagerMode::Rename(selected));
+                            };
+                        }
+                        KeyCode::Char('m') => {
+                            if let Some(selected) = selected {
+                                new_input_mode = InputMode::TagManager(TagManagerMode::Merge(selected));
+                            };
+                        }
+                        // Deleting only works for tags that aren't used by any snippet
+                        KeyCode::Char(c) if c == *k_delete => {
+                            if let Some(selected) = selected {
+                                let tag = app.tag_list.items[selected].clone();
+                                if app.remove_tag_if_unused(&tag) {
+                                    app.refresh_tag_list();
+                                    save_app_state(app)?;
+                                };
+                            };
+                        }
+                        KeyCode::Char('u') => {
+                            app.remove_unused_tags();
+                            app.refresh_tag_list();
+                            save_app_state(app)?;
+                        }
+                        _ => (),
+                    };
+                },
+                InputMode::TagManager(TagManagerMode::Rename(list_idx)) => {
+                    match key.code {
+                        KeyCode::Esc => {
+                            new_input_mode = InputMode::TagManager(TagManagerMode::Browse);
+                        }
+                        KeyCode::Enter => {
+                            let new_name = app.prompt.trim().to_string();
+                            // Tags are separated by spaces, so they can't contain any
+                            if !new_name.is_empty() && !new_name.contains(char::is_whitespace) {
+                                let old_name = app.tag_list.items[list_idx].clone();
+                                app.rename_tag(&old_name, &new_name);
+                                app.refresh_tag_list();
+                                save_app_state(app)?;
+                                new_input_mode = InputMode::TagManager(TagManagerMode::Browse);
+                            };
+                        }
+                        KeyCode::Char(c) => {
+                            app.prompt.push(c);
+                        }
+                        KeyCode::Backspace => {
+                            pop_grapheme(&mut app.prompt);
+                        }
+                        _ => (),
+                    };
+                },
+                InputMode::TagManager(TagManagerMode::Merge(source_idx)) => {
+                    match key.code {
+                        KeyCode::Esc => {
+                            new_input_mode = InputMode::TagManager(TagManagerMode::Browse);
+                        }
+                        KeyCode::Up => {
+                            app.tag_list.previous();
+                        }
+                        KeyCode::Down => {
+                            app.tag_list.next();
+                        }
+                        KeyCode::Enter => {
+                            if let Some(target_idx) = app.tag_list.state.selected() {
+                                if target_idx != source_idx {
+                                    let source = app.tag_list.items[source_idx].clone();
+                                    let target = app.tag_list.items[target_idx].clone();
+                                    app.rename_tag(&source, &target);
+                                    app.refresh_tag_list();
+                                    let new_target_idx = app.tag_list.items.iter().position(|t| *t == target);
+                                    app.tag_list.state.select(new_target_idx);
+                                    save_app_state(app)?;
+                                };
+                            };
+                            new_input_mode = InputMode::TagManager(TagManagerMode::Browse);
+                        }
+                        _ => (),
+                    };
+                },
+                InputMode::SavedSearches(SavedSearchMode::Browse) => {
+                    let selected = app.saved_searches.state.selected();
+                    match key.code {
+                        KeyCode::Esc => {
+                            new_input_mode = InputMode::Normal;
+                        }
+                        KeyCode::Up => {
+                            app.saved_searches.previous();
+                        }
+                        KeyCode::Down => {
+                            app.saved_searches.next();
+                        }
+                        // Run the saved search in the search field
+                        KeyCode::Enter => {
+                            if let Some(selected) = selected {
+                                app.input = app.saved_searches.items[selected].query.clone();
+                                app.found_snippets.unselect();
+                                new_input_mode = InputMode::Search;
+                            };
+                        }
+                        KeyCode::Char('r') => {
+                            if let Some(selected) = selected {
+                                app.prompt = app.saved_searches.items[selected].name.clone();
+                                new_input_mode = InputMode::SavedSearches(SavedSearchMode::Name(Some(selected)));
+                            };
+                        }
+                        KeyCode::Char('e') => {
+                            if let Some(selected) = selected {
+                                app.prompt = app.saved_searches.items[selected].query.clone();
+                                new_input_mode = InputMode::SavedSearches(SavedSearchMode::Query(selected));
+                            };
+                        }
+                        KeyCode::Char(c) if c == *k_delete => {
+                            if let Some(selected) = selected {
+                                app.remove_saved_search(selected);
+                                save_app_state(app)?;
+                            };
+                        }
+                        _ => (),
+                    };
+                },
+                InputMode::SavedSearches(prompt_mode) => {
+                    match key.code {
+                        KeyCode::Esc => {
+                            new_input_mode = match prompt_mode {
+                                SavedSearchMode::Name(None) => InputMode::Search,
+                                _ => InputMode::SavedSearches(SavedSearchMode::Browse),
+                            };
+                        }
+                        KeyCode::Enter => {
+                            let text = app.prompt.trim().to_string();
+                            if !text.is_empty() {
+                                match prompt_mode {
+                                    SavedSearchMode::Name(None) => {
+                                        let query = app.input.clone();
+                                        match app.save_search(&text, &query) {
+                                            true => {
+                                                app.add_to_search_history(&query);
+                                                new_input_mode = InputMode::Search;
+                                            },
+                                            false => app.status_message = format!("There is a saved search named {} already", text),
+                                        };
+                                    },
+                                    SavedSearchMode::Name(Some(list_idx)) => {
+                                        match app.rename_saved_search(list_idx, &text) {
+                                            true => new_input_mode = InputMode::SavedSearches(SavedSearchMode::Browse),
+                                            false => app.status_message = format!("There is a saved search named {} already", text),
+                                        };
+                                    },
+                                    SavedSearchMode::Query(list_idx) => {
+                                        app.saved_searches.items[list_idx].query = text;
+                                        new_input_mode = InputMode::SavedSearches(SavedSearchMode::Browse);
+                                    },
+                                    SavedSearchMode::Browse => (),
+                                };
+                                save_app_state(app)?;
+                            };
+                        }
+                        KeyCode::Char(c) => {
+                            app.prompt.push(c);
+                        }
+                        KeyCode::Backspace => {
+                            pop_grapheme(&mut app.prompt);
+                        }
+                        _ => (),
+                    };
+                    if new_input_mode == InputMode::Search {
+                        found_indices = search_snippets(&app.snippets, &app.input);
                     };
                 },
                 InputMode::ConfirmDelete(idx) => {
@@ -328,6 +682,117 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: &mut App, config: &S
                         _ => (),
                     };
                 }
+                InputMode::ConfirmRun(idx) => {
+                    match key.code {
+                        KeyCode::Char('y') => {
+                            new_input_mode = InputMode::Normal;
+                            let snip = app.get_snippet(idx).cloned();
+                            let interpreter = app.run_output.as_ref().map(|o| o.interpreter.clone());
+                            if let (Some(snip), Some(interpreter)) = (snip, interpreter) {
+                                match RunningSnippet::start(&snip, &interpreter) {
+                                    Ok(run) => {
+                                        running = Some(run);
+                                        // Started only now, so the duration doesn't include the confirmation
+                                        app.run_output = Some(RunOutput::new(&snip, &interpreter));
+                                        new_input_mode = InputMode::RunOutput;
+                                    },
+                                    Err(err) => app.status_message = err.to_string(),
+                                };
+                            };
+                        },
+                        KeyCode::Char('n') | KeyCode::Esc => {
+                            app.run_output = None;
+                            new_input_mode = InputMode::Normal;
+                        },
+                        _ => (),
+                    };
+                }
+                InputMode::RunOutput => {
+                    if let Some(output) = app.run_output.as_mut() {
+                        match key.code {
+                            // Stops the snippet if it's still running
+                            KeyCode::Esc => {
+                                running = None;
+                                new_input_mode = InputMode::Normal;
+                            }
+                            KeyCode::Up => {
+                                output.scroll_back = std::cmp::min(output.scroll_back + 1, output.lines.len().saturating_sub(1));
+                            }
+                            KeyCode::Down => {
+                                output.scroll_back = output.scroll_back.saturating_sub(1);
+                            }
+                            KeyCode::PageUp => {
+                                output.scroll_back = std::cmp::min(output.scroll_back + 10, output.lines.len().saturating_sub(1));
+                            }
+                            KeyCode::PageDown => {
+                                output.scroll_back = output.scroll_back.saturating_sub(10);
+                            }
+                            _ => (),
+                        };
+                    };
+                }
+                InputMode::ConfirmDuplicate(_) => {
+                    match key.code {
+                        KeyCode::Char('y') => {
+                            push_current_snippet = true;
+                            allow_duplicate = true;
+                            new_input_mode = InputMode::Normal;
+                        },
+                        // Go back to editing, so nothing typed so far is lost
+                        KeyCode::Char('n') | KeyCode::Esc => {
+                            if let Some(current_snip) = &app.current_snippet {
+                                app.input = current_snip.tags.join(" ");
+                            };
+                            new_input_mode = InputMode::NewSnippet(NewSnippetMode::TypeCode);
+                        },
+                        _ => (),
+                    };
+                }
+                InputMode::Duplicates(DuplicatesMode::Browse) => {
+                    match key.code {
+                        KeyCode::Esc => {
+                            new_input_mode = InputMode::Normal;
+                        }
+                        KeyCode::Up => {
+                            app.duplicate_groups.previous();
+                        }
+                        KeyCode::Down => {
+                            app.duplicate_groups.next();
+                        }
+                        KeyCode::Char('m') | KeyCode::Enter => {
+                            if let Some(selected) = app.duplicate_groups.state.selected() {
+                                let first_idx = app.duplicate_groups.items[selected][0];
+                                app.prompt = app.get_snippet(first_idx).map(|s| s.name.clone()).unwrap_or_default();
+                                new_input_mode = InputMode::Duplicates(DuplicatesMode::MergeName(selected));
+                            };
+                        }
+                        _ => (),
+                    };
+                }
+                InputMode::Duplicates(DuplicatesMode::MergeName(group_idx)) => {
+                    match key.code {
+                        KeyCode::Esc => {
+                            new_input_mode = InputMode::Duplicates(DuplicatesMode::Browse);
+                        }
+                        KeyCode::Enter => {
+                            let name = app.prompt.trim().to_string();
+                            if !name.is_empty() {
+                                let group = app.duplicate_groups.items[group_idx].clone();
+                                app.merge_snippets(&group, &name);
+                                app.refresh_duplicate_groups();
+                                save_app_state(app)?;
+                                new_input_mode = InputMode::Duplicates(DuplicatesMode::Browse);
+                            };
+                        }
+                        KeyCode::Char(c) => {
+                            app.prompt.push(c);
+                        }
+                        KeyCode::Backspace => {
+                            pop_grapheme(&mut app.prompt);
+                        }
+                        _ => (),
+                    };
+                }
             }
         };
         if clear_found_snippets {
@@ -335,19 +800,27 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: &mut App, config: &S
         };
         app.input_mode = new_input_mode.to_owned();
 
+        if let Some(snippet_idx) = edit_externally {
+            edit_snippet_externally(terminal, app, snippet_idx)?;
+        };
+
 
         if push_current_snippet {
-            // Save current snippet
-            if let Some(current_snip) = app.current_snippet.clone() {
-                // If currently editing an existing snippet
-                if app.has_snippet_with_idx(current_snip.idx) {
-                    app.remove_snippet(current_snip.idx);
+            // Save current snippet, unless it's a duplicate the user didn't confirm yet
+            let duplicate = match &app.current_snippet {
+                Some(current_snip) if !allow_duplicate => find_duplicates(&app.snippets, current_snip).first().map(|d| d.0),
+                _ => None,
+            };
+            if let Some(duplicate_idx) = duplicate {
+                app.input_mode = InputMode::ConfirmDuplicate(duplicate_idx);
+            } else {
+                if let Some(current_snip) = app.current_snippet.clone() {
+                    app.add_snippet(current_snip);
                 };
-                app.snippets.push(current_snip);
+                app.current_snippet = None;
+                found_indices = search_snippets(&app.snippets, &app.input);
+                save_app_state(app)?;
             };
-            app.current_snippet = None;
-            found_indices = search_snippets(&mut app.snippets, &app.input);
-            save_app_state(app);
         };
 
         // Call to delete a snippet
@@ -362,11 +835,22 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: &mut App, config: &S
             if let Some(remove_idx_in_found) = remove_idx_in_found {
                 found_indices.remove(remove_idx_in_found);
             };
-            save_app_state(app);
+            save_app_state(app)?;
         };
         
-        if app.input_mode == InputMode::Normal {
-            found_indices = search_snippets(&mut app.snippets, "")
+        if app.input_mode == InputMode::Normal || matches!(app.input_mode, InputMode::Related(_)) {
+            found_indices = search_snippets(&app.snippets, "")
+        };
+        // Saved searches are live collections, so their results are re-computed every time
+        if let InputMode::SavedSearches(saved_mode) = app.input_mode {
+            let query = match saved_mode {
+                SavedSearchMode::Query(_) => Some(app.prompt.clone()),
+                SavedSearchMode::Name(None) => Some(app.input.clone()),
+                _ => app.saved_searches.state.selected().map(|i| app.saved_searches.items[i].query.clone()),
+            };
+            if let Some(query) = query {
+                found_indices = search_snippets(&app.snippets, &query);
+            };
         };
         app.found_snippets.items.clear();
         for idx in found_indices.iter() {
@@ -375,34 +859,108 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: &mut App, config: &S
                 app.found_snippets.items.push(snip.clone());
             }
         }
+        if let Some(snip_idx) = jump_to_snippet {
+            let position = app.found_snippets.items.iter().position(|s| s.idx == snip_idx);
+            app.found_snippets.state.select(position);
+        };
         
         terminal.draw(|f| ui(f, app))?;
     }
 }
 
-fn search_snippets(snippets: &'_ mut [CodeSnippet], input: &str) -> Vec<(usize, usize)> {
-    let mut indices = Vec::<(usize, usize)>::new();
-    let input_lower = input.to_lowercase();
-    let input_lower = input_lower.as_str();
-    for (snippet_idx, snippet) in snippets.iter().enumerate() {
-        for tag in snippet.tags.iter() {
-            let name_lower = snippet.name.to_lowercase();
-            let tag_lower = tag.to_lowercase();
-            if (tag_lower.contains(input_lower) || name_lower.contains(input_lower)) && !indices.contains(&(snippet_idx, snippet.idx)) {
-                indices.push((snippet_idx, snippet.idx));
+/// Handles a key of the code field, returns whether the code changed.
+/// The clipboard keys are handled here, Ctrl-C and Ctrl-X copy and cut the selection
+fn code_editor_input(editor: &mut TextArea, key: KeyEvent) -> bool {
+    if key.modifiers != KeyModifiers::CONTROL || !matches!(key.code, KeyCode::Char('v' | 'c' | 'x')) {
+        return editor.input(key);
+    };
+    // Only the clipboard keys connect to the clipboard
+    let clipboard: Result<ClipboardContext, _> = ClipboardProvider::new();
+    let Ok(mut ctx) = clipboard else {
+        return editor.input(key);
+    };
+    match key.code {
+        KeyCode::Char('v') => {
+            match ctx.get_contents() {
+                Ok(paste_content) => {
+                    editor.insert_str(&paste_content);
+                    true
+                },
+                Err(_) => false,
+            }
+        },
+        KeyCode::Char('c') => {
+            if let Some(selected) = editor.selected_text() {
+                let _ = ctx.set_contents(selected);
             };
-        };
+            false
+        },
+        _ => {
+            match editor.selected_text() {
+                Some(selected) => {
+                    let _ = ctx.set_contents(selected);
+                    editor.delete_selection()
+                },
+                None => false,
+            }
+        },
+    }
+}
+
+/// Returns the existing tag the last word typed into the tags field can be completed to
+fn tag_completion(app: &App) -> Option<String> {
+    let last_word = app.input.rsplit(' ').next().unwrap_or("");
+    app.complete_tag(last_word)
+}
+
+/// Suspends the interface while the snippet is edited in $VISUAL / $EDITOR.
+/// The snippet is only changed if the editor exited successfully
+fn edit_snippet_externally<B: Backend + io::Write>(terminal: &mut Terminal<B>, app: &mut App, snippet_idx: usize) -> io::Result<()> {
+    let Some(mut snip) = app.get_snippet(snippet_idx).cloned() else {
+        return Ok(());
+    };
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    terminal.show_cursor()?;
+
+    let edited = edit_in_external_editor(&snip);
+
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+
+    match edited {
+        Ok(code) if code != snip.code => {
+            snip.code = code;
+            app.add_snippet(snip);
+            save_app_state(app)?;
+            app.status_message = String::from("Saved the changes from the editor");
+        },
+        Ok(_) => app.status_message = String::from("The snippet wasn't changed"),
+        Err(err) => app.status_message = format!("The snippet wasn't changed: {}", err),
     };
-    indices
+    Ok(())
 }
 
-fn edit_snippet_from_list<'a>(app: &mut App, cur_input_mode: InputMode) -> InputMode {
+/// Asks whether the snippet selected in the list should be deleted
+fn confirm_delete_selected(app: &App, cur_input_mode: InputMode) -> InputMode {
     let selected_snippet = app.found_snippets.state.selected();
     let mut new_input_mode = cur_input_mode;
     if let Some(selected_snip_idx) = selected_snippet {
-        let snip = &app.found_snippets.items[selected_snip_idx];
-        app.current_snippet = Some(snip.clone());
-        app.input = snip.tags.join(" ");
+        if !app.snippets.is_empty() {
+            let snip = &app.found_snippets.items[selected_snip_idx];
+            new_input_mode = InputMode::ConfirmDelete(snip.idx);
+        }
+    }
+    new_input_mode
+}
+
+fn edit_snippet_from_list(app: &mut App, cur_input_mode: InputMode) -> InputMode {
+    let selected_snippet = app.found_snippets.state.selected();
+    let mut new_input_mode = cur_input_mode;
+    if let Some(selected_snip_idx) = selected_snippet {
+        let snip = app.found_snippets.items[selected_snip_idx].clone();
+        app.start_editing(snip);
         new_input_mode = InputMode::NewSnippet(NewSnippetMode::TypeName);
     };
     new_input_mode
@@ -416,7 +974,7 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
         .margin(2)
         .constraints(
             [
-                Constraint::Length(6), // Title
+                Constraint::Length(12), // Title
                 Constraint::Length(1), // Just a bit of space
                 Constraint::Length(3), // Search field
                 Constraint::Max(2), // Found snippets field
@@ -425,6 +983,7 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
         )
         .split(f.size());
     let title_chunk = chunks[0];
+    let status_chunk = chunks[1];
     let search_chunk = chunks[2];
     let found_chunk = chunks[3];
 
@@ -453,21 +1012,67 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
         Spans::from(
             vec![Span::styled("    - x", keybinds_style), Span::styled(" to delete the selected snippet", Style::default())]
         ),
+        Spans::from(
+            vec![Span::styled("    - r", keybinds_style), Span::styled(" to jump to a snippet related to the selected one", Style::default())]
+        ),
+        Spans::from(
+            vec![Span::styled("    - d", keybinds_style), Span::styled(" to find and merge duplicate snippets", Style::default())]
+        ),
+        Spans::from(
+            vec![Span::styled("    - e", keybinds_style), Span::styled(" to edit the selected snippet in $EDITOR", Style::default())]
+        ),
+        Spans::from(
+            vec![Span::styled("    - g", keybinds_style), Span::styled(" to run the selected snippet", Style::default())]
+        ),
+        Spans::from(
+            vec![Span::styled("    - t", keybinds_style), Span::styled(" to manage tags (rename, merge, delete unused)", Style::default())]
+        ),
+        Spans::from(
+            vec![Span::styled("    - l", keybinds_style), Span::styled(" to open the saved searches (CTRL-S in Search Mode saves the search)", Style::default())]
+        ),
     ];
     let snippy_text = Text::from(snippy_title);
     let app_title = Paragraph::new(snippy_text);
     f.render_widget(app_title, title_chunk);
+    let status = Paragraph::new(Span::styled(app.status_message.as_str(), Style::default().fg(Color::DarkGray)));
+    f.render_widget(status, status_chunk);
 
     match app.input_mode {
-        InputMode::Normal | InputMode::Search => {
+        InputMode::Normal | InputMode::Search | InputMode::SavedSearches(_) | InputMode::HistorySearch(_) | InputMode::Related(_) => {
             let (mut title, mut t_color) = ("Normal Mode - Press f to go into Search Mode", Color::White);
+            let mut search_text = app.input.clone();
             if app.input_mode == InputMode::Search {
-                (title, t_color) = ("Search Mode - Press ESC to go back to Normal Mode", Color::Yellow);
+                (title, t_color) = ("Search Mode - Press ESC to go back to Normal Mode, CTRL-R to search the history", Color::Yellow);
+            } else if let InputMode::HistorySearch(_) = app.input_mode {
+                (title, t_color) = ("Search Mode", Color::Yellow);
+            } else if let InputMode::SavedSearches(_) = app.input_mode {
+                (title, t_color) = ("Saved Searches - Enter to run, r to rename, e to edit, x to delete, ESC to go back", Color::Cyan);
+                if let Some(selected) = app.saved_searches.state.selected() {
+                    search_text = app.saved_searches.items[selected].query.clone();
+                };
             }
                 
             
             // Draw Search field
-            input_field(f, &String::from(title), t_color, &app.input, true, &search_chunk);
+            input_field(f, &String::from(title), t_color, &search_text, app.input_mode == InputMode::Search || app.input_mode == InputMode::Normal, &search_chunk);
+
+            // The saved searches sidebar is only shown once there is something in it
+            let mut found_chunk = found_chunk;
+            let sidebar_focused = matches!(app.input_mode, InputMode::SavedSearches(_));
+            if sidebar_focused || !app.saved_searches.items.is_empty() {
+                let found_chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(25), Constraint::Percentage(75)].as_ref())
+                    .split(found_chunk);
+                saved_searches_sidebar(f, app, sidebar_focused, found_chunks[0]);
+                found_chunk = found_chunks[1];
+            };
+            let found_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                .split(found_chunk);
+            found_chunk = found_chunks[0];
+            snippet_preview(f, app, found_chunks[1]);
             
             let unselected_text_style = Style::default()
                 .add_modifier(Modifier::UNDERLINED);
@@ -500,6 +1105,24 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
 
             // We can now render the item list
             f.render_stateful_widget(items, found_chunk, &mut app.found_snippets.state);
+
+            if let InputMode::SavedSearches(saved_mode) = app.input_mode {
+                let title = match saved_mode {
+                    SavedSearchMode::Browse => None,
+                    SavedSearchMode::Name(None) => Some("Name of the saved search"),
+                    SavedSearchMode::Name(Some(_)) => Some("New name of the saved search"),
+                    SavedSearchMode::Query(_) => Some("Query of the saved search"),
+                };
+                if let Some(title) = title {
+                    let area = centered_rect(60, 5, false, f.size());
+                    f.render_widget(Clear, area); //this clears out the background
+                    let area = area.inner(&Margin { vertical: 1, horizontal: 0});
+                    input_field(f, &format!("{} (Enter to confirm, ESC to cancel)", title), Color::Cyan, &app.prompt, true, &area);
+                };
+            };
+            if let InputMode::HistorySearch(match_idx) = app.input_mode {
+                history_search_popup(f, app, match_idx);
+            };
         },
         InputMode::NewSnippet(new_mode) => {
             let block = Block::default()
@@ -519,7 +1142,7 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                     [
                         Constraint::Length(3), // Name Input
                         Constraint::Length(3), // Tags Input
-                        Constraint::Length(3), // Tags Input
+                        Constraint::Min(3), // Code Editor
                     ]
                     .as_ref(),
                 )
@@ -529,93 +1152,350 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
             let code_chunk = inner_chunks[2];
             
             if let Some(current_snippet) = &app.current_snippet {
+                let (line, column) = app.code_editor.cursor_position();
+                let code_title = match &app.vim {
+                    Some(vim) => format!("Code of the Snippet (:w to save the snippet, :q to close) - Ln {}, Col {} - {}", line, column, vim.status()),
+                    None => format!(
+                        "Code of the Snippet (press CTRL-S to save the snippet, ALT-Z to {} lines) - Ln {}, Col {}",
+                        if app.code_editor.wrap {"not wrap"} else {"wrap"},
+                        line,
+                        column,
+                    ),
+                };
                 let texts = [
                     String::from("Name of the Snippet"),
-                    String::from("Tags (separate by space)"),
-                    String::from("Code of the Snippet (press CTRL-S to save the snippet)"),
+                    String::from("Tags (separate by space, Tab to complete)"),
+                    code_title,
                 ];
                 input_field(f, &texts[0], Color::DarkGray, &current_snippet.name,new_mode==NewSnippetMode::TypeName, &name_chunk);
                 input_field(f, &texts[1], Color::DarkGray, &app.input, new_mode==NewSnippetMode::TypeTags, &tags_chunk);
-                input_field(f, &texts[2], Color::DarkGray, &current_snippet.code, new_mode==NewSnippetMode::TypeCode, &code_chunk);
+                if new_mode == NewSnippetMode::TypeTags {
+                    tag_completion_hint(f, app, &tags_chunk);
+                };
+                let code_title = Span::styled(&texts[2], Style::default().fg(Color::DarkGray).add_modifier(Modifier::BOLD));
+                let code_block = Block::default().borders(Borders::ALL).title(Spans::from(code_title));
+                app.code_editor.draw(f, code_block, code_chunk, new_mode==NewSnippetMode::TypeCode);
+            };
+        }
+        InputMode::TagManager(tag_mode) => {
+            let title = match tag_mode {
+                TagManagerMode::Merge(source_idx) => format!(
+                    "Merge '{}' into the selected tag - Enter to merge, ESC to cancel",
+                    app.tag_list.items[source_idx]
+                ),
+                _ => String::from("Tags - r to rename, m to merge, x to delete an unused tag, u to delete all unused tags, ESC to go back"),
+            };
+            let counts = app.tag_counts();
+            let items: Vec<ListItem> = app
+                .tag_list
+                .items
+                .iter()
+                .map(|tag| {
+                    let count = counts.iter().find(|(t, _)| t == tag).map(|(_, c)| *c).unwrap_or(0);
+                    let style = if count == 0 {Style::default().fg(Color::DarkGray)} else {Style::default()};
+                    ListItem::new(Spans::from(Span::styled(format!("{} ({})", tag, count), style)))
+                })
+                .collect();
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(Span::styled(title, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))))
+                .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Rgb(60, 63, 71)))
+                .highlight_symbol(">> ");
+            let list_area = Rect::new(search_chunk.x, search_chunk.y, search_chunk.width, found_chunk.bottom() - search_chunk.y);
+            f.render_stateful_widget(list, list_area, &mut app.tag_list.state);
+
+            if let TagManagerMode::Rename(_) = tag_mode {
+                let area = centered_rect(60, 5, false, f.size());
+                f.render_widget(Clear, area); //this clears out the background
+                let area = area.inner(&Margin { vertical: 1, horizontal: 0});
+                input_field(f, &String::from("New name of the tag (Enter to confirm, ESC to cancel)"), Color::Cyan, &app.prompt, true, &area);
             };
         }
         InputMode::ConfirmDelete(_) => {
-            let block = Block::default()
-                .title("Confirm Deletion of selected Snippet.")
-                .borders(Borders::all())
-                .border_style(Style::default().fg(Color::Red))
-                .border_type(BorderType::Double);
-            let area = centered_rect(35, 8, false, f.size());
-            f.render_widget(Clear, area); //this clears out the background
-            f.render_widget(block, area);
-            
-            let inner_area = area.inner(&Margin { vertical: 1, horizontal: 2});
-            let inner_chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .margin(1)
-                .constraints(
-                    [
-                        Constraint::Length(2), // Name Input
-                        Constraint::Length(3), // Tags Input
-                    ]
-                    .as_ref(),
-                )
-                .split(inner_area);
-            let info_text = Spans::from(Span::styled("Do you really want to delete this snippet?", Style::default()));
-            let yes = Spans::from(
-                Span::styled("Yes (y)", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
-            );
-            let no = Spans::from(
-                Span::styled("No (n)", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
-            );
-            
-            let para1 = Paragraph::new(info_text)
-                .style(Style::default())
-                .alignment(Alignment::Center)
-                .wrap(Wrap{trim: false});
-            f.render_widget(para1, inner_chunks[0]);
-            let yes_para = Paragraph::new(yes)
-                .style(Style::default())
-                .alignment(Alignment::Left);
-            f.render_widget(yes_para, inner_chunks[1]);
-            let no_para = Paragraph::new(no)
-                .style(Style::default())
-                .alignment(Alignment::Right);
-            f.render_widget(no_para, inner_chunks[1]);
+            confirm_popup(f, "Confirm Deletion of selected Snippet.", "Do you really want to delete this snippet?", 35, 8);
+        }
+        InputMode::ConfirmDuplicate(duplicate_idx) => {
+            let (name, kind) = match &app.current_snippet {
+                Some(current_snip) => {
+                    let kind = find_duplicates(&app.snippets, current_snip)
+                        .into_iter()
+                        .find(|d| d.0 == duplicate_idx)
+                        .map(|d| d.1);
+                    (app.get_snippet(duplicate_idx).map(|s| s.name.clone()).unwrap_or_default(), kind)
+                },
+                None => (String::new(), None),
+            };
+            let kind_text = match kind {
+                Some(DuplicateKind::IgnoringWhitespace) => "almost the same code (ignoring whitespace) as",
+                _ => "the same code as",
+            };
+            let question = format!("This snippet has {} '{}'. Save it anyway?", kind_text, name);
+            confirm_popup(f, "Duplicate Snippet", &question, 50, 9);
+        }
+        InputMode::Duplicates(duplicates_mode) => {
+            let title = "Duplicates - Enter or m to merge the selected group, ESC to go back";
+            let items: Vec<ListItem> = app
+                .duplicate_groups
+                .items
+                .iter()
+                .map(|group| {
+                    let names: Vec<String> = group
+                        .iter()
+                        .filter_map(|idx| app.get_snippet(*idx))
+                        .map(|s| format!("{} [{}]", s.name, s.tags.join(", ")))
+                        .collect();
+                    ListItem::new(Spans::from(Span::raw(names.join("  |  "))))
+                })
+                .collect();
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(Span::styled(title, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))))
+                .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Rgb(60, 63, 71)))
+                .highlight_symbol(">> ");
+            let list_area = Rect::new(search_chunk.x, search_chunk.y, search_chunk.width, found_chunk.bottom() - search_chunk.y);
+            f.render_stateful_widget(list, list_area, &mut app.duplicate_groups.state);
+
+            if let DuplicatesMode::MergeName(_) = duplicates_mode {
+                let area = centered_rect(60, 5, false, f.size());
+                f.render_widget(Clear, area); //this clears out the background
+                let area = area.inner(&Margin { vertical: 1, horizontal: 0});
+                input_field(f, &String::from("Name of the merged snippet (Enter to merge, ESC to cancel)"), Color::Cyan, &app.prompt, true, &area);
+            };
+        }
+        InputMode::ConfirmRun(_) => {
+            let question = match &app.run_output {
+                Some(output) => format!("Run '{}' with {}?", output.name, output.interpreter.join(" ")),
+                None => String::new(),
+            };
+            confirm_popup(f, "Run Snippet", &question, 50, 9);
+        }
+        InputMode::RunOutput => {
+            if let Some(output) = &app.run_output {
+                let output_area = Rect::new(search_chunk.x, search_chunk.y, search_chunk.width, found_chunk.bottom() - search_chunk.y);
+                run_output_pane(f, output, output_area);
+            };
         }
     }
     
 }
 
+/// Output of a running (or finished) snippet, stderr in red, scrolled to the end unless the user scrolled up
+fn run_output_pane<B: Backend>(f: &mut Frame<B>, output: &RunOutput, render_area: Rect) {
+    let color = match output.finished {
+        None => Color::Yellow,
+        Some((Some(0), _)) => Color::Green,
+        Some(_) => Color::Red,
+    };
+    let title = format!(
+        "Output - {} ({}) - {} - Up/Down to scroll, ESC to {}",
+        output.name,
+        output.interpreter.join(" "),
+        output.status_text(),
+        if output.is_running() {"stop"} else {"close"},
+    );
+    let visible_lines = render_area.height.saturating_sub(2) as usize;
+    let end = output.lines.len().saturating_sub(output.scroll_back);
+    let start = end.saturating_sub(visible_lines);
+    let lines: Vec<Spans> = output.lines[start..end]
+        .iter()
+        .map(|line| match line.is_stderr {
+            true => Spans::from(Span::styled(expand_tabs(&line.text), Style::default().fg(Color::Red))),
+            false => Spans::from(Span::raw(expand_tabs(&line.text))),
+        })
+        .collect();
+    let pane = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(color)).title(Span::styled(title, Style::default().fg(color).add_modifier(Modifier::BOLD))));
+    f.render_widget(pane, render_area);
+}
+
+/// Popup asking a yes (y) / no (n) question
+fn confirm_popup<B: Backend>(f: &mut Frame<B>, title: &str, question: &str, width: u16, height: u16) {
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::all())
+        .border_style(Style::default().fg(Color::Red))
+        .border_type(BorderType::Double);
+    let area = centered_rect(width, height, false, f.size());
+    f.render_widget(Clear, area); //this clears out the background
+    f.render_widget(block, area);
+    
+    let inner_area = area.inner(&Margin { vertical: 1, horizontal: 2});
+    let inner_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Length(height.saturating_sub(6)), // Question
+                Constraint::Length(3), // Answers
+            ]
+            .as_ref(),
+        )
+        .split(inner_area);
+    let info_text = Spans::from(Span::styled(question, Style::default()));
+    let yes = Spans::from(
+        Span::styled("Yes (y)", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+    );
+    let no = Spans::from(
+        Span::styled("No (n)", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+    );
+    
+    let para1 = Paragraph::new(info_text)
+        .style(Style::default())
+        .alignment(Alignment::Center)
+        .wrap(Wrap{trim: false});
+    f.render_widget(para1, inner_chunks[0]);
+    let yes_para = Paragraph::new(yes)
+        .style(Style::default())
+        .alignment(Alignment::Left);
+    f.render_widget(yes_para, inner_chunks[1]);
+    let no_para = Paragraph::new(no)
+        .style(Style::default())
+        .alignment(Alignment::Right);
+    f.render_widget(no_para, inner_chunks[1]);
+}
+
+fn preview_title(snip: &CodeSnippet) -> String {
+    match &snip.language {
+        Some(language) => format!("Preview - {} ({})", snip.name, language),
+        None => format!("Preview - {}", snip.name),
+    }
+}
+
+/// Shows the code of the selected snippet and the snippets related to it
+fn snippet_preview<B: Backend>(f: &mut Frame<B>, app: &App, render_area: Rect) {
+    let selected = app.found_snippets.state.selected().and_then(|i| app.found_snippets.items.get(i));
+    let block = Block::default().borders(Borders::ALL).title(Span::styled("Preview", Style::default().add_modifier(Modifier::BOLD)));
+    let Some(snip) = selected else {
+        f.render_widget(block, render_area);
+        return;
+    };
+
+    let related = app.related_snippets(snip.idx, RELATED_SNIPPETS);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(related.len() as u16 + 2)].as_ref())
+        .split(render_area);
+
+    let code = Paragraph::new(expand_tabs(&snip.code))
+        .block(block.title(Span::styled(preview_title(snip), Style::default().add_modifier(Modifier::BOLD))));
+    f.render_widget(code, chunks[0]);
+
+    let items: Vec<ListItem> = related
+        .iter()
+        .map(|rel| ListItem::new(Spans::from(Span::raw(format!("{}, Tags: [{}]", rel.name, rel.tags.join(", "))))))
+        .collect();
+    let (title, border_color) = match app.input_mode {
+        InputMode::Related(_) => ("Related - Enter to jump, ESC to go back", Color::Cyan),
+        _ => ("Related - Press r to select", Color::White),
+    };
+    let list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(border_color))
+            .title(Span::styled(title, Style::default().add_modifier(Modifier::BOLD))))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Rgb(60, 63, 71)))
+        .highlight_symbol(">> ");
+    let mut state = ListState::default();
+    if let InputMode::Related(related_idx) = app.input_mode {
+        state.select(Some(related_idx));
+    };
+    f.render_stateful_widget(list, chunks[1], &mut state);
+}
+
+/// Shows the rest of the suggested tag behind the cursor of the tags field
+fn tag_completion_hint<B: Backend>(f: &mut Frame<B>, app: &App, render_area: &Rect) {
+    let last_word = app.input.rsplit(' ').next().unwrap_or("");
+    if let Some(tag) = tag_completion(app) {
+        let rest: String = tag.chars().skip(last_word.chars().count()).collect();
+        let x = render_area.x + 1 + display_width(&app.input) as u16;
+        if x + 1 >= render_area.right() {
+            return;
+        };
+        let width = std::cmp::min(display_width(&rest) as u16, render_area.right() - x - 1);
+        let hint = Paragraph::new(Span::styled(rest, Style::default().fg(Color::DarkGray)));
+        f.render_widget(hint, Rect::new(x, render_area.y + 1, width, 1));
+    };
+}
+
+fn history_search_popup<B: Backend>(f: &mut Frame<B>, app: &App, match_idx: usize) {
+    let area = centered_rect(70, 14, false, f.size());
+    f.render_widget(Clear, area); //this clears out the background
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(3)].as_ref())
+        .split(area);
+
+    let title = String::from("Reverse search (CTRL-R older, CTRL-S newer, Enter to accept, ESC to cancel)");
+    input_field(f, &title, Color::Yellow, &app.prompt, true, &chunks[0]);
+
+    let matches = app.search_history_matches(&app.prompt);
+    let items: Vec<ListItem> = matches
+        .iter()
+        .map(|entry| ListItem::new(Spans::from(Span::raw(entry.as_str()))))
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(Span::styled("Search History", Style::default().add_modifier(Modifier::BOLD))))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Rgb(60, 63, 71)))
+        .highlight_symbol(">> ");
+    let mut state = ListState::default();
+    if !matches.is_empty() {
+        state.select(Some(match_idx.min(matches.len() - 1)));
+    };
+    f.render_stateful_widget(list, chunks[1], &mut state);
+}
+
+fn saved_searches_sidebar<B: Backend>(f: &mut Frame<B>, app: &mut App, focused: bool, render_area: Rect) {
+    let border_color = if focused {Color::Cyan} else {Color::White};
+    let items: Vec<ListItem> = app
+        .saved_searches
+        .items
+        .iter()
+        .map(|saved| {
+            let count = search_snippets(&app.snippets, &saved.query).len();
+            ListItem::new(Spans::from(Span::raw(format!("{} ({})", saved.name, count))))
+        })
+        .collect();
+
+    let mut list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(border_color))
+            .title(Span::styled("Saved Searches", Style::default().add_modifier(Modifier::BOLD))));
+    if focused {
+        list = list
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Rgb(60, 63, 71)))
+            .highlight_symbol(">> ");
+    };
+    f.render_stateful_widget(list, render_area, &mut app.saved_searches.state);
+}
+
 fn input_field<B: Backend>(f: &mut Frame<B>, input_title: &String, title_color: Color, input: &str, set_cursor: bool, render_area: &Rect) {
     let txt = Span::styled(input_title, Style::default()
         .fg(title_color)
         .add_modifier(Modifier::BOLD)
     );
-    let mut input_para = Paragraph::new(input)
+    let mut input_para = Paragraph::new(expand_tabs(input))
         .style(Style::default())
         .block(Block::default().borders(Borders::ALL).title(Spans::from(txt)));
     
-    let mut len_measure = input.len();
-    let lines = input.split('\n');
+    let mut len_measure = display_width(input);
+    let mut lines = input.split('\n');
     let mut line_count = lines.clone().count() as u16;
     line_count = std::cmp::max(line_count, 1);
     let offset = 3;
     let mut did_scroll = false;
     if render_area.height > 1 && line_count > render_area.height - 2 {
         let scoll_amt = (line_count + offset) - render_area.height;
-        input_para = input_para.scroll((scoll_amt as u16, 0));
+        input_para = input_para.scroll((scoll_amt, 0));
         did_scroll = true;
     }
     f.render_widget(input_para, *render_area);
     if set_cursor {
-        if let Some(last_line) = lines.last() {
-            len_measure = last_line.len();
+        if let Some(last_line) = lines.next_back() {
+            len_measure = display_width(last_line);
             if last_line.ends_with('\n') {
                 line_count += 1;
             }
         }
-        let mut y_val = render_area.y + line_count as u16;
+        let mut y_val = render_area.y + line_count;
         if did_scroll {
             y_val = render_area.y + render_area.height - offset;
         }
@@ -647,7 +1527,7 @@ fn centered_rect(value_x: u16, value_y: u16, mut use_percentage: bool, r: Rect)
     
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(contraints_y.as_ref())
+        .constraints(contraints_y)
         .split(r);
 
     let contraints_x = match use_percentage {
@@ -666,6 +1546,6 @@ fn centered_rect(value_x: u16, value_y: u16, mut use_percentage: bool, r: Rect)
     
     Layout::default()
         .direction(Direction::Horizontal)
-        .constraints(contraints_x.as_ref())
+        .constraints(contraints_x)
         .split(popup_layout[1])[1]
 }
\ No newline at end of file
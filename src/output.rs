@@ -0,0 +1,115 @@
+use std::io::{self, Write};
+use clap::ValueEnum;
+use serde_derive::Serialize;
+
+use crate::snippet::CodeSnippet;
+
+/// Version of the machine-readable output, see docs/output-format.md.
+/// Has to be increased whenever a field is renamed, removed or changes its meaning
+pub const FORMAT_VERSION: u32 = 1;
+
+
+#[derive(Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable
+    #[default]
+    Plain,
+    /// One JSON document holding all snippets
+    Json,
+    /// One JSON object per line
+    Jsonl,
+    /// Tab separated values with a header row
+    Tsv,
+}
+
+
+/// The stable fields of a snippet that are written in the machine-readable formats
+#[derive(Serialize)]
+pub struct SnippetRecord<'a> {
+    pub id: usize,
+    pub name: &'a str,
+    pub tags: &'a [String],
+    pub language: Option<&'a str>,
+    pub code: &'a str,
+}
+impl<'a> SnippetRecord<'a> {
+    pub fn new(snippet: &'a CodeSnippet) -> SnippetRecord<'a> {
+        SnippetRecord {
+            id: snippet.idx,
+            name: &snippet.name,
+            tags: &snippet.tags,
+            language: snippet.language.as_deref(),
+            code: &snippet.code,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonDocument<'a> {
+    version: u32,
+    snippets: Vec<SnippetRecord<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    version: u32,
+    #[serde(flatten)]
+    snippet: SnippetRecord<'a>,
+}
+
+
+/// Writes the snippets in the given format. `plain` is the short listing of id, name and tags
+pub fn write_snippets(out: &mut impl Write, snippets: &[&CodeSnippet], format: OutputFormat) -> io::Result<()> {
+    match format {
+        OutputFormat::Plain => {
+            for snip in snippets {
+                writeln!(out, "{}\t{}\t{}", snip.idx, snip.name, snip.tags.join(","))?;
+            };
+        },
+        OutputFormat::Json => {
+            let document = JsonDocument {
+                version: FORMAT_VERSION,
+                snippets: snippets.iter().map(|s| SnippetRecord::new(s)).collect(),
+            };
+            serde_json::to_writer_pretty(&mut *out, &document)?;
+            writeln!(out)?;
+        },
+        OutputFormat::Jsonl => {
+            for snip in snippets {
+                let line = JsonLine { version: FORMAT_VERSION, snippet: SnippetRecord::new(snip) };
+                serde_json::to_writer(&mut *out, &line)?;
+                writeln!(out)?;
+            };
+        },
+        OutputFormat::Tsv => {
+            writeln!(out, "id\tname\ttags\tlanguage\tcode")?;
+            for snip in snippets {
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}\t{}",
+                    snip.idx,
+                    escape_tsv(&snip.name),
+                    escape_tsv(&snip.tags.join(",")),
+                    escape_tsv(snip.language.as_deref().unwrap_or("")),
+                    escape_tsv(&snip.code),
+                )?;
+            };
+        },
+    };
+    Ok(())
+}
+
+/// Backslash, tab and newlines are written as `\\`, `\t`, `\n` and `\r` so every snippet stays on one line
+fn escape_tsv(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        };
+    };
+    escaped
+}
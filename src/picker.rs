@@ -0,0 +1,331 @@
+use std::io::{self, Read, Write};
+use crossterm::{
+    cursor::MoveTo,
+    event::{self, Event, KeyCode, KeyModifiers},
+    queue,
+    terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType},
+};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+    Frame, Terminal, TerminalOptions, Viewport,
+};
+
+#[cfg(not(unix))]
+use crossterm::cursor;
+
+use crate::{
+    app::App,
+    search::search_snippets,
+    snippet::CodeSnippet,
+    unicode::{display_width, expand_tabs, pop_grapheme},
+    StatefulList,
+};
+
+/// How many lines of the terminal the picker uses
+const PICKER_HEIGHT: u16 = 14;
+/// How many lines of the terminal the review of an import uses, it needs room for the code
+const REVIEW_HEIGHT: u16 = 20;
+
+
+/// State of the picker while it's open
+struct Picker<'a> {
+    app: &'a App,
+    query: String,
+    multi: bool,
+    /// Snippet idxs matching the query
+    results: StatefulList<usize>,
+    /// Snippet idxs marked with Tab, in the order they were marked
+    marked: Vec<usize>,
+}
+
+impl<'a> Picker<'a> {
+    fn new(app: &'a App, query: &str, multi: bool) -> Picker<'a> {
+        let mut picker = Picker {
+            app,
+            query: query.to_string(),
+            multi,
+            results: StatefulList::with_items(vec![]),
+            marked: vec![],
+        };
+        picker.update_results();
+        picker
+    }
+
+    fn update_results(&mut self) {
+        self.results.items = search_snippets(&self.app.snippets, &self.query)
+            .into_iter()
+            .map(|(_, snippet_idx)| snippet_idx)
+            .collect();
+        self.results.unselect();
+        self.results.next();
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.results.state.selected().and_then(|i| self.results.items.get(i).copied())
+    }
+
+    fn toggle_mark(&mut self) {
+        if let Some(snippet_idx) = self.selected() {
+            match self.marked.iter().position(|m| *m == snippet_idx) {
+                Some(position) => {
+                    self.marked.remove(position);
+                },
+                None => self.marked.push(snippet_idx),
+            };
+        };
+    }
+}
+
+/// State of the review while it's open
+struct Review<'a> {
+    snippets: &'a [CodeSnippet],
+    /// Whether each snippet is kept, all are at first
+    keep: Vec<bool>,
+    /// Positions in `snippets`
+    list: StatefulList<usize>,
+}
+
+
+/// Lets the user pick snippets in a small search interface drawn below the cursor,
+/// without using the alternate screen. The interface is drawn to the terminal directly,
+/// so stdout stays free for the result.
+/// Returns the idxs of the picked snippets or None if the user cancelled
+pub fn pick_snippets(app: &App, query: &str, multi: bool) -> io::Result<Option<Vec<usize>>> {
+    let mut picker = Picker::new(app, query, multi);
+    run_inline(PICKER_HEIGHT, |terminal| run_picker(terminal, &mut picker))
+}
+
+/// Lets the user go through snippets that are about to be imported and unmark the ones not to keep,
+/// drawn like the picker. Returns which snippets to keep or None if the user cancelled
+pub fn review_snippets(snippets: &[CodeSnippet]) -> io::Result<Option<Vec<bool>>> {
+    let mut review = Review {
+        snippets,
+        keep: vec![true; snippets.len()],
+        list: StatefulList::with_items((0..snippets.len()).collect()),
+    };
+    review.list.next();
+    run_inline(REVIEW_HEIGHT, |terminal| run_review(terminal, &mut review))
+}
+
+/// Runs `interface` in `height` lines below the cursor and removes it again afterwards
+fn run_inline<T>(
+    height: u16,
+    interface: impl FnOnce(&mut Terminal<CrosstermBackend<Box<dyn Write>>>) -> io::Result<T>,
+) -> io::Result<T> {
+    let (width, rows) = terminal::size()?;
+    let height = std::cmp::min(height, rows);
+
+    let raw_mode = RawMode::enable()?;
+    let (tty, cursor_y) = make_room(height)?;
+    let area = Rect::new(0, (cursor_y + 1).saturating_sub(height), width, height);
+
+    let backend = CrosstermBackend::new(tty);
+    let mut terminal = Terminal::with_options(backend, TerminalOptions { viewport: Viewport::fixed(area) })?;
+    let result = interface(&mut terminal);
+
+    // Remove the interface again and leave the cursor where it started
+    let cleared = queue!(terminal.backend_mut(), MoveTo(0, area.y), Clear(ClearType::FromCursorDown))
+        .and_then(|_| terminal.show_cursor())
+        .and_then(|_| Write::flush(terminal.backend_mut()));
+    drop(raw_mode);
+    cleared?;
+    result
+}
+
+/// Keeps the terminal in raw mode until it's dropped, also when returning early with an error
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<RawMode> {
+        enable_raw_mode()?;
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Scrolls the terminal content up (if needed) until there are `height` lines below the cursor.
+/// Returns where to draw to and the row the cursor ends up in
+#[cfg(unix)]
+fn make_room(height: u16) -> io::Result<(Box<dyn Write>, u16)> {
+    let mut tty = std::fs::OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+    for _i in 1..height {
+        write!(tty, "\r\n")?;
+    }
+    // crossterm's cursor::position() asks on stdout, which is reserved for the result here
+    write!(tty, "\x1b[6n")?;
+    tty.flush()?;
+    let mut response = vec![];
+    let mut byte = [0u8; 1];
+    while tty.read(&mut byte)? == 1 && byte[0] != b'R' && response.len() < 32 {
+        response.push(byte[0]);
+    }
+    // The response looks like ESC [ row ; column R
+    let response = String::from_utf8_lossy(&response);
+    let row = response
+        .rsplit('[')
+        .next()
+        .and_then(|pos| pos.split(';').next())
+        .and_then(|row| row.parse::<u16>().ok())
+        .ok_or_else(|| io::Error::other("could not read the cursor position"))?;
+    Ok((Box::new(tty), row - 1))
+}
+
+#[cfg(not(unix))]
+fn make_room(height: u16) -> io::Result<(Box<dyn Write>, u16)> {
+    let mut tty = io::stderr();
+    for _i in 1..height {
+        write!(tty, "\r\n")?;
+    }
+    tty.flush()?;
+    let (_, row) = cursor::position()?;
+    Ok((Box::new(tty), row))
+}
+
+fn run_picker<B: Backend>(terminal: &mut Terminal<B>, picker: &mut Picker) -> io::Result<Option<Vec<usize>>> {
+    loop {
+        terminal.draw(|f| picker_ui(f, picker))?;
+
+        if let Event::Key(key) = event::read()? {
+            let ctrl = key.modifiers == KeyModifiers::CONTROL;
+            match key.code {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Char('c') if ctrl => return Ok(None),
+                KeyCode::Enter => {
+                    if !picker.marked.is_empty() {
+                        return Ok(Some(picker.marked.clone()));
+                    };
+                    if let Some(snippet_idx) = picker.selected() {
+                        return Ok(Some(vec![snippet_idx]));
+                    };
+                }
+                KeyCode::Up => picker.results.previous(),
+                KeyCode::Char('p') if ctrl => picker.results.previous(),
+                KeyCode::Down => picker.results.next(),
+                KeyCode::Char('n') if ctrl => picker.results.next(),
+                KeyCode::Tab if picker.multi => {
+                    picker.toggle_mark();
+                    picker.results.next();
+                }
+                KeyCode::Char(c) if !ctrl => {
+                    picker.query.push(c);
+                    picker.update_results();
+                }
+                KeyCode::Backspace => {
+                    pop_grapheme(&mut picker.query);
+                    picker.update_results();
+                }
+                _ => (),
+            };
+        };
+    }
+}
+
+fn picker_ui<B: Backend>(f: &mut Frame<B>, picker: &mut Picker) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(f.size());
+
+    let title = match picker.multi {
+        true => "Pick snippets - Tab to mark, Enter to select, ESC to cancel",
+        false => "Pick a snippet - Enter to select, ESC to cancel",
+    };
+    let input = Paragraph::new(picker.query.as_str())
+        .block(Block::default().borders(Borders::ALL).title(Span::styled(title, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))));
+    f.render_widget(input, chunks[0]);
+    f.set_cursor(chunks[0].x + display_width(&picker.query) as u16 + 1, chunks[0].y + 1);
+
+    let items: Vec<ListItem> = picker
+        .results
+        .items
+        .iter()
+        .filter_map(|idx| picker.app.get_snippet(*idx))
+        .map(|snip| {
+            let mark = if picker.marked.contains(&snip.idx) {"* "} else {"  "};
+            ListItem::new(Spans::from(Span::raw(format!("{}{}, Tags: [{}]", mark, snip.name, snip.tags.join(", ")))))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!("{} found", picker.results.items.len())))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Rgb(60, 63, 71)))
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, chunks[1], &mut picker.results.state);
+}
+
+fn run_review<B: Backend>(terminal: &mut Terminal<B>, review: &mut Review) -> io::Result<Option<Vec<bool>>> {
+    loop {
+        terminal.draw(|f| review_ui(f, review))?;
+
+        if let Event::Key(key) = event::read()? {
+            let ctrl = key.modifiers == KeyModifiers::CONTROL;
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+                KeyCode::Char('c') if ctrl => return Ok(None),
+                KeyCode::Enter => return Ok(Some(review.keep.clone())),
+                KeyCode::Up | KeyCode::Char('k') => review.list.previous(),
+                KeyCode::Down | KeyCode::Char('j') => review.list.next(),
+                KeyCode::Char(' ') | KeyCode::Tab => {
+                    if let Some(i) = review.list.state.selected() {
+                        review.keep[i] = !review.keep[i];
+                        review.list.next();
+                    };
+                }
+                KeyCode::Char('a') => {
+                    let keep_all = review.keep.iter().any(|k| !k);
+                    review.keep.iter_mut().for_each(|k| *k = keep_all);
+                }
+                _ => (),
+            };
+        };
+    }
+}
+
+fn review_ui<B: Backend>(f: &mut Frame<B>, review: &mut Review) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(f.size());
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .split(rows[1]);
+
+    let kept = review.keep.iter().filter(|k| **k).count();
+    let title = "Review the import - Space to toggle, a for all, Enter to import, ESC to cancel";
+    let summary = Paragraph::new(format!("Keeping {} of {}", kept, review.snippets.len()))
+        .block(Block::default().borders(Borders::ALL).title(Span::styled(title, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))));
+    f.render_widget(summary, rows[0]);
+
+    let items: Vec<ListItem> = review
+        .snippets
+        .iter()
+        .zip(&review.keep)
+        .map(|(snip, keep)| {
+            let mark = if *keep {"[x] "} else {"[ ] "};
+            let language = snip.language.as_deref().unwrap_or("no language");
+            ListItem::new(Spans::from(Span::raw(format!("{}{} ({})", mark, snip.name, language))))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("Snippets"))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Rgb(60, 63, 71)))
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, chunks[0], &mut review.list.state);
+
+    let selected = review.list.state.selected().and_then(|i| review.snippets.get(i));
+    let (title, code) = match selected {
+        Some(snip) => (snip.source.clone().unwrap_or_default(), snip.code.as_str()),
+        None => (String::new(), ""),
+    };
+    let preview = Paragraph::new(expand_tabs(code)).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(preview, chunks[1]);
+}
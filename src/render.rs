@@ -0,0 +1,265 @@
+use clap::{Args, ValueEnum};
+use resvg::{tiny_skia, usvg};
+use syntect::highlighting::{Color, FontStyle, Style};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    highlight::{find_theme, highlight_lines, theme_set},
+    interop::escape_xml,
+    snippet::CodeSnippet,
+    unicode::{display_width, grapheme_width},
+};
+
+/// Advance of a character of a monospace font, in font sizes
+const CHAR_WIDTH: f32 = 0.6;
+/// Height of a line, in font sizes
+const LINE_HEIGHT: f32 = 1.5;
+/// Space between the window border and the code, in font sizes
+const WINDOW_PADDING: f32 = 1.5;
+/// Height of the title bar with the window buttons, in font sizes
+const TITLE_BAR_HEIGHT: f32 = 2.5;
+/// Colors of the close, minimize and maximize buttons
+const WINDOW_BUTTONS: [&str; 3] = ["#ff5f56", "#ffbd2e", "#27c93f"];
+
+
+#[derive(Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum ImageFormat {
+    /// Scalable, text stays text
+    #[default]
+    Svg,
+    /// Rasterized in software, no display or browser needed
+    Png,
+}
+
+/// How the picture of a snippet looks
+#[derive(Args)]
+pub struct RenderOptions {
+    /// Highlighting theme: base16-ocean.dark, base16-eighties.dark, base16-mocha.dark, base16-ocean.light,
+    /// InspiredGitHub, Solarized (dark) or Solarized (light)
+    #[arg(long, default_value = "base16-ocean.dark")]
+    pub theme: String,
+    /// Font family of the code, another monospace font is used if it isn't installed
+    #[arg(long, default_value = "DejaVu Sans Mono")]
+    pub font: String,
+    #[arg(long, default_value_t = 14.0)]
+    pub font_size: f32,
+    /// Space around the window
+    #[arg(long, default_value_t = 32.0)]
+    pub padding: f32,
+    #[arg(long)]
+    pub line_numbers: bool,
+    /// Color around the window (like `#abb8c3` or `white`), or `none`
+    #[arg(long, default_value = "#abb8c3")]
+    pub background: String,
+    /// Pixels per unit of the PNG, 2 keeps it sharp on high resolution screens
+    #[arg(long, default_value_t = 2.0)]
+    pub scale: f32,
+}
+
+
+/// Names of the themes `RenderOptions::theme` can be
+pub fn theme_names() -> Vec<&'static str> {
+    theme_set().themes.keys().map(String::as_str).collect()
+}
+
+/// The snippet in a window with a title bar, highlighted in the colors of the theme
+pub fn render_svg(snip: &CodeSnippet, options: &RenderOptions) -> Result<String, String> {
+    let theme = find_theme(&options.theme)
+        .ok_or_else(|| format!("unknown theme '{}', the themes are: {}", options.theme, theme_names().join(", ")))?;
+    let size = options.font_size;
+    let char_width = size * CHAR_WIDTH;
+    let line_height = size * LINE_HEIGHT;
+    let window_padding = size * WINDOW_PADDING;
+    let title_bar_height = size * TITLE_BAR_HEIGHT;
+
+    let mut lines = highlight_lines(&snip.code, snip.language.as_deref(), theme);
+    if lines.is_empty() {
+        lines.push(vec![]);
+    };
+    let lines: Vec<(Vec<(TokenStyle, String)>, usize)> = lines.into_iter().map(expand_tabs).collect();
+    let columns = lines.iter().map(|(_, columns)| *columns).max().unwrap_or(0);
+    let gutter_columns = match options.line_numbers {
+        true => lines.len().to_string().len() + 2,
+        false => 0,
+    };
+
+    // The title is a bit smaller than the code and has to fit between the buttons and the right edge
+    let title_size = size * 0.85;
+    let buttons_width = window_padding + size * 3.5;
+    let title_width = display_width(&snip.name) as f32 * title_size * CHAR_WIDTH;
+    let code_width = (gutter_columns + columns) as f32 * char_width;
+    let window_width = (window_padding * 2.0 + code_width).max(2.0 * (buttons_width + window_padding) + title_width);
+    let window_height = title_bar_height + lines.len() as f32 * line_height + window_padding;
+    let width = window_width + options.padding * 2.0;
+    let height = window_height + options.padding * 2.0;
+
+    let background = color_or(theme.settings.background, Color::BLACK);
+    let foreground = color_or(theme.settings.foreground, Color::WHITE);
+    let gutter = theme.settings.gutter_foreground.map(hex).unwrap_or_else(|| foreground.clone());
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+        w = px(width),
+        h = px(height),
+    );
+    svg.push_str(&format!(
+        "<defs><filter id=\"shadow\" x=\"-20%\" y=\"-20%\" width=\"140%\" height=\"140%\">\
+         <feDropShadow dx=\"0\" dy=\"{}\" stdDeviation=\"{}\" flood-color=\"#000000\" flood-opacity=\"0.5\"/></filter></defs>\n",
+        px(size * 0.8),
+        px(size * 1.2),
+    ));
+    if !matches!(options.background.as_str(), "none" | "transparent") {
+        svg.push_str(&format!("<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n", escape_xml(&options.background, true)));
+    };
+    svg.push_str(&format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" fill=\"{}\" filter=\"url(#shadow)\"/>\n",
+        px(options.padding),
+        px(options.padding),
+        px(window_width),
+        px(window_height),
+        px(size * 0.5),
+        background,
+    ));
+
+    let left = options.padding + window_padding;
+    let top = options.padding;
+    for (i, color) in WINDOW_BUTTONS.iter().enumerate() {
+        svg.push_str(&format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n",
+            px(left + size * 0.4 + i as f32 * size * 1.4),
+            px(top + title_bar_height / 2.0),
+            px(size * 0.4),
+            color,
+        ));
+    };
+    let font_family = format!("'{}', monospace", escape_xml(&options.font, true));
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\" font-family=\"{}\" font-size=\"{}\" fill=\"{}\" text-anchor=\"middle\" xml:space=\"preserve\">{}</text>\n",
+        px(options.padding + window_width / 2.0),
+        px(top + title_bar_height / 2.0 + title_size * 0.35),
+        font_family,
+        px(title_size),
+        gutter,
+        escape_xml(&snip.name, false),
+    ));
+
+    svg.push_str(&format!("<g font-family=\"{}\" font-size=\"{}\" xml:space=\"preserve\">\n", font_family, px(size)));
+    let code_left = left + gutter_columns as f32 * char_width;
+    for (i, (tokens, _)) in lines.iter().enumerate() {
+        // The baseline sits a bit below the middle of the line, where it centers the capitals
+        let baseline = top + title_bar_height + i as f32 * line_height + (line_height + size * 0.7) / 2.0;
+        if options.line_numbers {
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" fill=\"{}\" fill-opacity=\"0.6\" text-anchor=\"end\">{}</text>\n",
+                px(code_left - 2.0 * char_width),
+                px(baseline),
+                gutter,
+                i + 1,
+            ));
+        };
+        if tokens.is_empty() {
+            continue;
+        };
+        svg.push_str(&format!("<text x=\"{}\" y=\"{}\">", px(code_left), px(baseline)));
+        for (style, text) in tokens {
+            svg.push_str(&format!("<tspan{}>{}</tspan>", style.attributes(), escape_xml(text, false)));
+        };
+        svg.push_str("</text>\n");
+    };
+    svg.push_str("</g>\n</svg>\n");
+    Ok(svg)
+}
+
+/// Rasterizes the SVG of `render_svg` with the fonts installed on the system. Returns the PNG
+/// and a warning if the font isn't installed and another monospace font was used
+pub fn render_png(svg: &str, options: &RenderOptions) -> Result<(Vec<u8>, Option<String>), String> {
+    let mut svg_options = usvg::Options::default();
+    let fonts = svg_options.fontdb_mut();
+    fonts.load_system_fonts();
+    let installed = |family: &str| {
+        fonts.faces().any(|face| face.families.iter().any(|(name, _)| name.eq_ignore_ascii_case(family)))
+    };
+    let mut warning = None;
+    if !installed(&options.font) {
+        warning = Some(format!("the font '{}' isn't installed, another monospace font was used", options.font));
+    };
+    // `monospace` means Courier New by default, which most Linux systems don't have
+    let monospace = fonts
+        .faces()
+        .find(|face| face.monospaced)
+        .and_then(|face| face.families.first())
+        .map(|(name, _)| name.clone());
+    if let Some(monospace) = monospace {
+        fonts.set_monospace_family(monospace);
+    } else if warning.is_some() {
+        warning = Some(String::from("no monospace font is installed, the code may not line up"));
+    };
+
+    let tree = usvg::Tree::from_str(svg, &svg_options).map_err(|e| e.to_string())?;
+    let size = tree.size().to_int_size().scale_by(options.scale).ok_or("the scale is too small")?;
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("the image is too large")?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(options.scale, options.scale), &mut pixmap.as_mut());
+    let png = pixmap.encode_png().map_err(|e| e.to_string())?;
+    Ok((png, warning))
+}
+
+
+/// Style of a piece of code as SVG attributes
+struct TokenStyle {
+    fill: String,
+    opacity: Option<f32>,
+    font_style: FontStyle,
+}
+
+impl TokenStyle {
+    fn attributes(&self) -> String {
+        let mut attributes = format!(" fill=\"{}\"", self.fill);
+        if let Some(opacity) = self.opacity {
+            attributes.push_str(&format!(" fill-opacity=\"{:.2}\"", opacity));
+        };
+        if self.font_style.contains(FontStyle::BOLD) {
+            attributes.push_str(" font-weight=\"bold\"");
+        };
+        if self.font_style.contains(FontStyle::ITALIC) {
+            attributes.push_str(" font-style=\"italic\"");
+        };
+        if self.font_style.contains(FontStyle::UNDERLINE) {
+            attributes.push_str(" text-decoration=\"underline\"");
+        };
+        attributes
+    }
+}
+
+/// Replaces the tabs of a highlighted line with spaces up to the next tab stop. Returns the line and its width in columns
+fn expand_tabs(line: Vec<(Style, String)>) -> (Vec<(TokenStyle, String)>, usize) {
+    let mut column = 0;
+    let mut expanded = vec![];
+    for (style, text) in line {
+        let mut piece = String::with_capacity(text.len());
+        for grapheme in text.graphemes(true) {
+            let width = grapheme_width(grapheme, column);
+            match grapheme {
+                "\t" => piece.push_str(&" ".repeat(width)),
+                _ => piece.push_str(grapheme),
+            };
+            column += width;
+        };
+        let opacity = (style.foreground.a < 255).then_some(style.foreground.a as f32 / 255.0);
+        expanded.push((TokenStyle { fill: hex(style.foreground), opacity, font_style: style.font_style }, piece));
+    };
+    (expanded, column)
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+fn color_or(color: Option<Color>, default: Color) -> String {
+    hex(color.unwrap_or(default))
+}
+
+/// A length for SVG attributes, without needless decimals
+fn px(value: f32) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    format!("{}", rounded)
+}
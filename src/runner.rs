@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{
+    language::language_from_shebang,
+    snippet::CodeSnippet,
+    storage::TempCodeFile,
+};
+
+/// Interpreters used when the config doesn't name one for the language.
+/// The path of the snippet's code is appended to the command
+pub const DEFAULT_INTERPRETERS: &[(&str, &str)] = &[
+    ("bash", "bash"),
+    ("zsh", "zsh"),
+    ("fish", "fish"),
+    ("powershell", "pwsh -File"),
+    ("python", "python3"),
+    ("ruby", "ruby"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("lua", "lua"),
+    ("javascript", "node"),
+    ("typescript", "ts-node"),
+    ("go", "go run"),
+];
+
+/// Oldest lines are dropped from the output pane after this many
+const MAX_OUTPUT_LINES: usize = 10_000;
+
+
+/// The command (program and arguments) that runs the snippet: the interpreter configured for its language
+/// (or the language of its shebang), or else the program named in the shebang
+pub fn interpreter_for(snippet: &CodeSnippet, interpreters: &HashMap<String, String>) -> Option<Vec<String>> {
+    let language = snippet.language.as_deref().or_else(|| language_from_shebang(&snippet.code));
+    let configured = language.and_then(|l| interpreters.get(l)).filter(|i| !i.trim().is_empty());
+    match configured {
+        Some(interpreter) => Some(interpreter.split_whitespace().map(String::from).collect()),
+        None => {
+            // Keep the arguments of the shebang, e.g. `#!/usr/bin/env -S deno run`
+            let shebang = snippet.code.lines().next()?.strip_prefix("#!")?;
+            let command: Vec<String> = shebang.split_whitespace().map(String::from).collect();
+            (!command.is_empty()).then_some(command)
+        },
+    }
+}
+
+/// Explains why `interpreter_for` found nothing
+pub fn missing_interpreter_message(snippet: &CodeSnippet) -> String {
+    match &snippet.language {
+        Some(language) => format!("no interpreter for {} snippets, add one to the interpreters in the config", language),
+        None => String::from("the snippet has neither a language nor a shebang, so it's unclear how to run it"),
+    }
+}
+
+fn build_command(interpreter: &[String], script: &Path, args: &[String]) -> Command {
+    let mut command = Command::new(&interpreter[0]);
+    command.args(&interpreter[1..]).arg(script).args(args);
+    command
+}
+
+/// Runs the snippet in the foreground, connected to the terminal, and waits until it exits
+pub fn run_snippet(snippet: &CodeSnippet, interpreter: &[String], args: &[String]) -> io::Result<ExitStatus> {
+    let script = TempCodeFile::new(snippet)?;
+    let status = build_command(interpreter, &script.path, args).status();
+    status.map_err(|err| io::Error::new(err.kind(), format!("could not start {}: {}", interpreter[0], err)))
+}
+
+
+#[derive(Clone)]
+pub struct OutputLine {
+    pub text: String,
+    pub is_stderr: bool,
+}
+
+/// What the output pane shows about a snippet that runs or ran
+#[derive(Clone)]
+pub struct RunOutput {
+    pub name: String,
+    /// Program and arguments the code is run with
+    pub interpreter: Vec<String>,
+    pub lines: Vec<OutputLine>,
+    pub started: Instant,
+    /// Exit code (None when killed by a signal) and how long the snippet ran, once it finished
+    pub finished: Option<(Option<i32>, Duration)>,
+    /// How many lines the pane is scrolled up from the end of the output
+    pub scroll_back: usize,
+}
+
+impl RunOutput {
+    pub fn new(snippet: &CodeSnippet, interpreter: &[String]) -> RunOutput {
+        RunOutput {
+            name: snippet.name.clone(),
+            interpreter: interpreter.to_vec(),
+            lines: vec![],
+            started: Instant::now(),
+            finished: None,
+            scroll_back: 0,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.finished.is_none()
+    }
+
+    /// e.g. `running for 2.1s` or `exited with 0 after 0.35s`
+    pub fn status_text(&self) -> String {
+        match self.finished {
+            None => format!("running for {:.1}s", self.started.elapsed().as_secs_f64()),
+            Some((Some(code), duration)) => format!("exited with {} after {:.2}s", code, duration.as_secs_f64()),
+            Some((None, duration)) => format!("killed after {:.2}s", duration.as_secs_f64()),
+        }
+    }
+
+    fn push_line(&mut self, line: OutputLine) {
+        if self.lines.len() >= MAX_OUTPUT_LINES {
+            self.lines.remove(0);
+        };
+        self.lines.push(line);
+    }
+}
+
+
+/// A snippet running in the background, its stdout and stderr are read line by line
+pub struct RunningSnippet {
+    child: Child,
+    receiver: Receiver<OutputLine>,
+    /// Removed once the snippet was stopped
+    _script: TempCodeFile,
+    output_closed: bool,
+}
+
+impl RunningSnippet {
+    pub fn start(snippet: &CodeSnippet, interpreter: &[String]) -> io::Result<RunningSnippet> {
+        let script = TempCodeFile::new(snippet)?;
+        let child = build_command(interpreter, &script.path, &[])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(err) => {
+                return Err(io::Error::new(err.kind(), format!("could not start {}: {}", interpreter[0], err)));
+            },
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        if let Some(stdout) = child.stdout.take() {
+            forward_lines(stdout, false, sender.clone());
+        };
+        if let Some(stderr) = child.stderr.take() {
+            forward_lines(stderr, true, sender);
+        };
+        Ok(RunningSnippet { child, receiver, _script: script, output_closed: false })
+    }
+
+    /// Moves the new output into `output`. Returns true once the snippet exited and all of its output was read
+    pub fn update(&mut self, output: &mut RunOutput) -> io::Result<bool> {
+        while !self.output_closed {
+            match self.receiver.try_recv() {
+                Ok(line) => output.push_line(line),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => self.output_closed = true,
+            };
+        }
+        if !self.output_closed {
+            return Ok(false);
+        };
+        match self.child.try_wait()? {
+            Some(status) => {
+                output.finished = Some((status.code(), output.started.elapsed()));
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+}
+
+impl Drop for RunningSnippet {
+    fn drop(&mut self) {
+        // Stops the snippet if it's still running, e.g. when the output pane is closed early
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Sends every line read from `stream` until it's closed
+fn forward_lines(stream: impl Read + Send + 'static, is_stderr: bool, sender: Sender<OutputLine>) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut buffer = vec![];
+        while let Ok(read) = reader.read_until(b'\n', &mut buffer) {
+            if read == 0 {
+                break;
+            };
+            let text = String::from_utf8_lossy(&buffer).trim_end_matches(['\n', '\r']).to_string();
+            if sender.send(OutputLine { text, is_stderr }).is_err() {
+                break;
+            };
+            buffer.clear();
+        }
+    });
+}
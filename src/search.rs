@@ -0,0 +1,50 @@
+use serde_derive::{Serialize, Deserialize};
+use crate::snippet::CodeSnippet;
+
+
+/// A named search query that is stored alongside the snippets
+/// and re-run against the library whenever it is displayed
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+}
+impl SavedSearch {
+    pub fn new(name: &str, query: &str) -> SavedSearch {
+        SavedSearch {
+            name: name.to_string(),
+            query: query.to_string(),
+        }
+    }
+}
+
+
+/// Returns (list idx, snippet idx) for every snippet matching `input`.
+///
+/// The input is split on whitespace and every term has to match the name or one of the tags
+/// (non-case-sensitive). Terms starting with `-` exclude snippets that match them instead,
+/// e.g. `k8s -deprecated`. An empty input matches every snippet.
+pub fn search_snippets(snippets: &[CodeSnippet], input: &str) -> Vec<(usize, usize)> {
+    let input_lower = input.to_lowercase();
+    let mut include = vec![];
+    let mut exclude = vec![];
+    for term in input_lower.split_whitespace() {
+        match term.strip_prefix('-') {
+            Some(excluded) if !excluded.is_empty() => exclude.push(excluded),
+            _ => include.push(term),
+        };
+    };
+
+    let mut indices = Vec::<(usize, usize)>::new();
+    for (snippet_idx, snippet) in snippets.iter().enumerate() {
+        let name_lower = snippet.name.to_lowercase();
+        let tags_lower: Vec<String> = snippet.tags.iter().map(|t| t.to_lowercase()).collect();
+        let matches = |term: &str| {
+            name_lower.contains(term) || tags_lower.iter().any(|t| t.contains(term))
+        };
+        if include.iter().all(|t| matches(t)) && !exclude.iter().any(|t| matches(t)) {
+            indices.push((snippet_idx, snippet.idx));
+        };
+    };
+    indices
+}
@@ -0,0 +1,36 @@
+use clap::ValueEnum;
+
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+const BASH_SCRIPT: &str = include_str!("shell/snippy.bash");
+const ZSH_SCRIPT: &str = include_str!("shell/snippy.zsh");
+const FISH_SCRIPT: &str = include_str!("shell/snippy.fish");
+
+/// Replaced with the path of the snippy executable, so the scripts work without snippy being in the PATH
+const BIN_PLACEHOLDER: &str = "__SNIPPY_BIN__";
+
+
+/// The shell integration script (key bindings for the picker and for saving the command line)
+/// that runs the snippy executable at `snippy_bin`
+pub fn init_script(shell: Shell, snippy_bin: &str) -> String {
+    let script = match shell {
+        Shell::Bash => BASH_SCRIPT,
+        Shell::Zsh => ZSH_SCRIPT,
+        Shell::Fish => FISH_SCRIPT,
+    };
+    script.replace(BIN_PLACEHOLDER, &quote(shell, snippy_bin))
+}
+
+/// Puts `value` into single quotes, escaped the way the shell expects it
+fn quote(shell: Shell, value: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("'{}'", value.replace('\'', "'\\''")),
+        Shell::Fish => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'")),
+    }
+}
@@ -0,0 +1,28 @@
+# Snippy shell integration for bash. Add this line to ~/.bashrc:
+#   eval "$(snippy init bash)"
+#
+# Ctrl-G  pick a snippet and insert it at the cursor
+# Alt-G   save the current command line as a new snippet
+
+__snippy_bin=__SNIPPY_BIN__
+
+__snippy_insert() {
+    local selected
+    selected="$("$__snippy_bin" pick)" || return
+    READLINE_LINE="${READLINE_LINE:0:READLINE_POINT}${selected}${READLINE_LINE:READLINE_POINT}"
+    READLINE_POINT=$((READLINE_POINT + ${#selected}))
+}
+
+__snippy_save() {
+    [ -n "$READLINE_LINE" ] || return
+    local name tags
+    read -r -p "Save as snippet, name (empty to use the command): " name </dev/tty || return
+    read -r -p "Tags (comma separated): " tags </dev/tty || return
+    local args=(add --language bash --code="$READLINE_LINE")
+    [ -n "$name" ] && args+=(--name="$name")
+    [ -n "$tags" ] && args+=(--tags="$tags")
+    "$__snippy_bin" "${args[@]}" >/dev/null && echo "Saved the command line as a snippet"
+}
+
+bind -x '"\C-g": __snippy_insert'
+bind -x '"\eg": __snippy_save'
@@ -0,0 +1,28 @@
+# Snippy shell integration for fish. Add this line to ~/.config/fish/config.fish:
+#   snippy init fish | source
+#
+# Ctrl-G  pick a snippet and insert it at the cursor
+# Alt-G   save the current command line as a new snippet
+
+set -g __snippy_bin __SNIPPY_BIN__
+
+function __snippy_insert
+    set -l selected ($__snippy_bin pick | string collect)
+    and commandline -i -- $selected
+    commandline -f repaint
+end
+
+function __snippy_save
+    set -l buffer (commandline | string collect)
+    test -n "$buffer"; or return
+    read -l -P "Save as snippet, name (empty to use the command): " name </dev/tty; or return
+    read -l -P "Tags (comma separated): " tags </dev/tty; or return
+    set -l args add --language fish --code=$buffer
+    test -n "$name"; and set -a args --name=$name
+    test -n "$tags"; and set -a args --tags=$tags
+    $__snippy_bin $args >/dev/null; and echo "Saved the command line as a snippet"
+    commandline -f repaint
+end
+
+bind \cg __snippy_insert
+bind \eg __snippy_save
@@ -0,0 +1,176 @@
+use std::{cell::OnceCell, collections::HashMap};
+use crate::snippet::CodeSnippet;
+
+/// How much the code counts compared to the tags when comparing two snippets
+const CODE_WEIGHT: f32 = 0.7;
+const TAG_WEIGHT: f32 = 1.0 - CODE_WEIGHT;
+
+
+/// Term frequencies and tags of a single snippet
+#[derive(Clone, Default)]
+struct SnippetTerms {
+    term_counts: HashMap<String, usize>,
+    tags: Vec<String>,
+}
+
+/// TF-IDF weights of the code of a snippet and their norm
+#[derive(Clone, Default)]
+struct Vector {
+    weights: HashMap<String, f32>,
+    norm: f32,
+}
+
+
+/// Offline index to find snippets that are similar to each other.
+///
+/// The code is compared with TF-IDF over its tokens, the tags by their overlap (Jaccard index).
+/// Snippets are added and removed one by one, so the index never has to be rebuilt as a whole.
+#[derive(Clone, Default)]
+pub struct SimilarityIndex {
+    /// Terms per snippet idx
+    snippets: HashMap<usize, SnippetTerms>,
+    /// In how many snippets each term occurs
+    document_frequency: HashMap<String, usize>,
+    /// TF-IDF vectors per snippet idx. They depend on every snippet, so any change drops them
+    /// and the next `related` computes them again
+    vectors: OnceCell<HashMap<usize, Vector>>,
+}
+
+impl SimilarityIndex {
+    pub fn from_snippets(snippets: &[CodeSnippet]) -> SimilarityIndex {
+        let mut index = SimilarityIndex::default();
+        for snip in snippets.iter() {
+            index.update(snip);
+        };
+        index
+    }
+
+    /// Adds the snippet to the index or replaces its previous version
+    pub fn update(&mut self, snippet: &CodeSnippet) {
+        self.remove(snippet.idx);
+        self.vectors.take();
+        let mut term_counts = HashMap::new();
+        for token in tokenize(&snippet.code) {
+            *term_counts.entry(token).or_insert(0) += 1;
+        };
+        for term in term_counts.keys() {
+            *self.document_frequency.entry(term.clone()).or_insert(0) += 1;
+        };
+        let tags = snippet.tags.iter().filter(|t| !t.is_empty()).map(|t| t.to_lowercase()).collect();
+        self.snippets.insert(snippet.idx, SnippetTerms { term_counts, tags });
+    }
+
+    pub fn remove(&mut self, snippet_idx: usize) {
+        self.vectors.take();
+        if let Some(old) = self.snippets.remove(&snippet_idx) {
+            for term in old.term_counts.keys() {
+                if let Some(count) = self.document_frequency.get_mut(term) {
+                    *count -= 1;
+                    if *count == 0 {
+                        self.document_frequency.remove(term);
+                    };
+                };
+            };
+        };
+    }
+
+    /// The `count` snippets most similar to the given one as (snippet idx, score), best first.
+    /// Snippets that have nothing in common with it are left out
+    pub fn related(&self, snippet_idx: usize, count: usize) -> Vec<(usize, f32)> {
+        let Some(terms) = self.snippets.get(&snippet_idx) else {
+            return vec![];
+        };
+        let vectors = self.vectors.get_or_init(|| {
+            self.snippets.iter().map(|(idx, terms)| (*idx, self.tf_idf(terms))).collect()
+        });
+        let vector = &vectors[&snippet_idx];
+        let mut scores: Vec<(usize, f32)> = self.snippets
+            .iter()
+            .filter(|(idx, _)| **idx != snippet_idx)
+            .map(|(idx, other)| {
+                let code_score = cosine_similarity(vector, &vectors[idx]);
+                let tag_score = jaccard_index(&terms.tags, &other.tags);
+                (*idx, CODE_WEIGHT * code_score + TAG_WEIGHT * tag_score)
+            })
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scores.truncate(count);
+        scores
+    }
+
+    fn tf_idf(&self, terms: &SnippetTerms) -> Vector {
+        let snippet_count = self.snippets.len() as f32;
+        let weights: HashMap<String, f32> = terms.term_counts
+            .iter()
+            .map(|(term, count)| {
+                let df = *self.document_frequency.get(term).unwrap_or(&1) as f32;
+                let idf = (1.0 + snippet_count / df).ln();
+                (term.clone(), *count as f32 * idf)
+            })
+            .collect();
+        let norm = weights.values().map(|w| w * w).sum::<f32>().sqrt();
+        Vector { weights, norm }
+    }
+}
+
+
+/// Splits code into lowercase identifiers, keywords and numbers
+fn tokenize(code: &str) -> impl Iterator<Item = String> + '_ {
+    code.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|token| token.chars().count() > 1)
+        .map(|token| token.to_lowercase())
+}
+
+fn cosine_similarity(a: &Vector, b: &Vector) -> f32 {
+    if a.norm == 0.0 || b.norm == 0.0 {
+        return 0.0;
+    };
+    let dot: f32 = a.weights.iter().filter_map(|(term, weight)| b.weights.get(term).map(|w| w * weight)).sum();
+    dot / (a.norm * b.norm)
+}
+
+fn jaccard_index(a: &[String], b: &[String]) -> f32 {
+    let shared = a.iter().filter(|t| b.contains(t)).count();
+    let total = a.len() + b.len() - shared;
+    if total == 0 {
+        return 0.0;
+    };
+    shared as f32 / total as f32
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(idx: usize, code: &str, tags: &[&str]) -> CodeSnippet {
+        let mut snip = CodeSnippet::new(idx);
+        snip.code = code.to_string();
+        snip.tags = tags.iter().map(|t| t.to_string()).collect();
+        snip
+    }
+
+    #[test]
+    fn related_snippets() {
+        let index = SimilarityIndex::from_snippets(&[
+            snippet(0, "docker run --rm image", &["docker"]),
+            snippet(1, "docker ps --all", &["docker"]),
+            snippet(2, "git status", &["git"]),
+        ]);
+        let related: Vec<usize> = index.related(0, 5).into_iter().map(|(idx, _)| idx).collect();
+        assert_eq!(related, vec![1]);
+        assert!(index.related(7, 5).is_empty());
+    }
+
+    #[test]
+    fn changes_drop_the_cached_vectors() {
+        let mut index = SimilarityIndex::from_snippets(&[snippet(0, "git status", &[]), snippet(1, "ls -la", &[])]);
+        assert!(index.related(0, 5).is_empty());
+        index.update(&snippet(1, "git log", &[]));
+        assert_eq!(index.related(0, 5).len(), 1);
+        index.remove(1);
+        assert!(index.related(0, 5).is_empty());
+    }
+}
@@ -1,12 +1,29 @@
+use std::collections::BTreeMap;
 use serde_derive::{Serialize, Deserialize};
 
 
-#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CodeSnippet {
     pub tags: Vec<String>,
     pub name: String,
     pub code: String,
     pub idx: usize,
+    /// e.g. `rust` or `python`, see the language module
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Where the code was taken from, e.g. `src/lib.rs:120-180`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// What is typed in an editor to insert the snippet, kept when importing from and exporting to editors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<String>,
+    /// Longer explanation of the snippet, kept when importing from and exporting to editors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Settings of other tools without a snippy equivalent (e.g. the options of an UltiSnips snippet),
+    /// so exporting back to that tool loses nothing
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
 }
 impl CodeSnippet {
     pub fn new(new_idx: usize) -> CodeSnippet {
@@ -14,6 +31,11 @@ impl CodeSnippet {
             name: "Unnamed Code Snippet".to_string(),
             code: "".to_string(),
             idx: new_idx,
+            language: None,
+            source: None,
+            trigger: None,
+            description: None,
+            extra: BTreeMap::new(),
         }
     }
 }
\ No newline at end of file
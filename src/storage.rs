@@ -0,0 +1,113 @@
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, prelude::*};
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use tempfile::TempDir;
+
+use crate::{app::App, language::extension_for_language, snippet::CodeSnippet, SnippyConfig};
+
+pub const SAVEFILE_PATH: &str = "savestate.snippy";
+pub const CONFIG_PATH: &str = "config.snippy";
+
+
+/// Loads the app state from the save file if that exists, else creates a new App
+pub fn load_app_state() -> io::Result<App> {
+    let path = build_path_with_str(SAVEFILE_PATH);
+    let mut app = match Path::new(path.as_str()).exists() {
+        true => {
+            let contents = load_string_from_file(path.as_str())?;
+            let app_deserialized : App = serde_json::from_str(contents.as_str())?;
+            app_deserialized
+        },
+        false => {
+            App::default()
+        },
+    };
+    app.rebuild_similarity_index();
+    Ok(app)
+}
+
+/// Loads the config if that exists, else uses the default config
+pub fn load_config_state() -> io::Result<SnippyConfig> {
+    let path = build_path_with_str(CONFIG_PATH);
+    let mut config = match Path::new(path.as_str()).exists() {
+        true => {
+            let contents = load_string_from_file(path.as_str())?;
+            let config_deserialized : SnippyConfig = serde_json::from_str(contents.as_str())?;
+            config_deserialized
+        },
+        false => {
+            SnippyConfig::default()
+        },
+    };
+    config.fill_missing_keys();
+    Ok(config)
+}
+
+pub fn save_app_state(app: &App) -> io::Result<()> {
+    let path = build_path_with_str(SAVEFILE_PATH);
+    let file = File::create(path)?;
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    let mut ser = serde_json::Serializer::with_formatter(file, formatter);
+    app.serialize(&mut ser)?;
+    Ok(())
+}
+
+pub fn save_config_state(config: &SnippyConfig) -> io::Result<()> {
+    let path = build_path_with_str(CONFIG_PATH);
+    let file = File::create(path)?;
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    let mut ser = serde_json::Serializer::with_formatter(file, formatter);
+    config.serialize(&mut ser)?;
+    Ok(())
+}
+
+
+/// The code of a snippet in a file of its own temporary directory, for running or editing it.
+/// Only the user can enter the directory and the file is always created new, so no one else can
+/// swap the file for a link or their own code. The directory is removed when this is dropped
+pub struct TempCodeFile {
+    pub path: PathBuf,
+    _dir: TempDir,
+}
+
+impl TempCodeFile {
+    /// The file gets the extension of the snippet's language, so editors can highlight it
+    pub fn new(snippet: &CodeSnippet) -> io::Result<TempCodeFile> {
+        let mut builder = tempfile::Builder::new();
+        builder.prefix("snippy-");
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            builder.permissions(std::fs::Permissions::from_mode(0o700));
+            options.mode(0o600);
+        };
+        let dir = builder.tempdir()?;
+        let extension = extension_for_language(snippet.language.as_deref());
+        let path = dir.path().join(format!("snippet-{}.{}", snippet.idx, extension));
+        options.open(&path)?.write_all(snippet.code.as_bytes())?;
+        Ok(TempCodeFile { path, _dir: dir })
+    }
+}
+
+
+pub fn build_path_with_str(filename: &str) -> String {
+    let path = env::current_exe().unwrap();
+    let release_folder = path.parent().unwrap();
+    let target_folder = release_folder.parent().unwrap();
+    let main_folder = target_folder.parent().unwrap();
+    let full_path = main_folder.join(filename);
+    let path_str = full_path.to_str().unwrap();
+    String::from(path_str)
+}
+
+
+pub fn load_string_from_file(path: &str) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    Ok(contents)
+}
@@ -0,0 +1,799 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    undo::{EditKind, UndoHistory},
+    unicode::{display_width, grapheme_width, next_grapheme, offset_at_column, previous_grapheme},
+};
+
+/// Inserted by Tab unless the code is indented with tabs, and removed (at most) by Shift-Tab
+pub const INDENT: &str = "    ";
+/// How far PgUp/PgDn move before the text area was drawn the first time
+const DEFAULT_PAGE_HEIGHT: usize = 10;
+
+
+/// A place in the text: line and byte offset in the line
+pub type Position = (usize, usize);
+
+/// Where a key moves the cursor
+#[derive(Clone, Copy, PartialEq)]
+pub enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    /// Start of the word before the cursor
+    WordLeft,
+    /// End of the word after the cursor
+    WordRight,
+    /// First non-blank character of the line, or the start of the line if the cursor is there already
+    LineStart,
+    LineEnd,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+}
+
+/// A multi-line text field with a cursor that can be anywhere in the text, a selection,
+/// line numbers and scrolling. Long lines are scrolled horizontally, or wrapped
+#[derive(Clone)]
+pub struct TextArea {
+    /// Never empty, the text ends without a line break
+    lines: Vec<String>,
+    cursor: Position,
+    /// Column the cursor returns to when it's moved up and down through shorter lines
+    preferred_column: Option<usize>,
+    /// Where the selection started, it goes from there to the cursor
+    anchor: Option<Position>,
+    /// Text shown as selected while nothing is, e.g. the visual mode selection of the vim keymap
+    highlight: Option<(Position, Position)>,
+    /// First row shown (a line, or a part of a line when wrapping) and the first column shown
+    scroll: (usize, usize),
+    pub wrap: bool,
+    /// Height of the text area when it was drawn last, how far PgUp/PgDn move
+    page_height: usize,
+    /// The lines and the cursor before the edits that can be undone
+    history: UndoHistory<(Vec<String>, Position)>,
+}
+
+/// Part of a line shown on one row of the text area, a whole line unless lines are wrapped
+struct Row {
+    line: usize,
+    start: usize,
+    end: usize,
+}
+
+impl Default for TextArea {
+    fn default() -> Self {
+        TextArea::new("")
+    }
+}
+
+impl TextArea {
+    /// The cursor starts at the end of the text
+    pub fn new(text: &str) -> TextArea {
+        let lines: Vec<String> = text.split('\n').map(String::from).collect();
+        let cursor = (lines.len() - 1, lines[lines.len() - 1].len());
+        TextArea {
+            lines,
+            cursor,
+            preferred_column: None,
+            anchor: None,
+            highlight: None,
+            scroll: (0, 0),
+            wrap: false,
+            page_height: DEFAULT_PAGE_HEIGHT,
+            history: UndoHistory::default(),
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// The lines of the text, without the line breaks
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn cursor(&self) -> Position {
+        self.cursor
+    }
+
+    /// Moves the cursor to the position (which has to be in the text) and ends the selection
+    pub fn set_cursor(&mut self, position: Position) {
+        self.history.break_group();
+        self.anchor = None;
+        self.preferred_column = None;
+        self.cursor = position;
+    }
+
+    /// Replaces the text between the positions (start first) and moves the cursor behind the new text.
+    /// Undone on its own, returns whether the text changed
+    pub fn replace(&mut self, start: Position, end: Position, text: &str) -> bool {
+        self.edit(EditKind::Other, |area| {
+            area.anchor = None;
+            let deleted = area.delete_range(start, end);
+            area.cursor = start;
+            area.insert(text);
+            deleted || !text.is_empty()
+        })
+    }
+
+    /// Number of groups of edits that can be undone
+    pub fn undo_groups(&self) -> usize {
+        self.history.group_count()
+    }
+
+    /// Makes the edits since there were `count` groups of edits one group, undone at once
+    pub fn join_undo_groups(&mut self, count: usize) {
+        self.history.join_groups_since(count);
+    }
+
+    /// Shows the text between the positions (start first) as selected, None to stop
+    pub fn set_highlight(&mut self, range: Option<(Position, Position)>) {
+        self.highlight = range;
+    }
+
+    /// Line and column of the cursor, counted from 1
+    pub fn cursor_position(&self) -> (usize, usize) {
+        let (row, col) = self.cursor;
+        (row + 1, display_width(&self.lines[row][..col]) + 1)
+    }
+
+    /// Handles a key for editing or moving around, returns whether the text changed
+    pub fn input(&mut self, key: KeyEvent) -> bool {
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let motion = match key.code {
+            KeyCode::Left if ctrl || alt => Some(Motion::WordLeft),
+            KeyCode::Right if ctrl || alt => Some(Motion::WordRight),
+            KeyCode::Left => Some(Motion::Left),
+            KeyCode::Right => Some(Motion::Right),
+            KeyCode::Up => Some(Motion::Up),
+            KeyCode::Down => Some(Motion::Down),
+            KeyCode::Home if ctrl => Some(Motion::Top),
+            KeyCode::End if ctrl => Some(Motion::Bottom),
+            KeyCode::Home => Some(Motion::LineStart),
+            KeyCode::End => Some(Motion::LineEnd),
+            KeyCode::PageUp => Some(Motion::PageUp),
+            KeyCode::PageDown => Some(Motion::PageDown),
+            _ => None,
+        };
+        if let Some(motion) = motion {
+            self.move_cursor(motion, shift);
+            return false;
+        };
+
+        // Typing over a selection is undone on its own, like a paste
+        let typing = |c: char| match self.selection() {
+            Some(_) => EditKind::Other,
+            None => EditKind::typed(c),
+        };
+        match key.code {
+            KeyCode::Char('a') if ctrl => {
+                self.select_all();
+                false
+            },
+            KeyCode::Char('z') if alt => {
+                self.wrap = !self.wrap;
+                false
+            },
+            KeyCode::Char('z') if ctrl => self.undo(),
+            KeyCode::Char('y') if ctrl => self.redo(),
+            KeyCode::Char(c) if !ctrl && !alt => {
+                self.edit(typing(c), |area| {
+                    area.insert(c.encode_utf8(&mut [0; 4]));
+                    true
+                })
+            },
+            KeyCode::Enter => {
+                self.edit(typing('\n'), |area| {
+                    area.insert("\n");
+                    true
+                })
+            },
+            KeyCode::Tab => self.edit(EditKind::Other, TextArea::indent),
+            KeyCode::BackTab => self.edit(EditKind::Other, TextArea::dedent),
+            KeyCode::Backspace | KeyCode::Delete => {
+                self.edit(EditKind::Delete, |area| {
+                    if area.remove_selection() {
+                        return true;
+                    };
+                    match (key.code, ctrl || alt) {
+                        (KeyCode::Backspace, true) => area.delete_range(area.motion_target(Motion::WordLeft), area.cursor),
+                        (KeyCode::Backspace, false) => area.delete_range(area.motion_target(Motion::Left), area.cursor),
+                        (_, true) => area.delete_range(area.cursor, area.motion_target(Motion::WordRight)),
+                        (_, false) => area.delete_range(area.cursor, area.motion_target(Motion::Right)),
+                    }
+                })
+            },
+            _ => false,
+        }
+    }
+
+    /// Takes back the last group of edits, returns whether there was one
+    pub fn undo(&mut self) -> bool {
+        let Some((lines, cursor)) = self.history.undo((self.lines.clone(), self.cursor)) else {
+            return false;
+        };
+        self.restore(lines, cursor);
+        true
+    }
+
+    /// Makes the last undone group of edits again, returns whether there was one
+    pub fn redo(&mut self) -> bool {
+        let Some((lines, cursor)) = self.history.redo((self.lines.clone(), self.cursor)) else {
+            return false;
+        };
+        self.restore(lines, cursor);
+        true
+    }
+
+    /// Moves the cursor, extending the selection if `select` is set and ending it otherwise
+    pub fn move_cursor(&mut self, motion: Motion, select: bool) {
+        self.history.break_group();
+        match select {
+            true => {
+                self.anchor.get_or_insert(self.cursor);
+            },
+            false => self.anchor = None,
+        };
+        let target = self.motion_target(motion);
+        // Going up and down keeps the column the cursor had before the first move
+        match motion {
+            Motion::Up | Motion::Down | Motion::PageUp | Motion::PageDown => {
+                self.preferred_column.get_or_insert(display_width(&self.lines[self.cursor.0][..self.cursor.1]));
+            },
+            _ => self.preferred_column = None,
+        };
+        self.cursor = target;
+    }
+
+    /// Replaces the selection (if there is one) with the text and moves the cursor behind it.
+    /// Undone on its own, like a paste
+    pub fn insert_str(&mut self, text: &str) {
+        self.edit(EditKind::Other, |area| {
+            area.insert(text);
+            true
+        });
+    }
+
+    /// Replaces the selection (if there is one) with the text and moves the cursor behind it
+    fn insert(&mut self, text: &str) {
+        self.remove_selection();
+        self.preferred_column = None;
+        let text = text.replace("\r\n", "\n");
+        let (row, col) = self.cursor;
+        let rest = self.lines[row].split_off(col);
+        let mut new_lines = text.split('\n');
+        self.lines[row].push_str(new_lines.next().unwrap_or_default());
+        let mut last_row = row;
+        for line in new_lines {
+            last_row += 1;
+            self.lines.insert(last_row, line.to_string());
+        };
+        self.cursor = (last_row, self.lines[last_row].len());
+        self.lines[last_row].push_str(&rest);
+    }
+
+    /// The selected text, if anything is selected
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection()?;
+        if start.0 == end.0 {
+            return Some(self.lines[start.0][start.1..end.1].to_string());
+        };
+        let mut text = self.lines[start.0][start.1..].to_string();
+        for line in &self.lines[start.0 + 1..end.0] {
+            text.push('\n');
+            text.push_str(line);
+        };
+        text.push('\n');
+        text.push_str(&self.lines[end.0][..end.1]);
+        Some(text)
+    }
+
+    /// Deletes the selected text, returns whether anything was selected
+    pub fn delete_selection(&mut self) -> bool {
+        self.edit(EditKind::Other, TextArea::remove_selection)
+    }
+
+    pub fn select_all(&mut self) {
+        self.history.break_group();
+        self.anchor = Some((0, 0));
+        self.cursor = self.motion_target(Motion::Bottom);
+    }
+    /// Draws the text with line numbers inside the block and scrolls so the cursor is visible.
+    /// The terminal cursor is only placed on the text area if it's focused
+    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, block: Block, area: Rect, focused: bool) {
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let number_width = self.lines.len().to_string().len();
+        let gutter_width = number_width + 1;
+        if inner.width as usize <= gutter_width || inner.height == 0 {
+            return;
+        };
+        let text_width = inner.width as usize - gutter_width;
+        let height = inner.height as usize;
+        self.page_height = height;
+
+        let rows = self.rows(text_width);
+        // At a wrapping point the cursor belongs to the start of the next row
+        let cursor_row = rows
+            .iter()
+            .rposition(|r| r.line == self.cursor.0 && r.start <= self.cursor.1)
+            .unwrap_or(0);
+        let cursor_column = display_width(&self.lines[self.cursor.0][rows[cursor_row].start..self.cursor.1]);
+        if cursor_row < self.scroll.0 {
+            self.scroll.0 = cursor_row;
+        } else if cursor_row >= self.scroll.0 + height {
+            self.scroll.0 = cursor_row + 1 - height;
+        };
+        if self.wrap {
+            self.scroll.1 = 0;
+        } else if cursor_column < self.scroll.1 {
+            self.scroll.1 = cursor_column;
+        } else if cursor_column >= self.scroll.1 + text_width {
+            self.scroll.1 = cursor_column + 1 - text_width;
+        };
+
+        let selection = self.selection().or(self.highlight);
+        let number_style = Style::default().fg(Color::DarkGray);
+        let selected_style = Style::default().bg(Color::Rgb(60, 63, 71)).add_modifier(Modifier::BOLD);
+        let lines: Vec<Spans> = rows[self.scroll.0..]
+            .iter()
+            .take(height)
+            .map(|row| {
+                let number = match row.start {
+                    0 => format!("{:>width$} ", row.line + 1, width = number_width),
+                    _ => " ".repeat(gutter_width),
+                };
+                let style = match row.line == self.cursor.0 {
+                    true => number_style.fg(Color::Gray),
+                    false => number_style,
+                };
+                let mut spans = vec![Span::styled(number, style)];
+                spans.extend(self.row_spans(row, text_width, selection, selected_style));
+                Spans::from(spans)
+            })
+            .collect();
+        f.render_widget(Paragraph::new(lines), inner);
+
+        if focused {
+            f.set_cursor(
+                inner.x + (gutter_width + cursor_column - self.scroll.1) as u16,
+                inner.y + (cursor_row - self.scroll.0) as u16,
+            );
+        };
+    }
+
+
+    /// Makes an edit that can be undone, `apply` returns whether it changed the text
+    fn edit(&mut self, kind: EditKind, apply: impl FnOnce(&mut TextArea) -> bool) -> bool {
+        let before = (self.lines.clone(), self.cursor);
+        let changed = apply(self);
+        if changed {
+            self.history.record(before, kind);
+        };
+        changed
+    }
+
+    fn restore(&mut self, lines: Vec<String>, cursor: Position) {
+        self.lines = lines;
+        self.cursor = cursor;
+        self.anchor = None;
+        self.preferred_column = None;
+    }
+
+    /// Deletes the selected text without recording it for undo, returns whether anything was selected
+    fn remove_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            self.anchor = None;
+            return false;
+        };
+        self.anchor = None;
+        self.delete_range(start, end);
+        true
+    }
+
+    /// Start and end of the selection, start first. None if nothing is selected
+    fn selection(&self) -> Option<(Position, Position)> {
+        let anchor = self.anchor.filter(|a| *a != self.cursor)?;
+        Some((anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    /// Where the motion would move the cursor to
+    fn motion_target(&self, motion: Motion) -> Position {
+        let (row, col) = self.cursor;
+        let line = &self.lines[row];
+        let last_row = self.lines.len() - 1;
+        let vertical = |target_row: usize| {
+            let column = self.preferred_column.unwrap_or_else(|| display_width(&line[..col]));
+            (target_row, offset_at_column(&self.lines[target_row], column))
+        };
+        match motion {
+            Motion::Left if col > 0 => (row, previous_grapheme(line, col)),
+            Motion::Left if row > 0 => (row - 1, self.lines[row - 1].len()),
+            Motion::Left => (row, col),
+            Motion::Right if col < line.len() => (row, next_grapheme(line, col)),
+            Motion::Right if row < last_row => (row + 1, 0),
+            Motion::Right => (row, col),
+            Motion::Up if row > 0 => vertical(row - 1),
+            Motion::Up => (0, 0),
+            Motion::Down if row < last_row => vertical(row + 1),
+            Motion::Down => (row, line.len()),
+            Motion::PageUp => vertical(row.saturating_sub(self.page_height)),
+            Motion::PageDown => vertical((row + self.page_height).min(last_row)),
+            Motion::WordLeft => self.word_left(),
+            Motion::WordRight => self.word_right(),
+            Motion::LineStart => {
+                let indentation = line.len() - line.trim_start().len();
+                match col == indentation {
+                    true => (row, 0),
+                    false => (row, indentation),
+                }
+            },
+            Motion::LineEnd => (row, line.len()),
+            Motion::Top => (0, 0),
+            Motion::Bottom => (last_row, self.lines[last_row].len()),
+        }
+    }
+
+    /// Start of the word before the cursor, or the end of the line above at the start of a line
+    fn word_left(&self) -> Position {
+        let (row, col) = self.cursor;
+        if col == 0 {
+            return self.motion_target(Motion::Left);
+        };
+        let graphemes: Vec<(usize, &str)> = self.lines[row][..col].grapheme_indices(true).collect();
+        let mut i = graphemes.len();
+        while i > 0 && grapheme_kind(graphemes[i - 1].1) == 0 {
+            i -= 1;
+        };
+        if i > 0 {
+            let kind = grapheme_kind(graphemes[i - 1].1);
+            while i > 0 && grapheme_kind(graphemes[i - 1].1) == kind {
+                i -= 1;
+            };
+        };
+        (row, graphemes.get(i).map(|(offset, _)| *offset).unwrap_or(0))
+    }
+
+    /// End of the word after the cursor, or the start of the line below at the end of a line
+    fn word_right(&self) -> Position {
+        let (row, col) = self.cursor;
+        let line = &self.lines[row];
+        if col == line.len() {
+            return self.motion_target(Motion::Right);
+        };
+        let mut graphemes = line[col..].grapheme_indices(true).peekable();
+        while graphemes.next_if(|(_, g)| grapheme_kind(g) == 0).is_some() {};
+        if let Some(&(_, first)) = graphemes.peek() {
+            let kind = grapheme_kind(first);
+            while graphemes.next_if(|(_, g)| grapheme_kind(g) == kind).is_some() {};
+        };
+        (row, graphemes.peek().map(|(offset, _)| col + offset).unwrap_or(line.len()))
+    }
+
+    /// Deletes the text between the positions (start first) and moves the cursor to the start.
+    /// Returns whether anything was deleted
+    fn delete_range(&mut self, start: Position, end: Position) -> bool {
+        self.preferred_column = None;
+        if start == end {
+            return false;
+        };
+        if start.0 == end.0 {
+            self.lines[start.0].replace_range(start.1..end.1, "");
+        } else {
+            let rest = self.lines[end.0][end.1..].to_string();
+            self.lines.drain(start.0 + 1..=end.0);
+            self.lines[start.0].truncate(start.1);
+            self.lines[start.0].push_str(&rest);
+        };
+        self.cursor = start;
+        true
+    }
+
+    /// Indents the lines of a selection spanning several lines, else replaces the selection with an indent
+    fn indent(&mut self) -> bool {
+        let indent = self.indent_unit();
+        let Some((first, last)) = self.selected_lines() else {
+            self.insert(indent);
+            return true;
+        };
+        for line in &mut self.lines[first..=last] {
+            line.insert_str(0, indent);
+        };
+        self.shift_columns(first, last, |col, _| col + indent.len());
+        true
+    }
+
+    /// A tab if the line of the cursor is indented with one, or else the first indented line of the text, else INDENT
+    fn indent_unit(&self) -> &'static str {
+        let mut lines = std::iter::once(&self.lines[self.cursor.0]).chain(&self.lines);
+        match lines.find(|line| line.starts_with([' ', '\t'])).is_some_and(|line| line.starts_with('\t')) {
+            true => "\t",
+            false => INDENT,
+        }
+    }
+
+    /// Removes one indent (a tab or up to four spaces) from the line of the cursor or every selected line
+    fn dedent(&mut self) -> bool {
+        let (first, last) = self.selected_lines().unwrap_or((self.cursor.0, self.cursor.0));
+        let mut removed = vec![0; last - first + 1];
+        for (i, line) in self.lines[first..=last].iter_mut().enumerate() {
+            removed[i] = match line.starts_with('\t') {
+                true => 1,
+                false => line.len() - line.trim_start_matches(' ').len(),
+            }
+            .min(INDENT.len());
+            line.replace_range(..removed[i], "");
+        };
+        self.shift_columns(first, last, |col, row| col.saturating_sub(removed[row - first]));
+        removed.iter().any(|r| *r > 0)
+    }
+
+    /// First and last line of a selection spanning several lines. A selection ending at the start of a line
+    /// doesn't include that line
+    fn selected_lines(&self) -> Option<(usize, usize)> {
+        let (start, end) = self.selection().filter(|(start, end)| start.0 != end.0)?;
+        let last = match end.1 {
+            0 => end.0 - 1,
+            _ => end.0,
+        };
+        Some((start.0, last))
+    }
+
+    /// Changes the columns of the cursor and the anchor if they're on one of the lines
+    fn shift_columns(&mut self, first: usize, last: usize, shift: impl Fn(usize, usize) -> usize) {
+        for position in [Some(&mut self.cursor), self.anchor.as_mut()].into_iter().flatten() {
+            if (first..=last).contains(&position.0) {
+                position.1 = shift(position.1, position.0);
+            };
+        };
+    }
+
+    /// The rows the lines take up, lines longer than the width take several rows when wrapping
+    fn rows(&self, width: usize) -> Vec<Row> {
+        let mut rows = vec![];
+        for (i, line) in self.lines.iter().enumerate() {
+            if !self.wrap {
+                rows.push(Row { line: i, start: 0, end: line.len() });
+                continue;
+            };
+            let mut start = 0;
+            let mut columns = 0;
+            // Tab stops start over on every row
+            for (offset, grapheme) in line.grapheme_indices(true) {
+                if columns + grapheme_width(grapheme, columns) > width && offset > start {
+                    rows.push(Row { line: i, start, end: offset });
+                    start = offset;
+                    columns = 0;
+                };
+                columns += grapheme_width(grapheme, columns);
+            };
+            rows.push(Row { line: i, start, end: line.len() });
+            // A full last row leaves no room for the cursor at the end of the line
+            if columns >= width && start < line.len() {
+                rows.push(Row { line: i, start: line.len(), end: line.len() });
+            };
+        };
+        rows
+    }
+
+    /// The visible part of the row, with the selected text highlighted
+    fn row_spans(&self, row: &Row, width: usize, selection: Option<(Position, Position)>, selected_style: Style) -> Vec<Span<'static>> {
+        let line = &self.lines[row.line];
+        let is_selected = |offset: usize| {
+            selection.is_some_and(|(start, end)| start <= (row.line, offset) && (row.line, offset) < end)
+        };
+        let mut spans: Vec<Span<'static>> = vec![];
+        let mut piece = String::new();
+        let mut piece_selected = false;
+        let mut column = 0;
+        for (offset, grapheme) in line[row.start..row.end].grapheme_indices(true) {
+            let offset = row.start + offset;
+            let start_column = column;
+            column += grapheme_width(grapheme, column);
+            if column <= self.scroll.1 {
+                continue;
+            };
+            if column > self.scroll.1 + width {
+                break;
+            };
+            let selected = is_selected(offset);
+            if selected != piece_selected && !piece.is_empty() {
+                spans.push(styled_piece(std::mem::take(&mut piece), piece_selected, selected_style));
+            };
+            piece_selected = selected;
+            // Tabs are drawn as spaces, and so is the visible part of a wide grapheme cut off by the scrolling
+            match grapheme {
+                "\t" => piece.push_str(&" ".repeat(column - start_column.max(self.scroll.1))),
+                _ if start_column < self.scroll.1 => piece.push_str(&" ".repeat(column - self.scroll.1)),
+                _ => piece.push_str(grapheme),
+            };
+        };
+        if !piece.is_empty() {
+            spans.push(styled_piece(piece, piece_selected, selected_style));
+        };
+        // The line break is selected too, shown as a space at the end of the line
+        if row.end == line.len() && is_selected(line.len()) {
+            spans.push(Span::styled(" ", selected_style));
+        };
+        spans
+    }
+}
+
+
+fn styled_piece(text: String, selected: bool, selected_style: Style) -> Span<'static> {
+    match selected {
+        true => Span::styled(text, selected_style),
+        false => Span::raw(text),
+    }
+}
+
+/// Words are runs of graphemes of the same kind: letters, digits and `_`, or other symbols.
+/// A grapheme is of the kind of its first character, so accents stay with their letter
+fn grapheme_kind(grapheme: &str) -> u8 {
+    match grapheme.chars().next().unwrap_or(' ') {
+        c if c.is_whitespace() => 0,
+        c if c.is_alphanumeric() || c == '_' => 1,
+        _ => 2,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_area(text: &str, cursor: Position) -> TextArea {
+        let mut area = TextArea::new(text);
+        area.cursor = cursor;
+        area
+    }
+
+    fn selected(text: &str, anchor: Position, cursor: Position) -> TextArea {
+        let mut area = text_area(text, cursor);
+        area.anchor = Some(anchor);
+        area
+    }
+
+    fn rows(text: &str, width: usize) -> Vec<(usize, usize, usize)> {
+        let mut area = text_area(text, (0, 0));
+        area.wrap = true;
+        area.rows(width).iter().map(|row| (row.line, row.start, row.end)).collect()
+    }
+
+    #[test]
+    fn rows_wrap_long_lines() {
+        assert_eq!(rows("abcdef\nxy", 4), vec![(0, 0, 4), (0, 4, 6), (1, 0, 2)]);
+        // A full row gets an empty row after it for the cursor
+        assert_eq!(rows("abcd", 4), vec![(0, 0, 4), (0, 4, 4)]);
+        assert_eq!(rows("", 4), vec![(0, 0, 0)]);
+
+        let mut area = text_area("abcdef", (0, 0));
+        area.wrap = false;
+        assert_eq!(area.rows(4).len(), 1);
+    }
+
+    #[test]
+    fn rows_wrap_by_display_width() {
+        // Wide graphemes aren't split, the row ends before one that doesn't fit
+        assert_eq!(rows("日本語", 5), vec![(0, 0, 6), (0, 6, 9)]);
+        assert_eq!(rows("héllo", 3), vec![(0, 0, 4), (0, 4, 6)]);
+        // A tab reaches to the next tab stop
+        assert_eq!(rows("a\tb", 4), vec![(0, 0, 2), (0, 2, 3)]);
+    }
+
+    #[test]
+    fn delete_range() {
+        let mut area = text_area("héllo", (0, 5));
+        assert!(area.delete_range((0, 1), (0, 3)));
+        assert_eq!((area.text(), area.cursor), (String::from("hllo"), (0, 1)));
+
+        let mut area = text_area("ab\ncd\nef", (2, 2));
+        assert!(area.delete_range((0, 1), (2, 1)));
+        assert_eq!((area.text(), area.cursor), (String::from("af"), (0, 1)));
+
+        let mut area = text_area("日本", (0, 6));
+        assert!(area.delete_range((0, 3), (0, 6)));
+        assert_eq!((area.text(), area.cursor), (String::from("日"), (0, 3)));
+        assert!(!area.delete_range((0, 1), (0, 1)));
+    }
+
+    #[test]
+    fn word_motions() {
+        let text = "foo.bar  baz\nnext";
+        assert_eq!(text_area(text, (0, 12)).word_left(), (0, 9));
+        assert_eq!(text_area(text, (0, 9)).word_left(), (0, 4));
+        assert_eq!(text_area(text, (0, 4)).word_left(), (0, 3));
+        assert_eq!(text_area(text, (1, 0)).word_left(), (0, 12));
+        assert_eq!(text_area(text, (0, 0)).word_right(), (0, 3));
+        assert_eq!(text_area(text, (0, 3)).word_right(), (0, 4));
+        assert_eq!(text_area(text, (0, 7)).word_right(), (0, 12));
+        assert_eq!(text_area(text, (0, 12)).word_right(), (1, 0));
+    }
+
+    #[test]
+    fn word_motions_with_multi_byte_graphemes() {
+        let text = "héllo wörld 日本 語";
+        assert_eq!(text_area(text, (0, 0)).word_right(), (0, 6));
+        assert_eq!(text_area(text, (0, 6)).word_right(), (0, 13));
+        assert_eq!(text_area(text, (0, 13)).word_right(), (0, 20));
+        assert_eq!(text_area(text, (0, 24)).word_left(), (0, 21));
+        assert_eq!(text_area(text, (0, 13)).word_left(), (0, 7));
+        // An accent made of its own code point stays with its letter
+        assert_eq!(text_area("e\u{301}te x", (0, 0)).word_right(), (0, 5));
+    }
+
+    #[test]
+    fn selected_lines() {
+        assert_eq!(selected("ab\ncd\nef", (0, 1), (2, 1)).selected_lines(), Some((0, 2)));
+        // The line the selection ends at the start of isn't selected
+        assert_eq!(selected("ab\ncd\nef", (0, 1), (2, 0)).selected_lines(), Some((0, 1)));
+        assert_eq!(selected("ab\ncd\nef", (2, 0), (0, 1)).selected_lines(), Some((0, 1)));
+        assert_eq!(selected("ab\ncd", (0, 0), (0, 2)).selected_lines(), None);
+        assert_eq!(text_area("ab\ncd", (0, 0)).selected_lines(), None);
+    }
+
+    #[test]
+    fn indent() {
+        let mut area = text_area("x", (0, 0));
+        assert!(area.indent());
+        assert_eq!((area.text(), area.cursor), (String::from("    x"), (0, 4)));
+
+        let mut area = selected("ab\ncd\nef", (0, 1), (1, 1));
+        assert!(area.indent());
+        assert_eq!(area.text(), "    ab\n    cd\nef");
+        assert_eq!((area.anchor, area.cursor), (Some((0, 5)), (1, 5)));
+
+        let mut area = selected("é\nü", (0, 2), (1, 2));
+        assert!(area.indent());
+        assert_eq!((area.text(), area.cursor), (String::from("    é\n    ü"), (1, 6)));
+    }
+
+    #[test]
+    fn indent_with_tabs() {
+        let mut area = text_area("\tfoo", (0, 1));
+        assert!(area.indent());
+        assert_eq!((area.text(), area.cursor), (String::from("\t\tfoo"), (0, 2)));
+
+        // The first indented line tells, if the line of the cursor isn't indented
+        let mut area = text_area("a\n\tb", (0, 0));
+        assert!(area.indent());
+        assert_eq!(area.text(), "\ta\n\tb");
+
+        let mut area = selected("all:\n\tcc x\nx", (0, 0), (2, 1));
+        assert!(area.indent());
+        assert_eq!(area.text(), "\tall:\n\t\tcc x\n\tx");
+
+        let mut area = text_area("a\n  b\n\tc", (0, 0));
+        assert!(area.indent());
+        assert_eq!(area.text(), "    a\n  b\n\tc");
+    }
+
+    #[test]
+    fn dedent() {
+        let mut area = selected("\t  a\n      b\n  c\nd", (0, 3), (3, 1));
+        assert!(area.dedent());
+        assert_eq!(area.text(), "  a\n  b\nc\nd");
+        assert_eq!((area.anchor, area.cursor), (Some((0, 2)), (3, 1)));
+
+        let mut area = text_area("  日本", (0, 8));
+        assert!(area.dedent());
+        assert_eq!((area.text(), area.cursor), (String::from("日本"), (0, 6)));
+        assert!(!area.dedent());
+    }
+}
@@ -0,0 +1,180 @@
+/// What an edit did. Consecutive edits of the same kind are undone together, so undo takes back
+/// a word (and the spaces after it), a run of deletions or a paste at a time
+#[derive(Clone, Copy, PartialEq)]
+pub enum EditKind {
+    /// Typing letters, digits and `_`
+    Word,
+    /// Typing spaces, line breaks and other symbols
+    Space,
+    Delete,
+    /// Pasting, cutting, indenting or completing, always undone on its own
+    Other,
+}
+
+impl EditKind {
+    /// The kind of typing the character
+    pub fn typed(c: char) -> EditKind {
+        match c.is_alphanumeric() || c == '_' {
+            true => EditKind::Word,
+            false => EditKind::Space,
+        }
+    }
+}
+
+
+/// Undo and redo of a text field. Holds the state of the field before every group of edits
+#[derive(Clone)]
+pub struct UndoHistory<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+    /// Kind of the last edit, None if the next edit starts a new group (e.g. after the cursor moved)
+    last_edit: Option<EditKind>,
+}
+
+impl<T> Default for UndoHistory<T> {
+    fn default() -> Self {
+        UndoHistory { undo: vec![], redo: vec![], last_edit: None }
+    }
+}
+
+impl<T> UndoHistory<T> {
+    /// Records an edit that changed the field, `before` is the state before the edit
+    pub fn record(&mut self, before: T, kind: EditKind) {
+        let new_group = match (self.last_edit, kind) {
+            (_, EditKind::Other) | (None, _) => true,
+            // A word and the spaces typed after it are one group, the next word starts a new one
+            (Some(EditKind::Word), EditKind::Space) => false,
+            (Some(last), kind) => last != kind,
+        };
+        if new_group {
+            self.undo.push(before);
+        };
+        self.redo.clear();
+        self.last_edit = Some(kind);
+    }
+
+    /// Makes the next edit start a new group
+    pub fn break_group(&mut self) {
+        self.last_edit = None;
+    }
+
+    /// Number of groups that can be undone
+    pub fn group_count(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Joins the groups recorded since there were `count` groups into one
+    pub fn join_groups_since(&mut self, count: usize) {
+        self.undo.truncate(count + 1);
+    }
+
+    /// The state before the last group of edits, `current` can be brought back with `redo`
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let before = self.undo.pop()?;
+        self.redo.push(current);
+        self.last_edit = None;
+        Some(before)
+    }
+
+    /// The state before the last undo, `current` can be brought back with `undo`
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let after = self.redo.pop()?;
+        self.undo.push(current);
+        self.last_edit = None;
+        Some(after)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Types the text into `field` one character at a time, recording every edit
+    fn type_text(history: &mut UndoHistory<String>, field: &mut String, text: &str) {
+        for c in text.chars() {
+            history.record(field.clone(), EditKind::typed(c));
+            field.push(c);
+        };
+    }
+
+    /// Undoes everything, returns the states in the order they come back
+    fn undo_all(history: &mut UndoHistory<String>, mut field: String) -> Vec<String> {
+        let mut states = vec![];
+        while let Some(before) = history.undo(field) {
+            states.push(before.clone());
+            field = before;
+        };
+        states
+    }
+
+    #[test]
+    fn a_word_and_its_spaces_are_one_group() {
+        let mut history = UndoHistory::default();
+        let mut field = String::new();
+        // `=` is typed like a space, so `x = ` is one group
+        type_text(&mut history, &mut field, "let x = 1;");
+        assert_eq!(undo_all(&mut history, field), vec!["let x = ", "let ", ""]);
+    }
+
+    #[test]
+    fn deletions_are_one_group() {
+        let mut history = UndoHistory::default();
+        let mut field = String::new();
+        type_text(&mut history, &mut field, "abc");
+        for _ in 0..2 {
+            history.record(field.clone(), EditKind::Delete);
+            field.pop();
+        };
+        type_text(&mut history, &mut field, "d");
+        assert_eq!(undo_all(&mut history, field), vec!["a", "abc", ""]);
+    }
+
+    #[test]
+    fn other_edits_are_alone() {
+        let mut history = UndoHistory::default();
+        let mut field = String::from("x");
+        for pasted in ["a", "b"] {
+            history.record(field.clone(), EditKind::Other);
+            field.push_str(pasted);
+        };
+        type_text(&mut history, &mut field, "c");
+        // Typing after a paste starts a new group too
+        assert_eq!(undo_all(&mut history, field), vec!["xab", "xa", "x"]);
+    }
+
+    #[test]
+    fn break_group_and_join_groups_since() {
+        let mut history = UndoHistory::default();
+        let mut field = String::new();
+        type_text(&mut history, &mut field, "ab");
+        history.break_group();
+        type_text(&mut history, &mut field, "cd");
+        assert_eq!(history.group_count(), 2);
+
+        let count = history.group_count();
+        history.record(field.clone(), EditKind::Other);
+        field.push('e');
+        history.record(field.clone(), EditKind::Other);
+        field.push('f');
+        history.join_groups_since(count);
+        assert_eq!(undo_all(&mut history, field), vec!["abcd", "ab", ""]);
+    }
+
+    #[test]
+    fn redo() {
+        let mut history = UndoHistory::default();
+        let mut field = String::new();
+        type_text(&mut history, &mut field, "a b");
+        field = history.undo(field).unwrap();
+        assert_eq!(field, "a ");
+        field = history.redo(field).unwrap();
+        assert_eq!(field, "a b");
+        assert_eq!(history.redo(field.clone()), None);
+
+        // A new edit drops what could be redone
+        field = history.undo(field).unwrap();
+        type_text(&mut history, &mut field, "c");
+        assert_eq!(history.redo(field), None);
+    }
+}
@@ -0,0 +1,70 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Columns from one tab stop to the next
+pub const TAB_WIDTH: usize = 4;
+
+
+/// Columns the grapheme takes up on the screen if it starts at `column`, a tab reaches to the next tab stop
+pub fn grapheme_width(grapheme: &str, column: usize) -> usize {
+    match grapheme {
+        "\t" => TAB_WIDTH - column % TAB_WIDTH,
+        _ => grapheme.width(),
+    }
+}
+
+/// Columns a line takes up on the screen, with the tabs expanded
+pub fn display_width(line: &str) -> usize {
+    line.graphemes(true).fold(0, |column, grapheme| column + grapheme_width(grapheme, column))
+}
+
+/// Byte offset of the grapheme at the column, or the end of the line if it's shorter
+pub fn offset_at_column(line: &str, column: usize) -> usize {
+    let mut columns = 0;
+    for (offset, grapheme) in line.grapheme_indices(true) {
+        if columns >= column {
+            return offset;
+        };
+        columns += grapheme_width(grapheme, columns);
+    };
+    line.len()
+}
+
+/// The text with every tab replaced by spaces up to the next tab stop, the terminal would show tabs as nothing
+pub fn expand_tabs(text: &str) -> String {
+    if !text.contains('\t') {
+        return text.to_string();
+    };
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            let mut expanded = String::with_capacity(line.len());
+            let mut column = 0;
+            for grapheme in line.graphemes(true) {
+                let width = grapheme_width(grapheme, column);
+                match grapheme {
+                    "\t" => expanded.push_str(&" ".repeat(width)),
+                    _ => expanded.push_str(grapheme),
+                };
+                column += width;
+            };
+            expanded
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Removes the last grapheme, so Backspace takes away an emoji or a letter with its accents as a whole
+pub fn pop_grapheme(text: &mut String) {
+    text.truncate(previous_grapheme(text, text.len()));
+}
+
+/// Byte offset of the grapheme before the one at `offset`
+pub fn previous_grapheme(text: &str, offset: usize) -> usize {
+    text[..offset].grapheme_indices(true).next_back().map(|(i, _)| i).unwrap_or(0)
+}
+
+/// Byte offset of the grapheme after the one at `offset`
+pub fn next_grapheme(text: &str, offset: usize) -> usize {
+    text[offset..].graphemes(true).next().map(|g| offset + g.len()).unwrap_or(offset)
+}
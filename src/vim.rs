@@ -0,0 +1,1209 @@
+use std::{iter::Peekable, str::Chars};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    textarea::{Position, TextArea, INDENT},
+    unicode::{display_width, next_grapheme, offset_at_column, previous_grapheme},
+    StatefulList,
+};
+
+/// `p` and `P` put the register at most this many times, so a mistyped count can't fill the memory
+const MAX_PUT_COUNT: usize = 1000;
+
+
+#[derive(Clone, Copy, PartialEq, Default)]
+enum VimMode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+    /// Visual mode selecting whole lines
+    VisualLine,
+    /// Typing a command after `:`
+    Command,
+}
+
+/// What a `:` command asks the snippet popup to do
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExCommand {
+    /// `:w`, `:wq` and `:x`. Saving closes the popup, like CTRL-S
+    Save,
+    /// `:q`, or `:q!` to throw away the changes
+    Close,
+}
+
+/// What a key pressed in the snippet list asks for
+#[derive(Clone, Copy, PartialEq)]
+pub enum ListAction {
+    /// Moved in the list, or waiting for the second key of `gg` or `dd`
+    Done,
+    /// `/`
+    Search,
+    /// `dd`
+    Delete,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    /// `w`, or `W` for WORDs (runs of anything but blanks)
+    WordStart(bool),
+    /// `b`, or `B` for WORDs
+    WordBack(bool),
+    /// `e`, or `E` for WORDs
+    WordEnd(bool),
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    /// `gg`, or the line of the count
+    Top,
+    /// `G`, or the line of the count
+    Bottom,
+    /// `f`, `F`, `t` and `T`: the character, whether it's searched forward and whether the cursor stops before it
+    Find(char, bool, bool),
+}
+
+impl Motion {
+    /// Operators work on the whole lines between the cursor and the target
+    fn linewise(self) -> bool {
+        matches!(self, Motion::Up | Motion::Down | Motion::Top | Motion::Bottom)
+    }
+
+    /// Operators include the character at the target
+    fn inclusive(self) -> bool {
+        matches!(self, Motion::WordEnd(_) | Motion::Find(_, true, _))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+    Indent,
+    Dedent,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TextObject {
+    /// A word, or a WORD
+    Word(bool),
+    /// The text between two of the quotes on the line
+    Quote(char),
+    /// The text between the opening and the closing bracket around the cursor
+    Brackets(char, char),
+}
+
+enum Target {
+    Motion(Motion),
+    /// The text object, and whether it's the `a` (around) one
+    Object(TextObject, bool),
+    /// `dd`, `cc`, `yy`... the line of the cursor and the lines below it
+    Lines,
+}
+
+enum Command {
+    Move(Motion),
+    Operate(Operator, Target),
+    /// Selects the text object (and whether it's the `a` one) in visual mode
+    Select(TextObject, bool),
+    /// `r` and the character
+    Replace(char),
+    /// A key that does something by itself, like `p` or `i`
+    Key(char),
+}
+
+/// Outcome of parsing the keys typed so far
+enum Parse<T> {
+    Incomplete,
+    Invalid,
+    Done(T),
+}
+
+impl<T> Parse<T> {
+    fn map<U>(self, f: impl FnOnce(T) -> U) -> Parse<U> {
+        match self {
+            Parse::Incomplete => Parse::Incomplete,
+            Parse::Invalid => Parse::Invalid,
+            Parse::Done(value) => Parse::Done(f(value)),
+        }
+    }
+}
+
+/// What a run of graphemes is made of, for the word motions
+#[derive(Clone, Copy, PartialEq)]
+enum Class {
+    Blank,
+    /// Letters, digits and `_`, or anything but blanks for WORDs
+    Word,
+    Symbol,
+    LineBreak,
+}
+
+/// Text that was yanked or deleted, `p` and `P` put it back
+#[derive(Clone, Default)]
+struct Register {
+    text: String,
+    /// Whole lines, put below or above the line of the cursor
+    linewise: bool,
+}
+
+
+/// The vim keymap, enabled with `"keymap": "vim"` in the config. Holds the mode of the code editor,
+/// the keys of an unfinished command and the register, the editing itself is done by the TextArea
+#[derive(Clone, Default)]
+pub struct Vim {
+    mode: VimMode,
+    /// Keys of the normal or visual mode command typed so far, e.g. `2d` before `w`
+    pending: String,
+    /// What's typed after `:`
+    command: String,
+    register: Register,
+    /// Column `j` and `k` keep while going through shorter lines, `usize::MAX` after `$`
+    column: Option<usize>,
+    /// Groups of edits there were before the last command, the command and the text typed
+    /// in the insert mode it started are undone at once
+    undo_mark: usize,
+    /// Where the visual mode selection started
+    visual_start: Position,
+    /// Code the editor was opened with, `:q` doesn't close it if the code was changed
+    opened_code: String,
+    /// Error of the last `:` command
+    message: String,
+    /// First key of `gg` or `dd` in the snippet list
+    list_pending: Option<char>,
+}
+
+impl Vim {
+    /// Starts editing the code in normal mode. The register is kept
+    pub fn start_editing(&mut self, editor: &mut TextArea) {
+        *self = Vim { register: std::mem::take(&mut self.register), ..Vim::default() };
+        self.opened_code = editor.text();
+        clamp_cursor(editor);
+    }
+
+    /// The mode and the keys typed so far, shown in the title of the code editor
+    pub fn status(&self) -> String {
+        if !self.message.is_empty() {
+            return self.message.clone();
+        };
+        match self.mode {
+            VimMode::Normal if !self.pending.is_empty() => self.pending.clone(),
+            VimMode::Normal => String::from("-- NORMAL --"),
+            VimMode::Insert => String::from("-- INSERT --"),
+            VimMode::Visual => format!("-- VISUAL -- {}", self.pending),
+            VimMode::VisualLine => format!("-- VISUAL LINE -- {}", self.pending),
+            VimMode::Command => format!(":{}", self.command),
+        }
+    }
+
+    /// Handles a key of the code editor. Keys typed in insert mode go to `insert`, so the keys of
+    /// the default keymap (and the clipboard) work there. Returns the `:` command that was entered
+    pub fn editor_input(&mut self, editor: &mut TextArea, key: KeyEvent, insert: impl FnOnce(&mut TextArea, KeyEvent) -> bool) -> Option<ExCommand> {
+        self.message.clear();
+        match self.mode {
+            VimMode::Insert if key.code == KeyCode::Esc => {
+                editor.join_undo_groups(self.undo_mark);
+                self.mode = VimMode::Normal;
+                let (row, col) = editor.cursor();
+                editor.set_cursor((row, previous_grapheme(&editor.lines()[row], col)));
+                None
+            },
+            VimMode::Insert => {
+                insert(editor, key);
+                None
+            },
+            VimMode::Command => self.command_input(editor, key),
+            _ => {
+                self.normal_input(editor, key);
+                None
+            },
+        }
+    }
+
+    /// Handles a key of the snippet list: `j`/`k`, `gg`/`G`, `/` and `dd`. None if the key isn't one of them.
+    /// A `g` or `d` followed by another key is dropped, and the other key is handled on its own
+    pub fn list_input<T>(&mut self, key: KeyEvent, list: &mut StatefulList<T>) -> Option<ListAction> {
+        let pending = self.list_pending.take();
+        let c = match key.code {
+            KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => c,
+            // ESC only drops the waiting key
+            KeyCode::Esc if pending.is_some() => return Some(ListAction::Done),
+            _ => return None,
+        };
+        let last = list.items.len().checked_sub(1);
+        match (pending, c) {
+            (Some('g'), 'g') => list.state.select(last.map(|_| 0)),
+            (Some('d'), 'd') => return Some(ListAction::Delete),
+            (_, 'g' | 'd') => self.list_pending = Some(c),
+            (_, 'j') => list.next(),
+            (_, 'k') => list.previous(),
+            (_, 'G') => list.state.select(last),
+            (_, '/') => return Some(ListAction::Search),
+            _ => return None,
+        };
+        Some(ListAction::Done)
+    }
+
+
+    fn command_input(&mut self, editor: &mut TextArea, key: KeyEvent) -> Option<ExCommand> {
+        match key.code {
+            KeyCode::Esc => self.mode = VimMode::Normal,
+            KeyCode::Backspace if self.command.is_empty() => self.mode = VimMode::Normal,
+            KeyCode::Backspace => {
+                self.command.pop();
+            },
+            KeyCode::Char(c) => self.command.push(c),
+            KeyCode::Enter => {
+                self.mode = VimMode::Normal;
+                let command = std::mem::take(&mut self.command);
+                match command.trim() {
+                    "" => (),
+                    "w" | "w!" | "wq" | "wq!" | "x" | "x!" => return Some(ExCommand::Save),
+                    "q!" => return Some(ExCommand::Close),
+                    "q" if editor.text() == self.opened_code => return Some(ExCommand::Close),
+                    "q" => self.message = String::from("E37: No write since last change (add ! to override)"),
+                    // `:12` goes to line 12
+                    line if line.parse::<usize>().is_ok() => {
+                        let row = line.parse::<usize>().unwrap_or(1).saturating_sub(1).min(editor.lines().len() - 1);
+                        editor.set_cursor(first_non_blank(editor.lines(), row));
+                    },
+                    other => self.message = format!("E492: Not an editor command: {}", other),
+                };
+            },
+            _ => (),
+        };
+        None
+    }
+
+    fn normal_input(&mut self, editor: &mut TextArea, key: KeyEvent) {
+        let visual = self.mode != VimMode::Normal;
+        match key.code {
+            KeyCode::Esc => {
+                self.pending.clear();
+                if visual {
+                    self.leave_visual(editor);
+                };
+                return;
+            },
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.pending.clear();
+                editor.redo();
+                clamp_cursor(editor);
+                return;
+            },
+            _ => (),
+        };
+        let Some(c) = key_char(key) else {
+            self.pending.clear();
+            return;
+        };
+        self.pending.push(c);
+        match parse(&self.pending, visual) {
+            Parse::Incomplete => return,
+            Parse::Invalid => self.pending.clear(),
+            Parse::Done((command, count)) => {
+                self.pending.clear();
+                self.undo_mark = editor.undo_groups();
+                self.execute(editor, command, count);
+            },
+        };
+        match self.mode {
+            VimMode::Normal => clamp_cursor(editor),
+            VimMode::Visual | VimMode::VisualLine => {
+                clamp_cursor(editor);
+                editor.set_highlight(Some(self.visual_range(editor).0));
+            },
+            _ => (),
+        };
+    }
+
+    fn execute(&mut self, editor: &mut TextArea, command: Command, count: Option<usize>) {
+        let (row, col) = editor.cursor();
+        let times = count.unwrap_or(1);
+        match command {
+            Command::Move(motion) => {
+                let column = match motion {
+                    Motion::Up | Motion::Down => Some(self.column.unwrap_or_else(|| display_width(&editor.lines()[row][..col]))),
+                    Motion::LineEnd => Some(usize::MAX),
+                    _ => None,
+                };
+                self.column = column;
+                if let Some(target) = self.motion_target(editor, motion, count) {
+                    editor.set_cursor(target);
+                };
+                return;
+            },
+            Command::Operate(operator, target) => {
+                let range = match target {
+                    Target::Lines => {
+                        let last = row.saturating_add(times - 1).min(editor.lines().len() - 1);
+                        Some(((row, 0), (last, editor.lines()[last].len()), true))
+                    },
+                    Target::Motion(motion) => self.motion_range(editor, operator, motion, count),
+                    Target::Object(object, around) => object_range(editor.lines(), editor.cursor(), object, around),
+                };
+                if let Some((start, end, linewise)) = range {
+                    self.operate(editor, operator, start, end, linewise);
+                };
+            },
+            Command::Select(object, around) => {
+                if let Some((start, end, _)) = object_range(editor.lines(), editor.cursor(), object, around) {
+                    self.visual_start = start;
+                    editor.set_cursor(before(editor.lines(), end));
+                };
+            },
+            Command::Replace(c) => {
+                let line = &editor.lines()[row];
+                let mut end = col;
+                for _ in 0..times {
+                    if end == line.len() {
+                        return;
+                    };
+                    end = next_grapheme(line, end);
+                };
+                editor.replace((row, col), (row, end), &c.to_string().repeat(times));
+                let (row, col) = editor.cursor();
+                editor.set_cursor((row, previous_grapheme(&editor.lines()[row], col)));
+            },
+            Command::Key(key) if self.mode != VimMode::Normal => self.visual_key(editor, key),
+            Command::Key(key) => self.normal_key(editor, key, times),
+        };
+        self.column = None;
+    }
+
+    /// Keys of the normal mode that aren't motions or operators
+    fn normal_key(&mut self, editor: &mut TextArea, key: char, times: usize) {
+        let (row, col) = editor.cursor();
+        let line = &editor.lines()[row];
+        let last_row = editor.lines().len() - 1;
+        match key {
+            'i' => self.mode = VimMode::Insert,
+            'a' => {
+                editor.set_cursor((row, next_grapheme(line, col)));
+                self.mode = VimMode::Insert;
+            },
+            'I' => {
+                editor.set_cursor(first_non_blank(editor.lines(), row));
+                self.mode = VimMode::Insert;
+            },
+            'A' => {
+                editor.set_cursor((row, line.len()));
+                self.mode = VimMode::Insert;
+            },
+            'o' => {
+                editor.replace((row, line.len()), (row, line.len()), "\n");
+                self.mode = VimMode::Insert;
+            },
+            'O' => {
+                editor.replace((row, 0), (row, 0), "\n");
+                editor.set_cursor((row, 0));
+                self.mode = VimMode::Insert;
+            },
+            'v' | 'V' => {
+                self.visual_start = (row, col);
+                self.mode = match key {
+                    'v' => VimMode::Visual,
+                    _ => VimMode::VisualLine,
+                };
+            },
+            ':' => self.mode = VimMode::Command,
+            'u' => {
+                for _ in 0..times {
+                    if !editor.undo() {
+                        break;
+                    };
+                };
+            },
+            'p' | 'P' => self.put(editor, key == 'p', times.min(MAX_PUT_COUNT)),
+            'J' => {
+                for _ in 0..times.max(2) - 1 {
+                    let row = editor.cursor().0;
+                    if row == last_row {
+                        break;
+                    };
+                    let (line, next) = (&editor.lines()[row], &editor.lines()[row + 1]);
+                    let indentation = next.len() - next.trim_start().len();
+                    let separator = match next.trim().is_empty() || line.is_empty() || line.ends_with(' ') {
+                        true => "",
+                        false => " ",
+                    };
+                    let end_of_line = line.len();
+                    editor.replace((row, end_of_line), (row + 1, indentation), separator);
+                    editor.set_cursor((row, end_of_line));
+                };
+                editor.join_undo_groups(self.undo_mark);
+            },
+            _ => (),
+        };
+    }
+
+    /// Keys of the visual modes that aren't motions
+    fn visual_key(&mut self, editor: &mut TextArea, key: char) {
+        let ((start, end), linewise) = self.visual_range(editor);
+        let operator = match key {
+            'd' | 'x' => Operator::Delete,
+            'c' | 's' => Operator::Change,
+            'y' => Operator::Yank,
+            '>' => Operator::Indent,
+            '<' => Operator::Dedent,
+            'o' => {
+                let cursor = editor.cursor();
+                editor.set_cursor(self.visual_start);
+                self.visual_start = cursor;
+                return;
+            },
+            'v' | 'V' => {
+                let mode = match key {
+                    'v' => VimMode::Visual,
+                    _ => VimMode::VisualLine,
+                };
+                match self.mode == mode {
+                    true => self.leave_visual(editor),
+                    false => self.mode = mode,
+                };
+                return;
+            },
+            _ => return,
+        };
+        self.leave_visual(editor);
+        self.operate(editor, operator, start, end, linewise);
+    }
+
+    fn leave_visual(&mut self, editor: &mut TextArea) {
+        self.mode = VimMode::Normal;
+        editor.set_highlight(None);
+        clamp_cursor(editor);
+    }
+
+    /// The selected text (start first, the end isn't part of it) and whether whole lines are selected
+    fn visual_range(&self, editor: &TextArea) -> ((Position, Position), bool) {
+        let lines = editor.lines();
+        let start = self.visual_start.min(editor.cursor());
+        let end = self.visual_start.max(editor.cursor());
+        match self.mode {
+            VimMode::VisualLine => (((start.0, 0), (end.0, lines[end.0].len())), true),
+            _ => ((start, after(lines, end)), false),
+        }
+    }
+
+    /// Where the motion moves the cursor, `count` times. None if it can't, like `f` without a match
+    fn motion_target(&self, editor: &TextArea, motion: Motion, count: Option<usize>) -> Option<Position> {
+        let lines = editor.lines();
+        let (row, col) = editor.cursor();
+        let line = &lines[row];
+        let last_row = lines.len() - 1;
+        let times = count.unwrap_or(1);
+        let vertical = |target_row: usize| {
+            let column = self.column.unwrap_or_else(|| display_width(&line[..col]));
+            (target_row, offset_at_column(&lines[target_row], column))
+        };
+        let target = match motion {
+            Motion::Left => (0..times.min(line.len())).fold((row, col), |(row, col), _| (row, previous_grapheme(line, col))),
+            Motion::Right => (0..times.min(line.len())).fold((row, col), |(row, col), _| (row, next_grapheme(line, col))),
+            Motion::Up => vertical(row.saturating_sub(times)),
+            Motion::Down => vertical(row.saturating_add(times).min(last_row)),
+            Motion::WordStart(big) | Motion::WordBack(big) | Motion::WordEnd(big) => {
+                let graphemes = classify(lines, big);
+                let mut i = graphemes.iter().position(|(position, _)| *position >= (row, col)).unwrap_or(0);
+                for _ in 0..times.min(graphemes.len()) {
+                    i = match motion {
+                        Motion::WordStart(_) => word_start(&graphemes, i),
+                        Motion::WordBack(_) => word_back(&graphemes, i),
+                        _ => word_end(&graphemes, i),
+                    };
+                };
+                graphemes[i].0
+            },
+            Motion::LineStart => (row, 0),
+            Motion::FirstNonBlank => first_non_blank(lines, row),
+            Motion::LineEnd => {
+                let row = row.saturating_add(times - 1).min(last_row);
+                (row, lines[row].len())
+            },
+            Motion::Top => first_non_blank(lines, count.map(|c| c - 1).unwrap_or(0).min(last_row)),
+            Motion::Bottom => first_non_blank(lines, count.map(|c| c - 1).unwrap_or(last_row).min(last_row)),
+            Motion::Find(c, forward, till) => (row, find_in_line(line, col, c, forward, till, times)?),
+        };
+        Some(target)
+    }
+
+    /// The text an operator works on when it's followed by the motion, start first
+    fn motion_range(&self, editor: &TextArea, operator: Operator, motion: Motion, count: Option<usize>) -> Option<(Position, Position, bool)> {
+        let lines = editor.lines();
+        let cursor = editor.cursor();
+        let target = match (operator, motion) {
+            // Like in vim, `cw` on a word changes to its end and leaves the blanks after it alone
+            (Operator::Change, Motion::WordStart(big)) => {
+                let graphemes = classify(lines, big);
+                let mut i = graphemes.iter().position(|(position, _)| *position >= cursor).unwrap_or(0);
+                match graphemes[i].1 {
+                    Class::Blank | Class::LineBreak => self.motion_target(editor, motion, count)?,
+                    class => {
+                        while i + 1 < graphemes.len() && graphemes[i + 1].1 == class {
+                            i += 1;
+                        };
+                        for _ in 1..count.unwrap_or(1) {
+                            i = word_end(&graphemes, i);
+                        };
+                        after(lines, graphemes[i].0)
+                    },
+                }
+            },
+            _ => self.motion_target(editor, motion, count)?,
+        };
+        let (start, mut end) = (cursor.min(target), cursor.max(target));
+        if motion.linewise() {
+            return Some(((start.0, 0), (end.0, lines[end.0].len()), true));
+        };
+        if motion.inclusive() {
+            end = after(lines, end);
+        };
+        // A motion that ends at the start of a line below, like `w` on the last word of a line, keeps the line break
+        if !motion.inclusive() && end.0 > start.0 && end.1 == 0 {
+            end = (end.0 - 1, lines[end.0 - 1].len());
+        };
+        Some((start, end, false))
+    }
+
+    /// Applies the operator to the text between the positions, or to the lines from the start to the end
+    fn operate(&mut self, editor: &mut TextArea, operator: Operator, start: Position, end: Position, linewise: bool) {
+        let lines = editor.lines();
+        match operator {
+            Operator::Indent | Operator::Dedent => {
+                for row in start.0..=end.0 {
+                    let line = &editor.lines()[row];
+                    let removed = match line.starts_with('\t') {
+                        true => 1,
+                        false => line.len() - line.trim_start_matches(' ').len(),
+                    }
+                    .min(INDENT.len());
+                    match operator {
+                        Operator::Indent if !line.is_empty() => editor.replace((row, 0), (row, 0), INDENT),
+                        Operator::Dedent => editor.replace((row, 0), (row, removed), ""),
+                        _ => false,
+                    };
+                };
+                editor.join_undo_groups(self.undo_mark);
+                editor.set_cursor(first_non_blank(editor.lines(), start.0));
+                return;
+            },
+            _ => (),
+        };
+        let text = match linewise {
+            true => lines[start.0..=end.0].join("\n"),
+            false => text_between(lines, start, end),
+        };
+        self.register = Register { text, linewise };
+        match (operator, linewise) {
+            (Operator::Yank, true) if editor.cursor().0 != start.0 => editor.set_cursor(first_non_blank(lines, start.0)),
+            (Operator::Yank, true) => (),
+            (Operator::Yank, false) => editor.set_cursor(start),
+            (Operator::Delete, true) => {
+                let last_row = lines.len() - 1;
+                match (end.0 < last_row, start.0 > 0) {
+                    (true, _) => editor.replace((start.0, 0), (end.0 + 1, 0), ""),
+                    (false, true) => editor.replace((start.0 - 1, lines[start.0 - 1].len()), (end.0, lines[end.0].len()), ""),
+                    (false, false) => editor.replace((0, 0), (end.0, lines[end.0].len()), ""),
+                };
+                let row = start.0.min(editor.lines().len() - 1);
+                editor.set_cursor(first_non_blank(editor.lines(), row));
+            },
+            (Operator::Change, true) => {
+                editor.replace((start.0, 0), (end.0, lines[end.0].len()), "");
+                self.mode = VimMode::Insert;
+            },
+            (Operator::Delete, false) => {
+                editor.replace(start, end, "");
+            },
+            (Operator::Change, false) => {
+                editor.replace(start, end, "");
+                self.mode = VimMode::Insert;
+            },
+            _ => (),
+        };
+    }
+
+    /// Puts the register after (`p`) or before (`P`) the cursor, or below or above its line for whole lines
+    fn put(&mut self, editor: &mut TextArea, after_cursor: bool, times: usize) {
+        let Register { text, linewise } = self.register.clone();
+        if text.is_empty() {
+            return;
+        };
+        let (row, col) = editor.cursor();
+        let line = &editor.lines()[row];
+        match (linewise, after_cursor) {
+            (true, true) => {
+                editor.replace((row, line.len()), (row, line.len()), &format!("\n{}", vec![text; times].join("\n")));
+                editor.set_cursor(first_non_blank(editor.lines(), row + 1));
+            },
+            (true, false) => {
+                editor.replace((row, 0), (row, 0), &format!("{}\n", vec![text; times].join("\n")));
+                editor.set_cursor(first_non_blank(editor.lines(), row));
+            },
+            (false, _) => {
+                let at = match after_cursor {
+                    true => next_grapheme(line, col),
+                    false => col,
+                };
+                editor.replace((row, at), (row, at), &text.repeat(times));
+                editor.set_cursor(before(editor.lines(), editor.cursor()));
+            },
+        };
+    }
+}
+
+
+/// The character a key stands for in normal and visual mode, the arrow keys work like `h`, `j`, `k` and `l`
+fn key_char(key: KeyEvent) -> Option<char> {
+    match key.code {
+        KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => Some(c),
+        KeyCode::Left | KeyCode::Backspace => Some('h'),
+        KeyCode::Right => Some('l'),
+        KeyCode::Up => Some('k'),
+        KeyCode::Down => Some('j'),
+        KeyCode::Home => Some('0'),
+        KeyCode::End => Some('$'),
+        _ => None,
+    }
+}
+
+/// Parses the keys of a normal or visual mode command like `3dw`, `ci(`, `gg` or `p`, with its count
+fn parse(keys: &str, visual: bool) -> Parse<(Command, Option<usize>)> {
+    let mut chars = keys.chars().peekable();
+    let count = take_count(&mut chars);
+    let Some(key) = chars.next() else {
+        return Parse::Incomplete;
+    };
+    let operator = match key {
+        'd' => Some(Operator::Delete),
+        'c' => Some(Operator::Change),
+        'y' => Some(Operator::Yank),
+        '>' => Some(Operator::Indent),
+        '<' => Some(Operator::Dedent),
+        _ => None,
+    };
+    // Shorthands for an operator and a motion
+    let shorthand = match key {
+        'x' => Some((Operator::Delete, Target::Motion(Motion::Right))),
+        'X' => Some((Operator::Delete, Target::Motion(Motion::Left))),
+        'D' => Some((Operator::Delete, Target::Motion(Motion::LineEnd))),
+        'C' => Some((Operator::Change, Target::Motion(Motion::LineEnd))),
+        's' => Some((Operator::Change, Target::Motion(Motion::Right))),
+        'S' => Some((Operator::Change, Target::Lines)),
+        'Y' => Some((Operator::Yank, Target::Lines)),
+        _ => None,
+    };
+    let command = match (operator, shorthand, visual) {
+        (Some(operator), _, false) => {
+            let count = match (count, take_count(&mut chars)) {
+                (Some(first), Some(second)) => Some(first.saturating_mul(second)),
+                (first, second) => first.or(second),
+            };
+            let Some(next) = chars.next() else {
+                return Parse::Incomplete;
+            };
+            let target = match next {
+                _ if next == key => Parse::Done(Target::Lines),
+                'i' | 'a' => text_object(chars.next()).map(|object| Target::Object(object, next == 'a')),
+                _ => motion(next, chars.next()).map(Target::Motion),
+            };
+            return target.map(|target| (Command::Operate(operator, target), count));
+        },
+        (None, Some((operator, target)), false) => Parse::Done(Command::Operate(operator, target)),
+        (_, _, true) if matches!(key, 'i' | 'a') => {
+            text_object(chars.next()).map(|object| Command::Select(object, key == 'a'))
+        },
+        (_, _, true) if matches!(key, 'd' | 'x' | 'c' | 's' | 'y' | '>' | '<' | 'o' | 'v' | 'V') => Parse::Done(Command::Key(key)),
+        (_, _, false) if "iaIAoOvV:upPJ".contains(key) => Parse::Done(Command::Key(key)),
+        (_, _, false) if key == 'r' => match chars.next() {
+            Some(c) => Parse::Done(Command::Replace(c)),
+            None => Parse::Incomplete,
+        },
+        _ => motion(key, chars.next()).map(Command::Move),
+    };
+    command.map(|command| (command, count))
+}
+
+/// Takes a count from the front of the keys. A `0` at the front is the motion to the start of the line
+fn take_count(chars: &mut Peekable<Chars>) -> Option<usize> {
+    let mut count: Option<usize> = None;
+    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)).filter(|d| *d > 0 || count.is_some()) {
+        count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize));
+        chars.next();
+    };
+    count
+}
+
+/// The motion of the key, `next` is the key after it (for `gg` and `f`)
+fn motion(key: char, next: Option<char>) -> Parse<Motion> {
+    let find = |forward: bool, till: bool| match next {
+        Some(c) => Parse::Done(Motion::Find(c, forward, till)),
+        None => Parse::Incomplete,
+    };
+    match key {
+        'h' => Parse::Done(Motion::Left),
+        'l' | ' ' => Parse::Done(Motion::Right),
+        'j' => Parse::Done(Motion::Down),
+        'k' => Parse::Done(Motion::Up),
+        'w' | 'W' => Parse::Done(Motion::WordStart(key == 'W')),
+        'b' | 'B' => Parse::Done(Motion::WordBack(key == 'B')),
+        'e' | 'E' => Parse::Done(Motion::WordEnd(key == 'E')),
+        '0' => Parse::Done(Motion::LineStart),
+        '^' => Parse::Done(Motion::FirstNonBlank),
+        '$' => Parse::Done(Motion::LineEnd),
+        'G' => Parse::Done(Motion::Bottom),
+        'g' => match next {
+            Some('g') => Parse::Done(Motion::Top),
+            Some(_) => Parse::Invalid,
+            None => Parse::Incomplete,
+        },
+        'f' => find(true, false),
+        'F' => find(false, false),
+        't' => find(true, true),
+        'T' => find(false, true),
+        _ => Parse::Invalid,
+    }
+}
+
+/// The text object of the key after `i` or `a`
+fn text_object(key: Option<char>) -> Parse<TextObject> {
+    match key {
+        None => Parse::Incomplete,
+        Some('w') => Parse::Done(TextObject::Word(false)),
+        Some('W') => Parse::Done(TextObject::Word(true)),
+        Some(quote @ ('"' | '\'' | '`')) => Parse::Done(TextObject::Quote(quote)),
+        Some('(' | ')' | 'b') => Parse::Done(TextObject::Brackets('(', ')')),
+        Some('{' | '}' | 'B') => Parse::Done(TextObject::Brackets('{', '}')),
+        Some('[' | ']') => Parse::Done(TextObject::Brackets('[', ']')),
+        Some('<' | '>') => Parse::Done(TextObject::Brackets('<', '>')),
+        Some(_) => Parse::Invalid,
+    }
+}
+
+/// In normal mode the cursor is on a character, not behind the last one
+fn clamp_cursor(editor: &mut TextArea) {
+    let (row, col) = editor.cursor();
+    let line = &editor.lines()[row];
+    if col >= line.len() && !line.is_empty() {
+        editor.set_cursor((row, previous_grapheme(line, line.len())));
+    };
+}
+
+fn first_non_blank(lines: &[String], row: usize) -> Position {
+    let line = &lines[row];
+    (row, line.len() - line.trim_start().len())
+}
+
+/// The position after the grapheme at the position, the start of the next line at the end of a line
+fn after(lines: &[String], (row, col): Position) -> Position {
+    match (col < lines[row].len(), row + 1 < lines.len()) {
+        (true, _) => (row, next_grapheme(&lines[row], col)),
+        (false, true) => (row + 1, 0),
+        (false, false) => (row, col),
+    }
+}
+
+/// The position of the grapheme before the position, the end of the line above at the start of a line
+fn before(lines: &[String], (row, col): Position) -> Position {
+    match (col > 0, row > 0) {
+        (true, _) => (row, previous_grapheme(&lines[row], col)),
+        (false, true) => (row - 1, lines[row - 1].len()),
+        (false, false) => (row, col),
+    }
+}
+
+fn text_between(lines: &[String], start: Position, end: Position) -> String {
+    if start.0 == end.0 {
+        return lines[start.0][start.1..end.1].to_string();
+    };
+    let mut text = lines[start.0][start.1..].to_string();
+    for line in &lines[start.0 + 1..end.0] {
+        text.push('\n');
+        text.push_str(line);
+    };
+    text.push('\n');
+    text.push_str(&lines[end.0][..end.1]);
+    text
+}
+
+fn grapheme_class(grapheme: &str, big: bool) -> Class {
+    match grapheme.chars().next().unwrap_or(' ') {
+        c if c.is_whitespace() => Class::Blank,
+        _ if big => Class::Word,
+        c if c.is_alphanumeric() || c == '_' => Class::Word,
+        _ => Class::Symbol,
+    }
+}
+
+/// Every grapheme of the text with its class, and the end of every line as a line break
+fn classify(lines: &[String], big: bool) -> Vec<(Position, Class)> {
+    let mut graphemes = vec![];
+    for (row, line) in lines.iter().enumerate() {
+        for (col, grapheme) in line.grapheme_indices(true) {
+            graphemes.push(((row, col), grapheme_class(grapheme, big)));
+        };
+        graphemes.push(((row, line.len()), Class::LineBreak));
+    };
+    graphemes
+}
+
+/// An empty line counts as a word of its own
+fn is_empty_line(grapheme: &(Position, Class)) -> bool {
+    grapheme.1 == Class::LineBreak && grapheme.0 .1 == 0
+}
+
+/// Index of the start of the next word
+fn word_start(graphemes: &[(Position, Class)], from: usize) -> usize {
+    let mut i = from;
+    let class = graphemes[i].1;
+    if matches!(class, Class::Word | Class::Symbol) {
+        while i < graphemes.len() && graphemes[i].1 == class {
+            i += 1;
+        };
+    };
+    while i < graphemes.len() && matches!(graphemes[i].1, Class::Blank | Class::LineBreak) && !(i != from && is_empty_line(&graphemes[i])) {
+        i += 1;
+    };
+    i.min(graphemes.len() - 1)
+}
+
+/// Index of the start of the word before
+fn word_back(graphemes: &[(Position, Class)], from: usize) -> usize {
+    let mut i = from.saturating_sub(1);
+    while i > 0 && matches!(graphemes[i].1, Class::Blank | Class::LineBreak) && !is_empty_line(&graphemes[i]) {
+        i -= 1;
+    };
+    let class = graphemes[i].1;
+    if class != Class::LineBreak {
+        while i > 0 && graphemes[i - 1].1 == class {
+            i -= 1;
+        };
+    };
+    i
+}
+
+/// Index of the end of the next word
+fn word_end(graphemes: &[(Position, Class)], from: usize) -> usize {
+    let mut i = (from + 1).min(graphemes.len() - 1);
+    while i + 1 < graphemes.len() && matches!(graphemes[i].1, Class::Blank | Class::LineBreak) {
+        i += 1;
+    };
+    let class = graphemes[i].1;
+    while i + 1 < graphemes.len() && graphemes[i + 1].1 == class {
+        i += 1;
+    };
+    i
+}
+
+/// Offset `f`, `F`, `t` or `T` move to in the line, the `times`th match
+fn find_in_line(line: &str, col: usize, c: char, forward: bool, till: bool, times: usize) -> Option<usize> {
+    let target = c.to_string();
+    let mut found = col;
+    for _ in 0..times {
+        found = match forward {
+            true => {
+                let start = next_grapheme(line, found);
+                start + line[start..].grapheme_indices(true).find(|(_, g)| *g == target)?.0
+            },
+            false => line[..found].grapheme_indices(true).rev().find(|(_, g)| *g == target)?.0,
+        };
+    };
+    Some(match (till, forward) {
+        (false, _) => found,
+        (true, true) => previous_grapheme(line, found),
+        (true, false) => next_grapheme(line, found),
+    })
+}
+
+/// The text of the object around the cursor, start first, and whether it's made of whole lines
+fn object_range(lines: &[String], cursor: Position, object: TextObject, around: bool) -> Option<(Position, Position, bool)> {
+    let (row, col) = cursor;
+    let line = &lines[row];
+    match object {
+        TextObject::Word(big) => {
+            let (start, end) = word_object(line, col, big, around);
+            Some(((row, start), (row, end), false))
+        },
+        TextObject::Quote(quote) => {
+            let quotes: Vec<usize> = line.match_indices(quote).map(|(i, _)| i).collect();
+            let (open, close) = quotes.chunks_exact(2).map(|pair| (pair[0], pair[1])).find(|(_, close)| *close >= col)?;
+            match around {
+                true => {
+                    let end = close + quote.len_utf8();
+                    let end = end + line[end..].len() - line[end..].trim_start_matches([' ', '\t']).len();
+                    Some(((row, open), (row, end), false))
+                },
+                false => Some(((row, open + quote.len_utf8()), (row, close), false)),
+            }
+        },
+        TextObject::Brackets(open, close) => brackets_object(lines, cursor, open, close, around),
+    }
+}
+
+/// Offsets of the run of graphemes of the same class at the column. The `a` object takes the blanks after it
+/// too, or the blanks before it if there are none after it
+fn word_object(line: &str, col: usize, big: bool, around: bool) -> (usize, usize) {
+    let graphemes: Vec<(usize, Class)> = line.grapheme_indices(true).map(|(i, g)| (i, grapheme_class(g, big))).collect();
+    if graphemes.is_empty() {
+        return (0, 0);
+    };
+    let run = |i: usize| {
+        let (mut start, mut end) = (i, i + 1);
+        while start > 0 && graphemes[start - 1].1 == graphemes[i].1 {
+            start -= 1;
+        };
+        while end < graphemes.len() && graphemes[end].1 == graphemes[i].1 {
+            end += 1;
+        };
+        (start, end)
+    };
+    let i = graphemes.iter().position(|(offset, _)| *offset >= col).unwrap_or(graphemes.len() - 1);
+    let (mut start, mut end) = run(i);
+    if around {
+        let blank = graphemes[i].1 == Class::Blank;
+        if end < graphemes.len() && (blank || graphemes[end].1 == Class::Blank) {
+            end = run(end).1;
+        } else if start > 0 && graphemes[start - 1].1 == Class::Blank {
+            start = run(start - 1).0;
+        };
+    };
+    (graphemes[start].0, graphemes.get(end).map(|(offset, _)| *offset).unwrap_or(line.len()))
+}
+
+/// The text between the brackets around the cursor, with the brackets for the `a` object. If the brackets are
+/// on lines of their own, the inner object is made of the lines between them
+fn brackets_object(lines: &[String], cursor: Position, open: char, close: char, around: bool) -> Option<(Position, Position, bool)> {
+    let chars: Vec<(Position, char)> = lines
+        .iter()
+        .enumerate()
+        .flat_map(|(row, line)| line.char_indices().map(move |(col, c)| ((row, col), c)).chain([((row, line.len()), '\n')]))
+        .collect();
+    let i = chars.iter().position(|(position, _)| *position >= cursor)?;
+    let mut depth = 0;
+    let mut start = i;
+    if chars[i].1 != open {
+        loop {
+            start = start.checked_sub(1)?;
+            match chars[start].1 {
+                c if c == close => depth += 1,
+                c if c == open && depth == 0 => break,
+                c if c == open => depth -= 1,
+                _ => (),
+            };
+        };
+    };
+    let mut end = start;
+    loop {
+        end += 1;
+        match chars.get(end)?.1 {
+            c if c == open => depth += 1,
+            c if c == close && depth == 0 => break,
+            c if c == close => depth -= 1,
+            _ => (),
+        };
+    };
+    let (open_position, close_position) = (chars[start].0, chars[end].0);
+    if around {
+        return Some((open_position, (close_position.0, close_position.1 + close.len_utf8()), false));
+    };
+    let inner_start = (open_position.0, open_position.1 + open.len_utf8());
+    let opens_line = inner_start.1 == lines[inner_start.0].len();
+    let closes_line = lines[close_position.0][..close_position.1].trim().is_empty();
+    match (opens_line, closes_line) {
+        (true, true) if close_position.0 > open_position.0 + 1 => {
+            let last = close_position.0 - 1;
+            Some(((open_position.0 + 1, 0), (last, lines[last].len()), true))
+        },
+        _ => Some((inner_start, close_position, false)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.split('\n').map(String::from).collect()
+    }
+
+    /// Types the keys in normal mode with the cursor at the position, `\x1b` is ESC.
+    /// Returns the text and the cursor afterwards
+    fn type_keys(text: &str, cursor: Position, keys: &str) -> (String, Position) {
+        let mut editor = TextArea::new(text);
+        editor.set_cursor(cursor);
+        let mut vim = Vim::default();
+        vim.start_editing(&mut editor);
+        for c in keys.chars() {
+            let key = match c {
+                '\x1b' => KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+                c => KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+            };
+            vim.editor_input(&mut editor, key, |editor, key| editor.input(key));
+        };
+        (editor.text(), editor.cursor())
+    }
+
+    #[test]
+    fn list_keys() {
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        let mut list = StatefulList::with_items(vec![1, 2, 3]);
+        let mut vim = Vim::default();
+        assert!(matches!(vim.list_input(key('G'), &mut list), Some(ListAction::Done)));
+        assert!(matches!(vim.list_input(key('g'), &mut list), Some(ListAction::Done)));
+        assert!(matches!(vim.list_input(key('g'), &mut list), Some(ListAction::Done)));
+        assert_eq!(list.state.selected(), Some(0));
+        // The key after an unfinished `g` is handled on its own
+        vim.list_input(key('g'), &mut list);
+        assert!(matches!(vim.list_input(key('j'), &mut list), Some(ListAction::Done)));
+        assert_eq!(list.state.selected(), Some(1));
+        vim.list_input(key('g'), &mut list);
+        assert!(matches!(vim.list_input(key('d'), &mut list), Some(ListAction::Done)));
+        assert!(matches!(vim.list_input(key('d'), &mut list), Some(ListAction::Delete)));
+        vim.list_input(key('d'), &mut list);
+        assert!(vim.list_input(key('x'), &mut list).is_none());
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert!(matches!(parse("3dw", false), Parse::Done((Command::Operate(Operator::Delete, Target::Motion(Motion::WordStart(false))), Some(3)))));
+        assert!(matches!(parse("2d3w", false), Parse::Done((Command::Operate(Operator::Delete, _), Some(6)))));
+        assert!(matches!(parse("dd", false), Parse::Done((Command::Operate(Operator::Delete, Target::Lines), None))));
+        assert!(matches!(parse("ci(", false), Parse::Done((Command::Operate(Operator::Change, Target::Object(TextObject::Brackets('(', ')'), false)), None))));
+        assert!(matches!(parse("yaW", false), Parse::Done((Command::Operate(Operator::Yank, Target::Object(TextObject::Word(true), true)), None))));
+        assert!(matches!(parse("gg", false), Parse::Done((Command::Move(Motion::Top), None))));
+        assert!(matches!(parse("0", false), Parse::Done((Command::Move(Motion::LineStart), None))));
+        assert!(matches!(parse("10j", false), Parse::Done((Command::Move(Motion::Down), Some(10)))));
+        assert!(matches!(parse("2tx", false), Parse::Done((Command::Move(Motion::Find('x', true, true)), Some(2)))));
+        assert!(matches!(parse("rx", false), Parse::Done((Command::Replace('x'), None))));
+        assert!(matches!(parse("p", false), Parse::Done((Command::Key('p'), None))));
+        assert!(matches!(parse("D", false), Parse::Done((Command::Operate(Operator::Delete, Target::Motion(Motion::LineEnd)), None))));
+        assert!(matches!(parse("iw", true), Parse::Done((Command::Select(TextObject::Word(false), false), None))));
+        assert!(matches!(parse("d", true), Parse::Done((Command::Key('d'), None))));
+        for incomplete in ["", "3", "d", "2d", "di", "g", "f", "r"] {
+            assert!(matches!(parse(incomplete, false), Parse::Incomplete), "{}", incomplete);
+        };
+        for invalid in ["q", "gx", "dq", "diq"] {
+            assert!(matches!(parse(invalid, false), Parse::Invalid), "{}", invalid);
+        };
+    }
+
+    #[test]
+    fn word_motions() {
+        let text = lines("foo bar\n\nbaz");
+        let graphemes = classify(&text, false);
+        let position = |i: usize| graphemes[i].0;
+        assert_eq!(position(word_start(&graphemes, 0)), (0, 4));
+        // An empty line is a word of its own
+        assert_eq!(position(word_start(&graphemes, 4)), (1, 0));
+        assert_eq!(position(word_start(&graphemes, 8)), (2, 0));
+        // The last word goes to the end of the text
+        assert_eq!(position(word_start(&graphemes, 9)), (2, 3));
+        assert_eq!(position(word_back(&graphemes, 9)), (1, 0));
+        assert_eq!(position(word_back(&graphemes, 8)), (0, 4));
+        assert_eq!(position(word_back(&graphemes, 1)), (0, 0));
+        assert_eq!(position(word_end(&graphemes, 0)), (0, 2));
+        assert_eq!(position(word_end(&graphemes, 2)), (0, 6));
+        assert_eq!(position(word_end(&graphemes, 6)), (2, 2));
+    }
+
+    #[test]
+    fn word_motions_with_symbols_and_multi_byte_graphemes() {
+        let text = lines("a.b héllo wörld");
+        let graphemes = classify(&text, false);
+        let position = |i: usize| graphemes[i].0;
+        assert_eq!(position(word_start(&graphemes, 0)), (0, 1));
+        assert_eq!(position(word_start(&graphemes, 1)), (0, 2));
+        let hello = graphemes.iter().position(|g| g.0 == (0, 4)).unwrap();
+        assert_eq!(position(word_start(&graphemes, hello)), (0, 11));
+        assert_eq!(position(word_end(&graphemes, hello)), (0, 9));
+        assert_eq!(position(word_back(&graphemes, hello)), (0, 2));
+
+        let big = classify(&text, true);
+        assert_eq!(big[word_start(&big, 0)].0, (0, 4));
+        assert_eq!(big[word_end(&big, 0)].0, (0, 2));
+    }
+
+    #[test]
+    fn word_and_quote_objects() {
+        let text = lines("foo bar baz");
+        assert_eq!(object_range(&text, (0, 5), TextObject::Word(false), false), Some(((0, 4), (0, 7), false)));
+        assert_eq!(object_range(&text, (0, 5), TextObject::Word(false), true), Some(((0, 4), (0, 8), false)));
+        // Without blanks after the word, `aw` takes the blanks before it
+        assert_eq!(object_range(&text, (0, 9), TextObject::Word(false), true), Some(((0, 7), (0, 11), false)));
+
+        let text = lines("say \"hi there\" now");
+        assert_eq!(object_range(&text, (0, 6), TextObject::Quote('"'), false), Some(((0, 5), (0, 13), false)));
+        assert_eq!(object_range(&text, (0, 6), TextObject::Quote('"'), true), Some(((0, 4), (0, 15), false)));
+        assert_eq!(object_range(&lines("no quotes"), (0, 1), TextObject::Quote('"'), false), None);
+    }
+
+    #[test]
+    fn bracket_objects() {
+        let text = lines("f(a, (b), c)");
+        assert_eq!(brackets_object(&text, (0, 2), '(', ')', false), Some(((0, 2), (0, 11), false)));
+        assert_eq!(brackets_object(&text, (0, 2), '(', ')', true), Some(((0, 1), (0, 12), false)));
+        assert_eq!(brackets_object(&text, (0, 6), '(', ')', false), Some(((0, 6), (0, 7), false)));
+        // On the opening bracket, the object is the one it opens
+        assert_eq!(brackets_object(&text, (0, 5), '(', ')', true), Some(((0, 5), (0, 8), false)));
+        assert_eq!(brackets_object(&lines("abc"), (0, 1), '(', ')', false), None);
+
+        // The inner object of a block is made of the lines between the brackets
+        let text = lines("if x {\n    a\n    b\n}");
+        assert_eq!(brackets_object(&text, (1, 4), '{', '}', false), Some(((1, 0), (2, 5), true)));
+        assert_eq!(brackets_object(&text, (1, 4), '{', '}', true), Some(((0, 5), (3, 1), false)));
+    }
+
+    #[test]
+    fn delete_word_at_the_end_of_a_line() {
+        assert_eq!(type_keys("foo bar\nbaz", (0, 4), "dw"), (String::from("foo \nbaz"), (0, 3)));
+        assert_eq!(type_keys("foo bar", (0, 0), "dw"), (String::from("bar"), (0, 0)));
+    }
+
+    #[test]
+    fn change_word() {
+        // `cw` on a word works like `ce` and keeps the blank after it
+        assert_eq!(type_keys("foo bar", (0, 0), "cwxy\x1b"), (String::from("xy bar"), (0, 1)));
+        assert_eq!(type_keys("héllo wörld", (0, 7), "cwx\x1b"), (String::from("héllo x"), (0, 7)));
+    }
+
+    #[test]
+    fn delete_lines() {
+        assert_eq!(type_keys("a\nb\nc", (2, 0), "dd"), (String::from("a\nb"), (1, 0)));
+        assert_eq!(type_keys("a\nb\nc", (0, 0), "dj"), (String::from("c"), (0, 0)));
+        assert_eq!(type_keys("a\nb\nc", (1, 0), "5dd"), (String::from("a"), (0, 0)));
+    }
+
+    #[test]
+    fn huge_counts() {
+        let count = "99999999999999999999";
+        assert_eq!(type_keys("a\nb\nc", (1, 0), &format!("{}dd", count)), (String::from("a"), (0, 0)));
+        assert_eq!(type_keys("a\nb\nc", (1, 0), &format!("{}D", count)), (String::from("a\n"), (1, 0)));
+        assert_eq!(type_keys("a\nb\nc", (0, 0), &format!("{}j", count)).1, (2, 0));
+        assert_eq!(type_keys("ab cd", (0, 0), &format!("{}l", count)).1, (0, 4));
+        assert_eq!(type_keys("ab cd", (0, 0), &format!("{}w", count)).1, (0, 4));
+        assert_eq!(type_keys("ab", (0, 0), &format!("x{}u", count)).0, "ab");
+        let (text, _) = type_keys("a", (0, 0), &format!("yy{}p", count));
+        assert_eq!(text.lines().count(), MAX_PUT_COUNT + 1);
+    }
+
+    #[test]
+    fn join_lines() {
+        assert_eq!(type_keys("foo\n    bar", (0, 0), "J"), (String::from("foo bar"), (0, 3)));
+        assert_eq!(type_keys("a\nb\nc", (0, 0), "3J"), (String::from("a b c"), (0, 3)));
+    }
+
+    #[test]
+    fn put_lines() {
+        assert_eq!(type_keys("a\nb", (0, 0), "yyp"), (String::from("a\na\nb"), (1, 0)));
+        assert_eq!(type_keys("a\nb", (1, 0), "yyP"), (String::from("a\nb\nb"), (1, 0)));
+        assert_eq!(type_keys("a\nb", (1, 0), "ddP"), (String::from("b\na"), (0, 0)));
+        assert_eq!(type_keys("    a\nb", (1, 0), "kyyjp"), (String::from("    a\nb\n    a"), (2, 4)));
+    }
+}